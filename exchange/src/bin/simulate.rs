@@ -0,0 +1,261 @@
+//! 无界面快速模拟 按配置文件创建交易所 连续模拟若干个交易日后输出成交、日K线及账户统计
+//!
+//! 也可以记录命令日志 或重放已有的日志 比较不同版本的撮合结果
+
+use exchange::config_file::ConfigFile;
+use exchange::exchange::Exchange;
+use exchange::export::{self, ExportFormat, ExportWriter};
+use exchange::journal::{self, JournalWriter};
+use exchange::log::SpillWriter;
+use exchange::simulation::SimulationSummary;
+use std::error::Error;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const DEFAULT_CONFIG_PATH: &str = "stock_server/config/exchange.yaml";
+const DEFAULT_DAYS: u32 = 20;
+const DEFAULT_OUTPUT_DIR: &str = "simulation_output";
+
+const USAGE: &str = "Usage: simulate [--config <path>] [--days <n>] [--output <dir>] [--format <csv|jsonl>] [--seed <seed>] [--journal <path>]\n       simulate --replay <journal> [--output <dir>] [--format <csv|jsonl>]";
+
+struct SimulateArgs {
+    config_path: String,
+    days: u32,
+    output_dir: PathBuf,
+    format: ExportFormat,
+    seed: Option<u64>,
+    /** 记录命令日志 */
+    journal_path: Option<PathBuf>,
+    /** 重放命令日志 不再按配置文件模拟 */
+    replay_path: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<SimulateArgs, String> {
+    let mut args = std::env::args().skip(1);
+    let mut simulate_args = SimulateArgs {
+        config_path: DEFAULT_CONFIG_PATH.to_string(),
+        days: DEFAULT_DAYS,
+        output_dir: PathBuf::from(DEFAULT_OUTPUT_DIR),
+        format: ExportFormat::Csv,
+        seed: None,
+        journal_path: None,
+        replay_path: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "-c" | "--config" => simulate_args.config_path = value()?,
+            "-d" | "--days" => {
+                let days = value()?;
+                simulate_args.days = days
+                    .parse()
+                    .map_err(|_| format!("invalid number of days: {}", days))?;
+            }
+            "-o" | "--output" => simulate_args.output_dir = PathBuf::from(value()?),
+            "-f" | "--format" => simulate_args.format = value()?.parse()?,
+            "-s" | "--seed" => {
+                let seed = value()?;
+                simulate_args.seed = Some(
+                    seed.parse()
+                        .map_err(|_| format!("invalid seed: {}", seed))?,
+                );
+            }
+            "-j" | "--journal" => simulate_args.journal_path = Some(PathBuf::from(value()?)),
+            "-r" | "--replay" => simulate_args.replay_path = Some(PathBuf::from(value()?)),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                println!(
+                    "  -c, --config <path>  exchange config file (default: {})",
+                    DEFAULT_CONFIG_PATH
+                );
+                println!(
+                    "  -d, --days <n>       trading days to simulate (default: {})",
+                    DEFAULT_DAYS
+                );
+                println!(
+                    "  -o, --output <dir>   output directory (default: {})",
+                    DEFAULT_OUTPUT_DIR
+                );
+                println!("  -f, --format <fmt>   output format, csv or jsonl (default: csv)");
+                println!(
+                    "  -s, --seed <seed>    random seed, overrides exchange.seed in the config"
+                );
+                println!("  -j, --journal <path> record every command to a journal file");
+                println!(
+                    "  -r, --replay <path>  rebuild the exchange from a journal instead of simulating"
+                );
+                std::process::exit(0);
+            }
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    Ok(simulate_args)
+}
+
+fn create_export(
+    output_dir: &Path,
+    name: &str,
+    format: ExportFormat,
+) -> Result<ExportWriter<File>, Box<dyn Error>> {
+    let path = output_dir.join(format!("{}.{}", name, format.extension()));
+    Ok(ExportWriter::create(path, format)?)
+}
+
+/** 输出成交记录、日K线、对账单及账户统计 成交记录只包括内存中保留的部分 */
+fn write_results(
+    exchange: &Exchange,
+    output_dir: &Path,
+    format: ExportFormat,
+) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(output_dir)?;
+    let mut trades = create_export(output_dir, "trades", format)?;
+    exchange.export_trades(None, None, 0, usize::MAX, &mut trades)?;
+    trades.finish()?;
+
+    let mut bars = create_export(output_dir, "bars", format)?;
+    for bar in exchange.daily_bars() {
+        bars.write_row(&bar)?;
+    }
+    bars.finish()?;
+
+    let mut statements = create_export(output_dir, "statements", format)?;
+    exchange.export_statements(None, 0, usize::MAX, &mut statements)?;
+    statements.finish()?;
+
+    let mut accounts = create_export(output_dir, "accounts", format)?;
+    for ranking in exchange.get_pnl_ranking() {
+        accounts.write_row(&ranking)?;
+    }
+    accounts.finish()?;
+    Ok(())
+}
+
+/**
+ * 逐日模拟 每个交易日结束后将命令写入日志 移除的成交记录写入保留策略指定的文件
+ * 收盘清除的委托及成交明细逐日写入 orders 文件
+ */
+fn simulate(
+    exchange: &mut Exchange,
+    days: u32,
+    journal_path: Option<&Path>,
+    output_dir: &Path,
+    format: ExportFormat,
+) -> Result<SimulationSummary, Box<dyn Error>> {
+    std::fs::create_dir_all(output_dir)?;
+    exchange.enable_order_archive();
+    let mut orders = create_export(output_dir, "orders", format)?;
+    let mut writer = match journal_path {
+        Some(path) => {
+            exchange.enable_journal();
+            Some(JournalWriter::create(path, exchange)?)
+        }
+        None => None,
+    };
+    let mut spill = match exchange.config.trade_log_retention.spill_path.clone() {
+        Some(path) => {
+            exchange.enable_log_spill();
+            Some(SpillWriter::open(path)?)
+        }
+        None => None,
+    };
+    let mut summary = SimulationSummary {
+        start_day: exchange.current_trade_day(),
+        end_day: exchange.current_trade_day(),
+        trading_days: 0,
+        steps: 0,
+        trades: 0,
+    };
+    for day in 0..days {
+        let daily = exchange.run_trading_days(1)?;
+        if day == 0 {
+            summary.start_day = daily.start_day;
+        }
+        summary.end_day = daily.end_day;
+        summary.trading_days += daily.trading_days;
+        summary.steps += daily.steps;
+        summary.trades += daily.trades;
+        if let Some(writer) = writer.as_mut() {
+            writer.write(&exchange.drain_journal())?;
+        }
+        if let Some(spill) = spill.as_mut() {
+            spill.write(&exchange.drain_spilled_logs())?;
+        }
+        export::export_archived_orders(&exchange.drain_archived_orders(), &mut orders)?;
+    }
+    orders.finish()?;
+    Ok(summary)
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        std::process::exit(2);
+    });
+
+    let started = Instant::now();
+    let exchange = match &args.replay_path {
+        Some(replay_path) => {
+            let replay = journal::replay(replay_path).unwrap_or_else(|err| {
+                eprintln!("{}: {}", replay_path.display(), err);
+                std::process::exit(1);
+            });
+            println!(
+                "Replayed {} journal entries up to {} in {:.1?}",
+                replay.entries,
+                replay.exchange.get_current_timestamp(),
+                started.elapsed()
+            );
+            replay.exchange
+        }
+        None => {
+            let mut exchange = ConfigFile::load(&args.config_path)
+                .and_then(|mut config| {
+                    if args.seed.is_some() {
+                        config.exchange.seed = args.seed;
+                    }
+                    config.build_exchange()
+                })
+                .unwrap_or_else(|err| {
+                    eprintln!("{}: {}", args.config_path, err);
+                    std::process::exit(1);
+                });
+
+            println!("Random seed: {}", exchange.config.seed());
+            let summary = simulate(
+                &mut exchange,
+                args.days,
+                args.journal_path.as_deref(),
+                &args.output_dir,
+                args.format,
+            )
+            .unwrap_or_else(|err| {
+                eprintln!("simulation failed: {}", err);
+                std::process::exit(1);
+            });
+            println!(
+                "Simulated {} trading days ({} - {}), {} steps, {} trades in {:.1?}",
+                summary.trading_days,
+                summary.start_day,
+                summary.end_day,
+                summary.steps,
+                summary.trades,
+                started.elapsed()
+            );
+            exchange
+        }
+    };
+
+    if let Err(err) = write_results(&exchange, &args.output_dir, args.format) {
+        eprintln!(
+            "failed to write results to {}: {}",
+            args.output_dir.display(),
+            err
+        );
+        std::process::exit(1);
+    }
+    println!("Results written to {}", args.output_dir.display());
+}
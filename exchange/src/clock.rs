@@ -0,0 +1,233 @@
+use crate::exchange::Exchange;
+use crate::timestamp::{MILLIS_PER_DAY, MILLIS_PER_SECOND};
+use crate::types::*;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error;
+
+/** 默认速度 每 100 毫秒推进 1 秒 */
+pub const DEFAULT_SPEED: u32 = 10;
+pub const MAX_SPEED: u32 = 1000;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ClockError {
+    #[error("speed must be between 1 and {MAX_SPEED}, got {0}")]
+    InvalidSpeed(u32),
+
+    #[error("cannot jump backwards to {target}, clock is at {current}")]
+    TargetInPast {
+        target: Timestamp,
+        current: Timestamp,
+    },
+
+    #[error("{0} is not a trading day")]
+    NotTradingDay(NaiveDate),
+
+    #[error("steps must be between 1 and {max}, got {steps}")]
+    InvalidSteps { steps: u32, max: u32 },
+}
+
+/**
+ * 模拟时钟 控制交易所时间的推进
+ *
+ * 时钟按固定步长推进 闭市后直接跳到下一个交易时段 当日最后一个时段结束后
+ * 按交易日历切换到下一交易日零点 因此可以连续运行多个交易日
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimulationClock {
+    paused: bool,
+    /** 速度倍数 模拟时间与真实时间之比 */
+    speed: u32,
+    /** 每步推进的模拟时间 */
+    step: TimeOfDay,
+    /** 尚未推进的模拟时间 毫秒 */
+    pending: u64,
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulationClock {
+    pub fn new() -> Self {
+        SimulationClock {
+            paused: false,
+            speed: DEFAULT_SPEED,
+            step: MILLIS_PER_SECOND,
+            pending: 0,
+        }
+    }
+
+    /** 指定每步推进的模拟时间 用于重放日志 */
+    pub fn with_step(step: TimeOfDay) -> Self {
+        SimulationClock {
+            step,
+            ..Self::new()
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: u32) -> Result<(), ClockError> {
+        if speed == 0 || speed > MAX_SPEED {
+            return Err(ClockError::InvalidSpeed(speed));
+        }
+        self.speed = speed;
+        Ok(())
+    }
+
+    /** 每步推进的模拟时间 */
+    pub fn step_size(&self) -> TimeOfDay {
+        self.step
+    }
+
+    /** 单次手动推进的最大步数 一天的模拟时间 */
+    pub fn max_steps(&self) -> u32 {
+        MILLIS_PER_DAY / self.step.max(1)
+    }
+
+    /** 检查手动推进的步数 */
+    pub fn check_steps(&self, steps: u32) -> Result<(), ClockError> {
+        let max = self.max_steps();
+        if steps == 0 || steps > max {
+            return Err(ClockError::InvalidSteps { steps, max });
+        }
+        Ok(())
+    }
+
+    /** 经过一段真实时间 返回应推进的步数 暂停时不计时 */
+    pub fn elapse(&mut self, real: Duration) -> u32 {
+        if self.paused {
+            return 0;
+        }
+        self.pending += real.as_millis() as u64 * self.speed as u64;
+        let steps = self.pending / self.step as u64;
+        self.pending %= self.step as u64;
+        steps as u32
+    }
+
+    /** 推进一步 返回推进后的时间 */
+    pub fn step(&self, exchange: &mut Exchange) -> Timestamp {
+        let current = exchange.get_current_timestamp();
+        let next_time = current.time() + self.step;
+        // 闭市时跳到下一个时段开始
+        let next_start = match exchange.config.get_current_period() {
+            Some(_) => None,
+            None => exchange
+                .config
+                .get_next_period()
+                .map(|period| period.start_tick),
+        };
+
+        if !exchange.trade_day_manager.is_trading_day(current.date()) {
+            // 非交易日直接切换到下一交易日
+            exchange.next_trade_day();
+        } else if let Some(start_tick) = next_start {
+            exchange.advance_to(current.with_time(start_tick));
+        } else if exchange.config.get_current_period().is_none() || next_time >= MILLIS_PER_DAY {
+            // 当日交易结束 全天交易的场所在午夜切换
+            exchange.next_trade_day();
+        } else {
+            exchange.advance_to(current.with_time(next_time));
+        }
+        exchange.get_current_timestamp()
+    }
+
+    /**
+     * 跳转到指定时间 途经的每个交易时段切换都会触发
+     *
+     * 跨日时按交易日历逐日切换 跳转过程中不执行机器人策略
+     */
+    pub fn jump_to(&self, exchange: &mut Exchange, target: Timestamp) -> Result<(), ClockError> {
+        self.check_jump(exchange, target)?;
+        while !self.jump_day(exchange, target)? {}
+        Ok(())
+    }
+
+    /** 检查跳转的目标时间 */
+    pub fn check_jump(&self, exchange: &Exchange, target: Timestamp) -> Result<(), ClockError> {
+        let current = exchange.get_current_timestamp();
+        if target <= current {
+            return Err(ClockError::TargetInPast { target, current });
+        }
+        if !exchange.trade_day_manager.is_trading_day(target.date()) {
+            return Err(ClockError::NotTradingDay(target.date()));
+        }
+        Ok(())
+    }
+
+    /**
+     * 向目标时间跳转至多一个交易日 到达目标时间时返回 true
+     *
+     * 供调用方分批跳转 每批之间可释放交易所锁 目标时间须先经 check_jump 检查
+     */
+    pub fn jump_day(&self, exchange: &mut Exchange, target: Timestamp) -> Result<bool, ClockError> {
+        // 分批之间时钟可能已被推进
+        let current = exchange.get_current_timestamp();
+        if target < current {
+            return Err(ClockError::TargetInPast { target, current });
+        }
+        if exchange.current_trade_day() < target.date() {
+            if exchange
+                .trade_day_manager
+                .is_trading_day(exchange.current_trade_day())
+            {
+                for time in self.transitions(exchange, MILLIS_PER_DAY) {
+                    let timestamp = exchange.get_current_timestamp().with_time(time);
+                    exchange.advance_to(timestamp);
+                }
+            }
+            exchange.next_trade_day();
+            return Ok(false);
+        }
+        for time in self.transitions(exchange, target.time()) {
+            exchange.advance_to(target.with_time(time));
+        }
+        exchange.advance_to(target);
+        Ok(true)
+    }
+
+    /**
+     * 当前时间之后 截止时间之前的时段切换时刻
+     *
+     * 包括各时段开始 紧接下一时段的时段结束 (如午间休市与下午交易首尾重叠) 及当日收市
+     */
+    fn transitions(&self, exchange: &Exchange, until: TimeOfDay) -> Vec<TimeOfDay> {
+        let now = exchange.get_current_timestamp().time();
+        let periods = &exchange.config.trading_periods;
+        let in_session = |time: &TimeOfDay| periods.iter().any(|period| period.contains(*time));
+        let close = periods.iter().map(|period| period.end_exclusive()).max();
+        let mut times: Vec<TimeOfDay> = periods
+            .iter()
+            .map(|period| period.start_tick)
+            .chain(
+                periods
+                    .iter()
+                    .map(|period| period.end_exclusive())
+                    .filter(in_session),
+            )
+            .chain(close)
+            .filter(|time| *time > now && *time < until && *time < MILLIS_PER_DAY)
+            .collect();
+        times.sort_unstable();
+        times.dedup();
+        times
+    }
+}
@@ -1,119 +1,144 @@
-use crate::types::{string_to_timestamp, timestamp_to_string, Timestamp};
-use serde::Serialize;
+use crate::fee::FeeSchedule;
+use crate::instrument::Board;
+use crate::instrument::InstrumentRulesTable;
+use crate::log::LogRetention;
+use crate::random::{derive_rng, SimRng, EXCHANGE_STREAM};
+use crate::timestamp::{time_of_day_serde, MILLIS_PER_SECOND};
+use crate::types::{string_to_time_of_day, time_of_day_to_string, TimeOfDay, Timestamp};
+use crate::venue::Venue;
+use chrono::NaiveDate;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ExchangeConfig {
     /** 交易所名称 */
     pub name: String,
-    /** 当前时间戳 */
+    /** 交易场所 */
+    pub venue: Venue,
+    /** 当前时间戳 日期即当前交易日 */
     pub current_timestamp: Timestamp,
     pub trading_periods: Vec<TradingPeriod>,
-    pub price_limit_percentage: f64,
+    /** 涨跌幅限制 None 表示不设涨跌幅限制 */
+    pub price_limit_percentage: Option<f64>,
+    /** 按板块设置的涨跌幅限制 未设置的板块使用 price_limit_percentage */
+    pub board_price_limits: HashMap<Board, f64>,
     pub ticks_per_trading_day: u32,
+    /** 交收周期 */
+    pub settlement_cycle: SettlementCycle,
+    /** 周末是否交易 */
+    pub trade_on_weekends: bool,
+    /** 持仓成本计算方式 */
+    pub cost_basis_method: CostBasisMethod,
+    /** 交易费用 */
+    pub fee_schedule: FeeSchedule,
+    /** 各板块交易规则 */
+    pub instrument_rules: InstrumentRulesTable,
+    /** 成交记录保留策略 */
+    #[serde(default)]
+    pub trade_log_retention: LogRetention,
+    /** 随机数主种子 相同种子的模拟结果相同 通过 set_seed 修改 */
+    seed: u64,
 
     current_period: Option<TradingPeriod>,
     next_period: Option<TradingPeriod>,
+    /** 当前时段的随机收市时间 */
+    random_close_tick: Option<TimeOfDay>,
+    rng: SimRng,
+}
+
+impl Default for ExchangeConfig {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ExchangeConfig {
+    /** A股 沪深交易所 */
     pub fn new() -> Self {
+        Self::for_venue(Venue::AShare)
+    }
+
+    /** 按交易场所模板创建配置 随机数种子随机生成 */
+    pub fn for_venue(venue: Venue) -> Self {
+        let seed = rand::random();
         ExchangeConfig {
-            name: "模拟交易所".to_string(),
-            current_timestamp: 0,
-            trading_periods: vec![
-                TradingPeriod {
-                    name: "集合竞价 可以撤单".to_string(),
-                    start_tick: string_to_timestamp("9:15:00").unwrap(), // 9:15:00
-                    end_tick: string_to_timestamp("9:19:59").unwrap(),   // 9:19:59
-                    period_type: TradingPeriodType::CallAuctionWithCancel,
-                    allow_order: true,
-                    allow_cancel: true,
-                    allow_matching: false,
-                    allow_record_price_history: false,
-                },
-                TradingPeriod {
-                    name: "集合竞价 不可以撤单".to_string(),
-                    start_tick: string_to_timestamp("9:20:00").unwrap(), // 9:20:00
-                    end_tick: string_to_timestamp("9:24:59").unwrap(),   // 9:24:59
-                    period_type: TradingPeriodType::CallAuctionNoCancel,
-                    allow_order: true,
-                    allow_cancel: false,
-                    allow_matching: false,
-                    allow_record_price_history: false,
-                },
-                TradingPeriod {
-                    name: "开盘集合竞价".to_string(),
-                    start_tick: string_to_timestamp("9:25:00").unwrap(), // 9:25:00
-                    end_tick: string_to_timestamp("9:29:59").unwrap(),   // 9:29:59
-                    period_type: TradingPeriodType::OpeningAuction,
-                    allow_order: true,
-                    allow_cancel: false,
-                    allow_matching: true,
-                    allow_record_price_history: false,
-                },
-                TradingPeriod {
-                    name: "连续竞价交易".to_string(),
-                    start_tick: string_to_timestamp("9:30:00").unwrap(), // 9:30:00
-                    end_tick: string_to_timestamp("11:29:59").unwrap(),  // 11:29:59
-                    period_type: TradingPeriodType::ContinuousTrading,
-                    allow_order: true,
-                    allow_cancel: true,
-                    allow_matching: true,
-                    allow_record_price_history: true,
-                },
-                TradingPeriod {
-                    name: "午间休市".to_string(),
-                    start_tick: string_to_timestamp("11:30:00").unwrap(), // 11:30:00
-                    end_tick: string_to_timestamp("13:00:00").unwrap(),   // 13:00:00
-                    period_type: TradingPeriodType::MiddayBreak,
-                    allow_order: false,
-                    allow_cancel: false,
-                    allow_matching: false,
-                    allow_record_price_history: false,
-                },
-                TradingPeriod {
-                    name: "下午连续竞价交易".to_string(),
-                    start_tick: string_to_timestamp("13:00:00").unwrap(), // 13:00:00
-                    end_tick: string_to_timestamp("14:56:59").unwrap(),   // 14:57:59
-                    period_type: TradingPeriodType::ContinuousTrading,
-                    allow_order: true,
-                    allow_cancel: true,
-                    allow_matching: true,
-                    allow_record_price_history: true,
-                },
-                TradingPeriod {
-                    name: "收盘集合竞价".to_string(),
-                    start_tick: string_to_timestamp("14:57:00").unwrap(), // 14:57:00
-                    end_tick: string_to_timestamp("15:00:00").unwrap(),   // 15:00:00
-                    period_type: TradingPeriodType::ClosingAuction,
-                    allow_order: true,
-                    allow_cancel: false,
-                    allow_matching: true,
-                    allow_record_price_history: true,
-                },
-            ],
-            price_limit_percentage: 0.10,
+            name: venue.display_name().to_string(),
+            venue,
+            current_timestamp: Timestamp::start_of_day(
+                NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            ),
+            trading_periods: venue.trading_periods(),
+            price_limit_percentage: venue.price_limit_percentage(),
+            board_price_limits: HashMap::new(),
             ticks_per_trading_day: 28800,
+            settlement_cycle: venue.settlement_cycle(),
+            trade_on_weekends: venue.trades_on_weekends(),
+            cost_basis_method: CostBasisMethod::AverageCost,
+            fee_schedule: venue.fee_schedule(),
+            instrument_rules: venue.instrument_rules(),
+            trade_log_retention: LogRetention::default(),
+            seed,
             current_period: None,
             next_period: None,
+            random_close_tick: None,
+            rng: derive_rng(seed, EXCHANGE_STREAM),
         }
     }
 
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /** 设置随机数主种子 */
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = derive_rng(seed, EXCHANGE_STREAM);
+    }
+
     pub fn set_current_timestamp(&mut self, timestamp: Timestamp) {
+        let previous = self.current_timestamp;
+        let previous_start = self.current_period.as_ref().map(|period| period.start_tick);
         self.current_timestamp = timestamp;
+        // 交易时段按日内时间匹配
+        let time = timestamp.time();
         self.current_period = self
             .trading_periods
             .iter()
-            .find(|period| {
-                self.current_timestamp >= period.start_tick
-                    && self.current_timestamp <= period.end_tick
-            })
+            .find(|period| period.contains(time))
             .cloned();
         self.next_period = self
             .trading_periods
             .iter()
-            .find(|period| self.current_timestamp < period.start_tick)
+            .find(|period| time < period.start_tick)
             .cloned();
+
+        // 进入新时段时确定随机收市时间
+        let current_start = self.current_period.as_ref().map(|period| period.start_tick);
+        if current_start != previous_start || timestamp.date() != previous.date() {
+            self.random_close_tick = self
+                .current_period
+                .as_ref()
+                .filter(|period| period.random_close_window > 0)
+                .map(|period| {
+                    (
+                        period.end_tick,
+                        period
+                            .random_close_window
+                            .min(period.end_tick - period.start_tick),
+                    )
+                })
+                .map(|(end_tick, window)| end_tick - self.rng.gen_range(0..=window));
+        }
+    }
+
+    /** 板块的涨跌幅限制 */
+    pub fn price_limit_percentage_for(&self, board: Board) -> Option<f64> {
+        self.board_price_limits
+            .get(&board)
+            .copied()
+            .or(self.price_limit_percentage)
     }
 
     pub fn get_current_period(&self) -> Option<&TradingPeriod> {
@@ -124,49 +149,119 @@ impl ExchangeConfig {
         self.next_period.as_ref()
     }
 
+    /** 当前时段的随机收市时间 */
+    pub fn get_random_close_tick(&self) -> Option<TimeOfDay> {
+        self.random_close_tick
+    }
+
+    /** 是否已随机收市 收市后不再接受申报及撤单 */
+    pub fn is_randomly_closed(&self) -> bool {
+        self.random_close_tick
+            .is_some_and(|tick| self.current_timestamp.time() > tick)
+    }
+
     // 判断当前tick是否可以下单
     pub fn is_allow_order(&self) -> bool {
-        self.get_current_period()
-            .map_or(false, |period| period.allow_order)
+        !self.is_randomly_closed()
+            && self
+                .get_current_period()
+                .is_some_and(|period| period.allow_order)
     }
 
     // 判断当前tick是否可以撤单
     pub fn is_allow_cancel(&self) -> bool {
-        self.get_current_period()
-            .map_or(false, |period| period.allow_cancel)
+        !self.is_randomly_closed()
+            && self
+                .get_current_period()
+                .is_some_and(|period| period.allow_cancel)
     }
 
     // 判断当前tick是否可以撮合
     pub fn is_allow_matching(&self) -> bool {
         self.get_current_period()
-            .map_or(false, |period| period.allow_matching)
+            .is_some_and(|period| period.allow_matching)
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TradingPeriod {
     /** 交易时段名称 */
     pub name: String,
-    pub start_tick: Timestamp,
-    pub end_tick: Timestamp,
+    #[serde(with = "time_of_day_serde")]
+    pub start_tick: TimeOfDay,
+    #[serde(with = "time_of_day_serde")]
+    pub end_tick: TimeOfDay,
     pub period_type: TradingPeriodType,
     pub allow_order: bool,
     pub allow_cancel: bool,
     pub allow_matching: bool,
     /** 是否记录价格历史 */
     pub allow_record_price_history: bool,
+    /** 随机收市窗口 大于0时在时段结束前的该时长内随机收市 */
+    #[serde(default, with = "time_of_day_serde")]
+    pub random_close_window: TimeOfDay,
+}
+
+impl TradingPeriod {
+    /** 按时段类型的默认权限创建交易时段 时间格式为 "09:30:00" */
+    pub fn new(name: &str, start: &str, end: &str, period_type: TradingPeriodType) -> Self {
+        let (allow_order, allow_cancel, allow_matching, allow_record_price_history) =
+            match period_type {
+                TradingPeriodType::CallAuctionWithCancel => (true, true, false, false),
+                TradingPeriodType::CallAuctionNoCancel => (true, false, false, false),
+                TradingPeriodType::OpeningAuction => (true, false, true, false),
+                TradingPeriodType::ContinuousTrading
+                | TradingPeriodType::PreMarket
+                | TradingPeriodType::AfterHours => (true, true, true, true),
+                TradingPeriodType::ClosingAuction => (true, false, true, true),
+                TradingPeriodType::MarketClosed
+                | TradingPeriodType::MiddayBreak
+                | TradingPeriodType::Blocking => (false, false, false, false),
+            };
+        TradingPeriod {
+            name: name.to_string(),
+            start_tick: string_to_time_of_day(start).unwrap(),
+            end_tick: string_to_time_of_day(end).unwrap(),
+            period_type,
+            allow_order,
+            allow_cancel,
+            allow_matching,
+            allow_record_price_history,
+            random_close_window: 0,
+        }
+    }
+
+    /**
+     * 时段结束的时刻 不含该时刻
+     *
+     * 结束时间为整秒时 "11:29:59" 包含该秒内的每一毫秒 即时段在 11:30:00 结束
+     * 带毫秒的结束时间 "11:29:59.500" 只包含该毫秒
+     */
+    pub fn end_exclusive(&self) -> TimeOfDay {
+        if self.end_tick.is_multiple_of(MILLIS_PER_SECOND) {
+            self.end_tick + MILLIS_PER_SECOND
+        } else {
+            self.end_tick + 1
+        }
+    }
+
+    /** 日内时间是否在该时段内 */
+    pub fn contains(&self, time: TimeOfDay) -> bool {
+        self.start_tick <= time && time < self.end_exclusive()
+    }
 }
 
-impl ToString for TradingPeriod {
-    fn to_string(&self) -> String {
-        format!(
+impl std::fmt::Display for TradingPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
             "{} - {}",
-            timestamp_to_string(self.start_tick),
-            timestamp_to_string(self.end_tick)
+            time_of_day_to_string(self.start_tick),
+            time_of_day_to_string(self.end_tick)
         )
     }
 }
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TradingPeriodType {
     /** 集合竞价阶段 交易所接受申报，可以撤单，但不进行撮合 */
     CallAuctionWithCancel,
@@ -176,10 +271,36 @@ pub enum TradingPeriodType {
     OpeningAuction,
     /** 连续竞价交易 可下单 可撤单 实时撮合 */
     ContinuousTrading,
+    /** 盘前交易 按连续竞价撮合 */
+    PreMarket,
+    /** 盘后交易 按连续竞价撮合 */
+    AfterHours,
     /** 收盘集合竞价 确定收盘价 */
     ClosingAuction,
+    /** 暂停时段 不接受申报也不撮合 */
+    Blocking,
     /** 闭市 */
     MarketClosed,
     /** 午间休市 */
     MiddayBreak,
 }
+
+/** 持仓成本计算方式 卖出时按此方式结转成本并计算已实现盈亏 */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum CostBasisMethod {
+    /** 移动加权平均成本 */
+    #[default]
+    AverageCost,
+    /** 先进先出 按买入批次结转成本 */
+    Fifo,
+}
+
+/** 交收周期 */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SettlementCycle {
+    /** 当日买入当日可卖 */
+    T0,
+    /** 当日买入下一交易日可卖 */
+    #[default]
+    T1,
+}
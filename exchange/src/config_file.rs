@@ -0,0 +1,779 @@
+use crate::config::{CostBasisMethod, ExchangeConfig, SettlementCycle, TradingPeriod};
+use crate::exchange::Exchange;
+use crate::exchange_error::ExchangeError;
+use crate::fee::FeeSchedule;
+use crate::fundamental::FundamentalParams;
+use crate::instrument::{Board, InstrumentRulesTable};
+use crate::log::LogRetention;
+use crate::market_data::{MarketData, MarketDataError};
+use crate::timestamp::time_of_day_to_string;
+use crate::trading_strategy::{
+    InformedParams, MarketMakerParams, MeanReversionParams, NoiseParams, ReversionSignal,
+    TradingStrategy, TrendFollowingParams, TrendSignal,
+};
+use crate::types::*;
+use crate::venue::Venue;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("failed to parse config: {0}")]
+    Parse(#[from] serde_yaml::Error),
+
+    #[error("invalid config:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+
+    #[error("failed to set up exchange: {0}")]
+    Setup(#[from] ExchangeError),
+
+    #[error(transparent)]
+    MarketData(#[from] MarketDataError),
+}
+
+/**
+ * 配置文件 交易所参数及初始的股票、用户和机器人
+ *
+ * 交易所参数均可省略 省略时使用所选交易场所模板的默认值
+ */
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub exchange: ExchangeSection,
+    #[serde(default)]
+    pub stocks: Vec<StockSpec>,
+    #[serde(default)]
+    pub users: Vec<UserSpec>,
+    #[serde(default)]
+    pub bots: Vec<BotSpec>,
+    /** 历史行情 */
+    pub market_data: Option<MarketDataSpec>,
+    /** 基本面价值过程 */
+    pub fundamental: Option<FundamentalSpec>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExchangeSection {
+    /** 交易场所模板 默认为A股 */
+    pub venue: Option<Venue>,
+    pub name: Option<String>,
+    /** 起始交易日 */
+    pub start_date: Option<NaiveDate>,
+    pub trading_periods: Option<Vec<TradingPeriod>>,
+    pub price_limit: Option<PriceLimitSpec>,
+    pub settlement_cycle: Option<SettlementCycle>,
+    pub trade_on_weekends: Option<bool>,
+    pub cost_basis_method: Option<CostBasisMethod>,
+    pub fee_schedule: Option<FeeSchedule>,
+    pub instrument_rules: Option<InstrumentRulesTable>,
+    /** 成交记录保留策略 省略时全部保留在内存中 */
+    pub trade_log: Option<LogRetention>,
+    /** 随机数主种子 省略时随机生成 */
+    pub seed: Option<u64>,
+}
+
+/** 涨跌幅限制 按板块覆盖默认比例 percentage 省略时不设限制 */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PriceLimitSpec {
+    #[serde(default)]
+    pub percentage: Option<f64>,
+    #[serde(default)]
+    pub boards: HashMap<Board, f64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StockSpec {
+    pub code: StockCode,
+    pub name: String,
+    /** 昨日收盘价 */
+    pub price: Price,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserSpec {
+    pub name: String,
+    pub balance: Money,
+    pub fee_tier: Option<String>,
+    /** 登录密码的 argon2 哈希 未设置时账号只能由程序内部使用 不能登录 */
+    pub password_hash: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BotSpec {
+    pub name: String,
+    pub balance: Money,
+    pub strategy: StrategySpec,
+    /** 初始持仓 股票代码 -> 数量 */
+    #[serde(default)]
+    pub holdings: BTreeMap<StockCode, u64>,
+}
+
+/**
+ * 历史行情 CSV 文件或目录
+ *
+ * 起始交易日之前的K线作为股票的价格历史及昨收价 未在 stocks 中列出的股票以代码为名称添加
+ * replay 为 true 时由回放代理按起始交易日起的K线报价 需要指定 exchange.start_date
+ */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarketDataSpec {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub replay: bool,
+}
+
+/**
+ * 基本面价值过程 几何布朗运动叠加跳跃 参数以交易日计
+ *
+ * 初始价值为昨收价 stocks 省略时为全部股票
+ */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FundamentalSpec {
+    #[serde(default)]
+    pub drift: f64,
+    pub volatility: f64,
+    #[serde(default)]
+    pub jump_intensity: f64,
+    #[serde(default)]
+    pub jump_mean: f64,
+    #[serde(default)]
+    pub jump_volatility: f64,
+    pub stocks: Option<Vec<StockCode>>,
+}
+
+impl From<&FundamentalSpec> for FundamentalParams {
+    fn from(spec: &FundamentalSpec) -> Self {
+        FundamentalParams {
+            drift: spec.drift,
+            volatility: spec.volatility,
+            jump_intensity: spec.jump_intensity,
+            jump_mean: spec.jump_mean,
+            jump_volatility: spec.jump_volatility,
+        }
+    }
+}
+
+/** 机器人策略及参数 */
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum StrategySpec {
+    SimpleRandom,
+    TradeRandom {
+        param: u8,
+    },
+    /** 做市 skew_ticks 默认等于 spread_ticks requote_ticks 默认为1 */
+    MarketMaker {
+        spread_ticks: u32,
+        quote_size: Quantity,
+        target_position: u64,
+        max_position: u64,
+        skew_ticks: Option<u32>,
+        requote_ticks: Option<u32>,
+    },
+    /** 趋势跟踪 fast_lookback 默认为 lookback 的四分之一 threshold 默认为0 */
+    TrendFollowing {
+        signal: TrendSignal,
+        lookback: usize,
+        fast_lookback: Option<usize>,
+        threshold: Option<f64>,
+        order_size: Quantity,
+        max_position: u64,
+    },
+    /** 均值回归 threshold 默认 VWAP 为0.01 布林带为2倍标准差 */
+    MeanReversion {
+        signal: ReversionSignal,
+        lookback: usize,
+        threshold: Option<f64>,
+        order_size: Quantity,
+        max_position: u64,
+    },
+    /** 知情交易 需要配置 fundamental threshold 默认为0.005 */
+    Informed {
+        threshold: Option<f64>,
+        order_size: Quantity,
+        max_position: u64,
+    },
+    /** 噪声交易 noise 默认为0.01 probability 默认为0.2 */
+    Noise {
+        noise: Option<f64>,
+        order_size: Quantity,
+        probability: Option<f64>,
+    },
+}
+
+impl From<&StrategySpec> for TradingStrategy {
+    fn from(spec: &StrategySpec) -> Self {
+        match spec {
+            StrategySpec::SimpleRandom => TradingStrategy::SimpleRandom,
+            StrategySpec::TradeRandom { param } => TradingStrategy::TradeRandom(*param),
+            StrategySpec::MarketMaker {
+                spread_ticks,
+                quote_size,
+                target_position,
+                max_position,
+                skew_ticks,
+                requote_ticks,
+            } => TradingStrategy::MarketMaker(MarketMakerParams {
+                spread_ticks: *spread_ticks,
+                quote_size: *quote_size,
+                target_position: *target_position,
+                max_position: *max_position,
+                skew_ticks: skew_ticks.unwrap_or(*spread_ticks),
+                requote_ticks: requote_ticks.unwrap_or(1),
+            }),
+            StrategySpec::TrendFollowing {
+                signal,
+                lookback,
+                fast_lookback,
+                threshold,
+                order_size,
+                max_position,
+            } => TradingStrategy::TrendFollowing(TrendFollowingParams {
+                signal: *signal,
+                lookback: *lookback,
+                fast_lookback: fast_lookback.unwrap_or((lookback / 4).max(1)),
+                threshold: threshold.unwrap_or(0.0),
+                order_size: *order_size,
+                max_position: *max_position,
+            }),
+            StrategySpec::MeanReversion {
+                signal,
+                lookback,
+                threshold,
+                order_size,
+                max_position,
+            } => TradingStrategy::MeanReversion(MeanReversionParams {
+                signal: *signal,
+                lookback: *lookback,
+                threshold: threshold.unwrap_or(match signal {
+                    ReversionSignal::Vwap => 0.01,
+                    ReversionSignal::Bollinger => 2.0,
+                }),
+                order_size: *order_size,
+                max_position: *max_position,
+            }),
+            StrategySpec::Informed {
+                threshold,
+                order_size,
+                max_position,
+            } => TradingStrategy::Informed(InformedParams {
+                threshold: threshold.unwrap_or(0.005),
+                order_size: *order_size,
+                max_position: *max_position,
+            }),
+            StrategySpec::Noise {
+                noise,
+                order_size,
+                probability,
+            } => TradingStrategy::Noise(NoiseParams {
+                noise: noise.unwrap_or(0.01),
+                order_size: *order_size,
+                probability: probability.unwrap_or(0.2),
+            }),
+        }
+    }
+}
+
+impl ConfigFile {
+    /** 读取并校验配置文件 */
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Self::from_yaml(&contents)
+    }
+
+    /** 解析并校验 YAML 配置 */
+    pub fn from_yaml(contents: &str) -> Result<Self, ConfigError> {
+        let config: ConfigFile = serde_yaml::from_str(contents)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /** 校验配置 一次返回所有错误 */
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        let exchange_config = self.exchange_config();
+
+        if exchange_config.name.trim().is_empty() {
+            errors.push("exchange.name must not be empty".to_string());
+        }
+        self.validate_trading_periods(&exchange_config.trading_periods, &mut errors);
+        self.validate_price_limits(&mut errors);
+        self.validate_instrument_rules(&mut errors);
+        self.validate_fee_schedule(&mut errors);
+
+        let mut stock_codes = HashSet::new();
+        for (index, stock) in self.stocks.iter().enumerate() {
+            let context = format!("stocks[{}] ({})", index, stock.code);
+            if exchange_config.venue == Venue::AShare {
+                if stock.code.len() != 6 || !stock.code.chars().all(|c| c.is_ascii_digit()) {
+                    errors.push(format!("{}: code must be 6 digits", context));
+                }
+            } else if stock.code.is_empty()
+                || !stock
+                    .code
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+            {
+                errors.push(format!(
+                    "{}: code must be letters, digits, '.' or '-'",
+                    context
+                ));
+            }
+            if !stock_codes.insert(stock.code.as_str()) {
+                errors.push(format!("{}: duplicate stock code", context));
+            }
+            if stock.name.trim().is_empty() {
+                errors.push(format!("{}: name must not be empty", context));
+            }
+            if stock.price.is_zero() {
+                errors.push(format!("{}: price must be positive", context));
+            }
+            if let Some(rules) = exchange_config
+                .instrument_rules
+                .get(Board::from_code(&stock.code))
+            {
+                if rules.align_price(stock.price) != stock.price {
+                    errors.push(format!(
+                        "{}: price {} is not a multiple of tick size {}",
+                        context, stock.price, rules.tick_size
+                    ));
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        for (index, user) in self.users.iter().enumerate() {
+            let context = format!("users[{}] ({})", index, user.name);
+            self.validate_account(&context, &user.name, user.balance, &mut names, &mut errors);
+            if let Some(tier) = &user.fee_tier {
+                if !exchange_config.fee_schedule.tiers.contains_key(tier) {
+                    errors.push(format!("{}: unknown fee tier {}", context, tier));
+                }
+            }
+            if let Some(password_hash) = &user.password_hash {
+                if !password_hash.starts_with('$') {
+                    errors.push(format!(
+                        "{}: password_hash must be a PHC string such as $argon2id$...",
+                        context
+                    ));
+                }
+            }
+        }
+        for (index, bot) in self.bots.iter().enumerate() {
+            let context = format!("bots[{}] ({})", index, bot.name);
+            self.validate_account(&context, &bot.name, bot.balance, &mut names, &mut errors);
+            for (stock_code, quantity) in &bot.holdings {
+                if *quantity == 0 {
+                    errors.push(format!(
+                        "{}: holding of {} must be positive",
+                        context, stock_code
+                    ));
+                }
+            }
+            self.validate_strategy(&context, &bot.strategy, &mut errors);
+        }
+
+        if let Some(market_data) = &self.market_data {
+            if market_data.replay && self.exchange.start_date.is_none() {
+                errors.push("market_data.replay requires exchange.start_date".to_string());
+            }
+        }
+        if let Some(fundamental) = &self.fundamental {
+            self.validate_fundamental(fundamental, &mut errors);
+        }
+        // 导入历史行情时可能添加股票 创建交易所时再检查
+        if self.market_data.is_none() {
+            errors.extend(self.unknown_stocks(|stock_code| stock_codes.contains(stock_code)));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    fn validate_trading_periods(&self, periods: &[TradingPeriod], errors: &mut Vec<String>) {
+        if periods.is_empty() {
+            errors.push("exchange.trading_periods must not be empty".to_string());
+        }
+        for (index, period) in periods.iter().enumerate() {
+            let context = format!("exchange.trading_periods[{}] ({})", index, period.name);
+            if period.name.trim().is_empty() {
+                errors.push(format!("{}: name must not be empty", context));
+            }
+            if period.start_tick > period.end_tick {
+                errors.push(format!(
+                    "{}: start {} is after end {}",
+                    context,
+                    time_of_day_to_string(period.start_tick),
+                    time_of_day_to_string(period.end_tick)
+                ));
+            }
+            if let Some(previous) = index.checked_sub(1).map(|i| &periods[i]) {
+                // 相邻时段允许首尾相接 时段需按时间顺序排列
+                if period.start_tick < previous.end_tick {
+                    errors.push(format!(
+                        "{}: starts at {} before previous period {} ends at {}",
+                        context,
+                        time_of_day_to_string(period.start_tick),
+                        previous.name,
+                        time_of_day_to_string(previous.end_tick)
+                    ));
+                }
+            }
+        }
+    }
+
+    fn validate_price_limits(&self, errors: &mut Vec<String>) {
+        let Some(price_limit) = &self.exchange.price_limit else {
+            return;
+        };
+        let valid = |percentage: f64| percentage > 0.0 && percentage < 1.0;
+        if let Some(percentage) = price_limit.percentage {
+            if !valid(percentage) {
+                errors.push(format!(
+                    "exchange.price_limit.percentage: {} must be between 0 and 1",
+                    percentage
+                ));
+            }
+        }
+        for (board, percentage) in &price_limit.boards {
+            if !valid(*percentage) {
+                errors.push(format!(
+                    "exchange.price_limit.boards.{:?}: {} must be between 0 and 1",
+                    board, percentage
+                ));
+            }
+        }
+    }
+
+    fn validate_instrument_rules(&self, errors: &mut Vec<String>) {
+        let Some(table) = &self.exchange.instrument_rules else {
+            return;
+        };
+        let mut boards: Vec<_> = table.boards.iter().collect();
+        boards.sort_by_key(|(board, _)| format!("{:?}", board));
+        for (board, rules) in boards {
+            let context = format!("exchange.instrument_rules.boards.{:?}", board);
+            for (field, value) in [
+                ("min_quantity", rules.min_quantity),
+                ("max_quantity", rules.max_quantity),
+                ("quantity_step", rules.quantity_step),
+            ] {
+                if value == 0 {
+                    errors.push(format!("{}: {} must be positive", context, field));
+                }
+            }
+            if rules.tick_size.is_zero() {
+                errors.push(format!("{}: tick_size must be positive", context));
+            }
+            if rules.min_quantity > rules.max_quantity {
+                errors.push(format!(
+                    "{}: min_quantity {} exceeds max_quantity {}",
+                    context, rules.min_quantity, rules.max_quantity
+                ));
+            }
+        }
+    }
+
+    fn validate_fee_schedule(&self, errors: &mut Vec<String>) {
+        let Some(fee_schedule) = &self.exchange.fee_schedule else {
+            return;
+        };
+        let mut rates = vec![
+            ("commission_rate".to_string(), fee_schedule.commission_rate),
+            ("stamp_duty_rate".to_string(), fee_schedule.stamp_duty_rate),
+            (
+                "transfer_fee_rate".to_string(),
+                fee_schedule.transfer_fee_rate,
+            ),
+        ];
+        let mut tiers: Vec<_> = fee_schedule.tiers.iter().collect();
+        tiers.sort_by_key(|(name, _)| name.as_str());
+        for (name, tier) in tiers {
+            rates.push((
+                format!("tiers.{}.commission_rate", name),
+                tier.commission_rate,
+            ));
+        }
+        for (field, rate) in rates {
+            if !(0.0..1.0).contains(&rate) {
+                errors.push(format!(
+                    "exchange.fee_schedule.{}: {} must be at least 0 and below 1",
+                    field, rate
+                ));
+            }
+        }
+    }
+
+    fn validate_strategy(&self, context: &str, strategy: &StrategySpec, errors: &mut Vec<String>) {
+        match strategy {
+            StrategySpec::MarketMaker {
+                spread_ticks,
+                quote_size,
+                target_position,
+                max_position,
+                ..
+            } => {
+                if *spread_ticks == 0 {
+                    errors.push(format!("{}: spread_ticks must be positive", context));
+                }
+                if *quote_size == 0 {
+                    errors.push(format!("{}: quote_size must be positive", context));
+                }
+                if target_position > max_position {
+                    errors.push(format!(
+                        "{}: target_position {} exceeds max_position {}",
+                        context, target_position, max_position
+                    ));
+                }
+            }
+            StrategySpec::TrendFollowing {
+                signal,
+                lookback,
+                fast_lookback,
+                threshold,
+                order_size,
+                ..
+            } => {
+                if *lookback == 0 {
+                    errors.push(format!("{}: lookback must be positive", context));
+                }
+                if let Some(fast_lookback) = fast_lookback {
+                    if *signal == TrendSignal::Crossover
+                        && (*fast_lookback == 0 || fast_lookback >= lookback)
+                    {
+                        errors.push(format!(
+                            "{}: fast_lookback {} must be between 1 and lookback {}",
+                            context, fast_lookback, lookback
+                        ));
+                    }
+                }
+                Self::validate_signal_sizing(context, *threshold, *order_size, errors);
+            }
+            StrategySpec::MeanReversion {
+                lookback,
+                threshold,
+                order_size,
+                ..
+            } => {
+                if *lookback < 2 {
+                    errors.push(format!("{}: lookback must be at least 2", context));
+                }
+                Self::validate_signal_sizing(context, *threshold, *order_size, errors);
+            }
+            StrategySpec::Informed {
+                threshold,
+                order_size,
+                ..
+            } => {
+                if self.fundamental.is_none() {
+                    errors.push(format!(
+                        "{}: informed strategy requires fundamental",
+                        context
+                    ));
+                }
+                Self::validate_signal_sizing(context, *threshold, *order_size, errors);
+            }
+            StrategySpec::Noise {
+                noise,
+                order_size,
+                probability,
+            } => {
+                if noise.is_some_and(|noise| !noise.is_finite() || noise < 0.0) {
+                    errors.push(format!("{}: noise must not be negative", context));
+                }
+                if probability.is_some_and(|probability| !(0.0..=1.0).contains(&probability)) {
+                    errors.push(format!("{}: probability must be between 0 and 1", context));
+                }
+                if *order_size == 0 {
+                    errors.push(format!("{}: order_size must be positive", context));
+                }
+            }
+            StrategySpec::SimpleRandom | StrategySpec::TradeRandom { .. } => {}
+        }
+    }
+
+    fn validate_fundamental(&self, fundamental: &FundamentalSpec, errors: &mut Vec<String>) {
+        for (name, value) in [
+            ("volatility", fundamental.volatility),
+            ("jump_intensity", fundamental.jump_intensity),
+            ("jump_volatility", fundamental.jump_volatility),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                errors.push(format!("fundamental.{} must not be negative", name));
+            }
+        }
+        for (name, value) in [
+            ("drift", fundamental.drift),
+            ("jump_mean", fundamental.jump_mean),
+        ] {
+            if !value.is_finite() {
+                errors.push(format!("fundamental.{} must be finite", name));
+            }
+        }
+    }
+
+    /** 机器人持仓及基本面价值引用的股票中 不在 is_listed 中的股票 */
+    fn unknown_stocks(&self, is_listed: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (index, bot) in self.bots.iter().enumerate() {
+            for stock_code in bot.holdings.keys() {
+                if !is_listed(stock_code) {
+                    errors.push(format!(
+                        "bots[{}] ({}): holding of unknown stock {}",
+                        index, bot.name, stock_code
+                    ));
+                }
+            }
+        }
+        let fundamental_stocks = self
+            .fundamental
+            .iter()
+            .flat_map(|fundamental| &fundamental.stocks);
+        for stock_code in fundamental_stocks.flatten() {
+            if !is_listed(stock_code) {
+                errors.push(format!("fundamental: unknown stock {}", stock_code));
+            }
+        }
+        errors
+    }
+
+    fn validate_signal_sizing(
+        context: &str,
+        threshold: Option<f64>,
+        order_size: Quantity,
+        errors: &mut Vec<String>,
+    ) {
+        if threshold.is_some_and(|threshold| !threshold.is_finite() || threshold < 0.0) {
+            errors.push(format!("{}: threshold must not be negative", context));
+        }
+        if order_size == 0 {
+            errors.push(format!("{}: order_size must be positive", context));
+        }
+    }
+
+    fn validate_account<'a>(
+        &self,
+        context: &str,
+        name: &'a str,
+        balance: Money,
+        names: &mut HashSet<&'a str>,
+        errors: &mut Vec<String>,
+    ) {
+        if name.trim().is_empty() {
+            errors.push(format!("{}: name must not be empty", context));
+        } else if !names.insert(name) {
+            errors.push(format!("{}: duplicate user name", context));
+        }
+        if balance.is_negative() {
+            errors.push(format!("{}: balance must not be negative", context));
+        }
+    }
+
+    /** 交易所参数 未配置的项使用默认值 */
+    pub fn exchange_config(&self) -> ExchangeConfig {
+        let section = &self.exchange;
+        let mut config = ExchangeConfig::for_venue(section.venue.unwrap_or_default());
+        if let Some(name) = &section.name {
+            config.name = name.clone();
+        }
+        if let Some(start_date) = section.start_date {
+            config.current_timestamp = Timestamp::start_of_day(start_date);
+        }
+        if let Some(trading_periods) = &section.trading_periods {
+            config.trading_periods = trading_periods.clone();
+        }
+        if let Some(price_limit) = &section.price_limit {
+            config.price_limit_percentage = price_limit.percentage;
+            config.board_price_limits = price_limit.boards.clone();
+        }
+        if let Some(settlement_cycle) = section.settlement_cycle {
+            config.settlement_cycle = settlement_cycle;
+        }
+        if let Some(trade_on_weekends) = section.trade_on_weekends {
+            config.trade_on_weekends = trade_on_weekends;
+        }
+        if let Some(cost_basis_method) = section.cost_basis_method {
+            config.cost_basis_method = cost_basis_method;
+        }
+        if let Some(fee_schedule) = &section.fee_schedule {
+            config.fee_schedule = fee_schedule.clone();
+        }
+        if let Some(instrument_rules) = &section.instrument_rules {
+            config.instrument_rules = instrument_rules.clone();
+        }
+        if let Some(trade_log) = &section.trade_log {
+            config.trade_log_retention = trade_log.clone();
+        }
+        if let Some(seed) = section.seed {
+            config.set_seed(seed);
+        }
+        config
+    }
+
+    /** 按配置创建交易所 并添加股票、历史行情、用户和机器人 */
+    pub fn build_exchange(&self) -> Result<Exchange, ConfigError> {
+        let mut exchange = Exchange::new(self.exchange_config());
+        for stock in &self.stocks {
+            exchange.add_stock(&stock.code, &stock.name, stock.price)?;
+        }
+        // 机器人的初始持仓按导入后的昨收价计算成本
+        if let Some(market_data) = &self.market_data {
+            let data = MarketData::load(&market_data.path)?;
+            exchange.import_market_data(data, market_data.replay)?;
+            let errors = self.unknown_stocks(|stock_code| {
+                exchange
+                    .stock_manager
+                    .get_stock(&stock_code.to_string())
+                    .is_some()
+            });
+            if !errors.is_empty() {
+                return Err(ConfigError::Invalid(errors));
+            }
+        }
+        if let Some(fundamental) = &self.fundamental {
+            let stock_codes = fundamental
+                .stocks
+                .clone()
+                .unwrap_or_else(|| exchange.stock_manager.get_stock_codes());
+            exchange.start_fundamental(fundamental.into(), stock_codes)?;
+        }
+        for user in &self.users {
+            let user_id = exchange.add_user(&user.name, user.balance)?;
+            if user.fee_tier.is_some() {
+                exchange.set_user_fee_tier(user_id, user.fee_tier.clone())?;
+            }
+        }
+        for bot in &self.bots {
+            let holdings = bot
+                .holdings
+                .iter()
+                .map(|(stock_code, quantity)| (stock_code.as_str(), *quantity))
+                .collect();
+            exchange.add_robot(&bot.name, bot.balance, (&bot.strategy).into(), holdings)?;
+        }
+        Ok(exchange)
+    }
+}
@@ -1,7 +1,7 @@
 use crate::exchange::Exchange;
-use crate::log::TradeLog;
+use crate::log::{PriceDirection, TradeLog};
 use crate::types::*;
-use crate::utils;
+use crate::utils::{self, OrderBookSide, QueuedOrder};
 
 use std::collections::BTreeMap;
 use std::collections::VecDeque;
@@ -9,6 +9,12 @@ use std::collections::VecDeque;
 // MatchingEngine 结构体
 pub struct MatchingEngine {}
 
+impl Default for MatchingEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MatchingEngine {
     pub fn new() -> Self {
         MatchingEngine {}
@@ -68,49 +74,44 @@ impl MatchingEngine {
     }
 
     /** 正式撮合交易 */
-    pub fn execute_match_trades(&self, _: &Exchange) -> () {}
+    pub fn execute_match_trades(&self, _: &Exchange) {}
+
+    /**
+     * 集合竞价集中撮合 每只股票按最大成交量价格一次性成交
+     *
+     * 价格不低于成交价的买入委托与价格不高于成交价的卖出委托按价格时间优先撮合 全部以成交价成交
+     */
+    pub fn call_auction_trades(&self, exchange: &Exchange) -> Vec<TradeLog> {
+        let mut trade_logs = Vec::new();
+
+        for (stock_code, price, volume) in self.simulate_match_trades(exchange) {
+            if volume == 0 {
+                continue;
+            }
+            let (mut buy_orders, mut sell_orders) = order_book_sides(exchange, &stock_code);
+            buy_orders.retain(|(buy_price, _)| *buy_price >= price);
+            sell_orders.retain(|(sell_price, _)| *sell_price <= price);
+
+            trade_logs.extend(
+                utils::match_orders(&mut buy_orders, &mut sell_orders)
+                    .into_iter()
+                    .map(|mut trade_log| {
+                        trade_log.stock_code = stock_code.clone();
+                        trade_log.price = price;
+                        trade_log.direction = PriceDirection::Flat;
+                        trade_log
+                    }),
+            );
+        }
+        trade_logs
+    }
 
     /** 实时连续竞价交易 */
     pub fn continuous_trading(&self, exchange: &Exchange) -> Vec<TradeLog> {
         let mut trade_logs = Vec::new();
 
         for stock_code in exchange.stock_manager.get_stock_codes() {
-            let stock = exchange.stock_manager.get_stock(&stock_code).unwrap();
-            let buy_orders: &BTreeMap<u32, Vec<u64>> = &stock.buy_orders;
-            let sell_orders: &BTreeMap<u32, Vec<u64>> = &stock.sell_orders;
-
-            // 买入委托单：价格从高到低排序
-            let mut buy_orders = buy_orders
-                .iter()
-                .rev() // 反转迭代器，使价格从高到低
-                .map(|(price, order_ids)| {
-                    (
-                        *price,
-                        order_ids
-                            .iter()
-                            .map(|id| exchange.order_manager.get_order(*id).unwrap())
-                            .map(|order| (order.id, order.user_id, order.remaining_quantity))
-                            .filter(|(_, _, quantity)| *quantity > 0)
-                            .collect::<VecDeque<(OrderId, UserId, Quantity)>>(),
-                    )
-                })
-                .collect::<VecDeque<(Price, VecDeque<(OrderId, UserId, Quantity)>)>>();
-
-            // 卖出委托单：价格从低到高排序
-            let mut sell_orders = sell_orders
-                .iter() // 已经是从低到高排序
-                .map(|(price, order_ids)| {
-                    (
-                        *price,
-                        order_ids
-                            .iter()
-                            .map(|id| exchange.order_manager.get_order(*id).unwrap())
-                            .map(|order| (order.id, order.user_id, order.remaining_quantity))
-                            .filter(|(_, _, quantity)| *quantity > 0)
-                            .collect::<VecDeque<(OrderId, UserId, Quantity)>>(),
-                    )
-                })
-                .collect::<VecDeque<(Price, VecDeque<(OrderId, UserId, Quantity)>)>>();
+            let (mut buy_orders, mut sell_orders) = order_book_sides(exchange, &stock_code);
 
             trade_logs.extend(
                 utils::match_orders(&mut buy_orders, &mut sell_orders)
@@ -124,3 +125,45 @@ impl MatchingEngine {
         trade_logs
     }
 }
+
+/** 股票的买卖委托队列 买入价格从高到低 卖出价格从低到高 */
+fn order_book_sides(exchange: &Exchange, stock_code: &StockCode) -> (OrderBookSide, OrderBookSide) {
+    let stock = exchange.stock_manager.get_stock(stock_code).unwrap();
+    let buy_orders: &BTreeMap<Price, Vec<OrderId>> = &stock.buy_orders;
+    let sell_orders: &BTreeMap<Price, Vec<OrderId>> = &stock.sell_orders;
+
+    // 买入委托单：价格从高到低排序
+    let buy_orders = buy_orders
+        .iter()
+        .rev() // 反转迭代器，使价格从高到低
+        .map(|(price, order_ids)| {
+            (
+                *price,
+                order_ids
+                    .iter()
+                    .map(|id| exchange.order_manager.get_order(*id).unwrap())
+                    .map(|order| (order.id, order.user_id, order.remaining_quantity))
+                    .filter(|(_, _, quantity)| *quantity > 0)
+                    .collect::<VecDeque<QueuedOrder>>(),
+            )
+        })
+        .collect::<OrderBookSide>();
+
+    // 卖出委托单：价格从低到高排序
+    let sell_orders = sell_orders
+        .iter() // 已经是从低到高排序
+        .map(|(price, order_ids)| {
+            (
+                *price,
+                order_ids
+                    .iter()
+                    .map(|id| exchange.order_manager.get_order(*id).unwrap())
+                    .map(|order| (order.id, order.user_id, order.remaining_quantity))
+                    .filter(|(_, _, quantity)| *quantity > 0)
+                    .collect::<VecDeque<QueuedOrder>>(),
+            )
+        })
+        .collect::<OrderBookSide>();
+
+    (buy_orders, sell_orders)
+}
@@ -0,0 +1,74 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+use crate::config::TradingPeriodType;
+use crate::types::Timestamp;
+
+/** 待推送事件的最大缓存数量 超出后丢弃最早的事件 */
+const MAX_PENDING_EVENTS: usize = 1024;
+
+// 交易所事件
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum ExchangeEvent {
+    /** 时钟 */
+    ClockTick { timestamp: Timestamp },
+    /** 交易时段切换 */
+    PeriodChanged {
+        timestamp: Timestamp,
+        from: Option<String>,
+        to: Option<String>,
+        period_type: Option<TradingPeriodType>,
+    },
+    /** 交易日切换 */
+    DayRollover { trade_day: NaiveDate },
+    /** 停止交易 */
+    Halt {
+        timestamp: Timestamp,
+        reason: String,
+    },
+}
+
+impl ExchangeEvent {
+    /** 事件名称 */
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExchangeEvent::ClockTick { .. } => "clock",
+            ExchangeEvent::PeriodChanged { .. } => "period",
+            ExchangeEvent::DayRollover { .. } => "rollover",
+            ExchangeEvent::Halt { .. } => "halt",
+        }
+    }
+}
+
+// 事件队列 由外部定期取出推送
+pub struct EventQueue {
+    events: VecDeque<ExchangeEvent>,
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        EventQueue {
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, event: ExchangeEvent) {
+        if self.events.len() >= MAX_PENDING_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /** 取出所有待推送事件 */
+    pub fn drain(&mut self) -> Vec<ExchangeEvent> {
+        self.events.drain(..).collect()
+    }
+}
@@ -1,24 +1,38 @@
 use crate::config::ExchangeConfig;
+use crate::config::TradingPeriod;
 use crate::config::TradingPeriodType;
 use crate::engine::MatchingEngine;
+use crate::event::{EventQueue, ExchangeEvent};
 use crate::exchange_error::ExchangeError;
-use crate::log::LogManager;
+use crate::fee::FeeReport;
+use crate::fundamental::FundamentalProcess;
+use crate::instrument::{Board, InstrumentRules};
+use crate::journal::{Command, CommandRecorder};
 use crate::log::TradeLog;
-use crate::order::{OrderManager, OrderType};
+use crate::log::{LogManager, TradeLogPage, TradeLogQuery};
+use crate::market_data::MarketReplay;
+use crate::order::{Order, OrderManager, OrderStatus, OrderType};
+use crate::random::derive_rng;
+use crate::record::{ExchangeRecord, RecordQueue};
+use crate::stock::OrderQueue;
 use crate::stock::PriceHistoryInfo;
 use crate::stock::StockInfo;
 use crate::stock::StockManager;
 use crate::trade_day::TradingCalendar;
 use crate::trading_bot::TradingBotManager;
-use crate::trading_strategy::TradingAction;
 use crate::trading_strategy::TradingStrategy;
+use crate::trading_strategy::{ActionOutcome, ActionResult, TradingAction};
 use crate::types::*;
-use crate::user::UserManager;
+use crate::user::{AccountInfo, PnlRanking, PnlSnapshot, PositionInfo, UserManager};
 use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-// 交易所结构体
+// 交易所结构体 撮合引擎无状态 事件队列只含待推送的事件 均不保存到快照
+#[derive(Serialize, Deserialize)]
 pub struct Exchange {
     pub config: ExchangeConfig,
+    #[serde(skip)]
     pub engine: MatchingEngine,
     pub stock_manager: StockManager,
     pub user_manager: UserManager,
@@ -26,33 +40,100 @@ pub struct Exchange {
     pub order_manager: OrderManager,
     pub log_manager: LogManager,
     pub trade_day_manager: TradingCalendar,
-    pub current_trade_day: NaiveDate,
+    /** 历史行情回放 */
+    #[serde(default)]
+    pub market_replay: Option<MarketReplay>,
+    /** 基本面价值过程 */
+    #[serde(default)]
+    pub fundamental: Option<FundamentalProcess>,
+    #[serde(skip)]
+    pub event_queue: EventQueue,
+    /** 待写入日志的命令 */
+    #[serde(skip)]
+    pub recorder: CommandRecorder,
+    /** 待持久化的记录 */
+    #[serde(skip)]
+    pub records: RecordQueue,
 }
 
 impl Exchange {
-    pub fn new(config: ExchangeConfig) -> Self {
-        let exchange = Self {
-            config: config,
+    pub fn new(mut config: ExchangeConfig) -> Self {
+        // 按最终的交易时段计算当前及下一时段
+        config.set_current_timestamp(config.current_timestamp);
+        let trade_day_manager = if config.trade_on_weekends {
+            TradingCalendar::every_day()
+        } else {
+            TradingCalendar::new()
+        };
+        let log_manager = LogManager::with_max_logs(config.trade_log_retention.max_logs);
+        Self {
+            config,
             engine: MatchingEngine::new(),
             user_manager: UserManager::new(),
             bot_manager: TradingBotManager::new(),
             order_manager: OrderManager::new(),
             stock_manager: StockManager::new(),
-            log_manager: LogManager::new(),
-            trade_day_manager: TradingCalendar::new(),
-            current_trade_day: NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
-        };
-        exchange
+            log_manager,
+            trade_day_manager,
+            market_replay: None,
+            fundamental: None,
+            event_queue: EventQueue::new(),
+            recorder: CommandRecorder::new(),
+            records: RecordQueue::new(),
+        }
+    }
+
+    /** 执行命令 最外层的命令记录到日志 */
+    pub(crate) fn journaled<T>(
+        &mut self,
+        command: Command,
+        apply: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        self.recorder.enter(command);
+        let result = apply(self);
+        self.recorder.exit();
+        result
+    }
+
+    /** 推进到当前交易日的某一时刻 如 "09:30:00" 或 "09:30:00.125" */
+    pub fn next_timestamp(&mut self, time: &str) {
+        let time = string_to_time_of_day(time).unwrap();
+        self.advance_to(self.config.current_timestamp.with_time(time));
     }
 
-    pub fn next_timestamp(&mut self, timestamp: &str) {
-        let timestamp = string_to_timestamp(timestamp).unwrap();
-        if timestamp <= self.config.current_timestamp {
+    /** 推进时钟 时间不能回退 也不能跨越交易日 跨日需调用 next_trade_day */
+    pub fn advance_to(&mut self, timestamp: Timestamp) {
+        self.journaled(Command::AdvanceTo { timestamp }, |exchange| {
+            exchange.advance_to_inner(timestamp)
+        })
+    }
+
+    fn advance_to_inner(&mut self, timestamp: Timestamp) {
+        if timestamp <= self.config.current_timestamp
+            || timestamp.date() != self.current_trade_day()
+        {
             return;
         }
+        let previous_period = self.config.get_current_period().cloned();
+        let was_closed = self.config.is_randomly_closed();
         self.config.set_current_timestamp(timestamp);
+        // 收盘竞价在随机收市或离开时段时集中撮合
+        let left_period = self
+            .config
+            .get_current_period()
+            .map(|period| period.start_tick)
+            != previous_period.as_ref().map(|period| period.start_tick);
+        if previous_period
+            .as_ref()
+            .is_some_and(is_matching_closing_auction)
+            && !was_closed
+            && (left_period || self.config.is_randomly_closed())
+        {
+            self.uncross_closing_auction(timestamp);
+        }
+        self.publish_clock_events(previous_period);
         // 当前配置阶段
-        let trading_period = self.config.get_current_period();
+        let trading_period = self.config.get_current_period().cloned();
         if let Some(period) = trading_period {
             match period.period_type {
                 TradingPeriodType::OpeningAuction => {
@@ -81,73 +162,15 @@ impl Exchange {
                     // 集合竞价 不可以撤单
                     self.engine.execute_match_trades(self);
                 }
-                TradingPeriodType::ContinuousTrading => {
-                    // 连续交易
+                TradingPeriodType::ContinuousTrading
+                | TradingPeriodType::PreMarket
+                | TradingPeriodType::AfterHours => {
+                    // 连续交易 盘前盘后同样连续撮合
                     let trade_logs = self.engine.continuous_trading(self);
-                    for trade_log in trade_logs {
-                        self.log_manager.add_log(trade_log.clone());
-                        let sell_order_id = trade_log.sell_order_id;
-                        let buy_order_id = trade_log.buy_order_id;
-                        let stock_code = trade_log.stock_code;
-                        let trade_price: u32 = trade_log.price;
-                        let trade_quantity = trade_log.quantity;
-
-                        let stock = self.stock_manager.get_stock_mut(&stock_code).unwrap();
-                        stock.set_current_price(trade_price);
-                        stock.add_price_to_history(timestamp, trade_price, trade_quantity);
-                        stock.update_daily_info();
-
-                        let buy_order = self.order_manager.get_order_mut(buy_order_id).unwrap();
-                        buy_order.execute(trade_quantity, trade_price, timestamp);
-
-                        // 更新买卖队列
-
-                        stock
-                            .buy_quantities
-                            .entry(buy_order.price)
-                            .and_modify(|quantity| {
-                                *quantity -= trade_quantity as u64;
-                            });
-
-                        if buy_order.is_filled() {
-                            stock
-                                .buy_orders
-                                .entry(buy_order.price)
-                                .and_modify(|orders| {
-                                    orders.retain(|order_id| order_id != &buy_order_id);
-                                });
-
-                            if stock.buy_orders.get(&buy_order.price).unwrap().is_empty() {
-                                stock.buy_orders.remove(&buy_order.price);
-                            }
-                        }
-
-                        let sell_order = self.order_manager.get_order_mut(sell_order_id).unwrap();
-                        sell_order.execute(trade_quantity, trade_price, timestamp);
-
-                        stock
-                            .sell_quantities
-                            .entry(sell_order.price)
-                            .and_modify(|quantity| {
-                                *quantity -= trade_quantity as u64;
-                            });
-
-                        if sell_order.is_filled() {
-                            stock
-                                .sell_orders
-                                .entry(sell_order.price)
-                                .and_modify(|orders| {
-                                    orders.retain(|order_id| order_id != &sell_order_id);
-                                });
-                            if stock.sell_orders.get(&sell_order.price).unwrap().is_empty() {
-                                stock.sell_orders.remove(&sell_order.price);
-                            }
-                        }
-                    }
+                    self.settle_trades(trade_logs, timestamp);
                 }
                 TradingPeriodType::ClosingAuction => {
-                    // 收盘竞价
-                    self.engine.execute_match_trades(self);
+                    // 收盘竞价 随机收市或时段结束时集中撮合
                 }
                 TradingPeriodType::MarketClosed => {
                     // 市场关闭
@@ -155,6 +178,9 @@ impl Exchange {
                 TradingPeriodType::MiddayBreak => {
                     // 午间休市
                 }
+                TradingPeriodType::Blocking => {
+                    // 暂停时段
+                }
             }
 
             if period.allow_record_price_history {
@@ -167,17 +193,241 @@ impl Exchange {
         }
     }
 
+    /** 收盘竞价集中撮合 以最大成交量价格成交 成交价即收盘价 */
+    fn uncross_closing_auction(&mut self, timestamp: Timestamp) {
+        let trade_logs = self.engine.call_auction_trades(self);
+        self.settle_trades(trade_logs, timestamp);
+    }
+
+    /** 成交记账 更新价格 委托队列 并完成买卖双方的交割 */
+    fn settle_trades(&mut self, trade_logs: Vec<TradeLog>, timestamp: Timestamp) {
+        for mut trade_log in trade_logs {
+            trade_log.timestamp = timestamp;
+            let trade_id = self.log_manager.add_log(trade_log.clone());
+            if self.records.is_enabled() {
+                let mut trade = trade_log.clone();
+                trade.id = trade_id;
+                self.records.push(ExchangeRecord::Trade(trade));
+            }
+            let sell_order_id = trade_log.sell_order_id;
+            let buy_order_id = trade_log.buy_order_id;
+            let stock_code = trade_log.stock_code;
+            let trade_price: Price = trade_log.price;
+            let trade_quantity = trade_log.quantity;
+
+            let stock = self.stock_manager.get_stock_mut(&stock_code).unwrap();
+            stock.set_current_price(trade_price);
+            stock.add_price_to_history(timestamp, trade_price, trade_quantity);
+            stock.update_daily_info();
+
+            // 计算买卖双方的交易费用
+            let trade_amount = trade_price * trade_quantity;
+            let buyer_tier = self
+                .user_manager
+                .get_user(trade_log.buyer_id)
+                .and_then(|user| user.fee_tier.clone());
+            let seller_tier = self
+                .user_manager
+                .get_user(trade_log.seller_id)
+                .and_then(|user| user.fee_tier.clone());
+
+            let buy_order = self.order_manager.get_order_mut(buy_order_id).unwrap();
+            let buy_fees = self.config.fee_schedule.calculate(
+                OrderType::Buy,
+                trade_amount,
+                buy_order.filled_amount(),
+                buy_order.commission_paid,
+                buyer_tier.as_deref(),
+            );
+            let buy_remaining_before = buy_order.remaining_quantity;
+            buy_order.execute(trade_quantity, trade_price, timestamp, buy_fees);
+            self.records.push_execution(buy_order);
+            // 按委托价冻结的现金及预估费用随成交释放
+            let buy_released = buy_order.price * trade_quantity
+                + buy_order.release_frozen_fee(
+                    trade_quantity,
+                    buy_remaining_before,
+                    buy_fees.total(),
+                );
+
+            // 更新买卖队列
+
+            stock
+                .buy_quantities
+                .entry(buy_order.price)
+                .and_modify(|quantity| {
+                    *quantity -= trade_quantity as u64;
+                });
+
+            if buy_order.is_filled() {
+                stock
+                    .buy_orders
+                    .entry(buy_order.price)
+                    .and_modify(|orders| {
+                        orders.retain(|order_id| order_id != &buy_order_id);
+                    });
+
+                if stock.buy_orders.get(&buy_order.price).unwrap().is_empty() {
+                    stock.buy_orders.remove(&buy_order.price);
+                }
+            }
+
+            let sell_order = self.order_manager.get_order_mut(sell_order_id).unwrap();
+            let sell_fees = self.config.fee_schedule.calculate(
+                OrderType::Sell,
+                trade_amount,
+                sell_order.filled_amount(),
+                sell_order.commission_paid,
+                seller_tier.as_deref(),
+            );
+            sell_order.execute(trade_quantity, trade_price, timestamp, sell_fees);
+            self.records.push_execution(sell_order);
+
+            stock
+                .sell_quantities
+                .entry(sell_order.price)
+                .and_modify(|quantity| {
+                    *quantity -= trade_quantity as u64;
+                });
+
+            if sell_order.is_filled() {
+                stock
+                    .sell_orders
+                    .entry(sell_order.price)
+                    .and_modify(|orders| {
+                        orders.retain(|order_id| order_id != &sell_order_id);
+                    });
+                if stock.sell_orders.get(&sell_order.price).unwrap().is_empty() {
+                    stock.sell_orders.remove(&sell_order.price);
+                }
+            }
+
+            // 交割
+            if let Some(buyer) = self.user_manager.get_user_mut(trade_log.buyer_id) {
+                buyer.settle_buy(
+                    &stock_code,
+                    buy_released,
+                    trade_price,
+                    trade_quantity,
+                    &buy_fees,
+                    self.config.settlement_cycle,
+                );
+            }
+            if let Some(seller) = self.user_manager.get_user_mut(trade_log.seller_id) {
+                seller.settle_sell(
+                    &stock_code,
+                    trade_price,
+                    trade_quantity,
+                    self.config.cost_basis_method,
+                    &sell_fees,
+                );
+            }
+        }
+    }
+
     pub fn next_trade_day(&mut self) {
-        self.current_trade_day = self
+        self.journaled(Command::Rollover, |exchange| {
+            exchange.next_trade_day_inner()
+        })
+    }
+
+    fn next_trade_day_inner(&mut self) {
+        // 收盘竞价尚未撮合时 收盘前完成集中撮合
+        if self
+            .config
+            .get_current_period()
+            .is_some_and(is_matching_closing_auction)
+            && !self.config.is_randomly_closed()
+        {
+            self.uncross_closing_auction(self.config.current_timestamp);
+        }
+
+        // 收盘盈亏快照
+        self.record_pnl_snapshots();
+
+        let trade_day = self
             .trade_day_manager
-            .next_trade_day(self.current_trade_day);
+            .next_trade_day(self.current_trade_day());
+
+        // 收盘时未成交的委托过期 并记录当日K线
+        if self.records.is_enabled() {
+            let timestamp = self.config.current_timestamp;
+            for order in self.order_manager.get_open_orders() {
+                self.records
+                    .push_order(order, OrderStatus::Expired, timestamp);
+            }
+            for bar in self.daily_bars_on(self.current_trade_day()) {
+                self.records.push(ExchangeRecord::DailyBar(bar));
+            }
+        }
 
-        // 清算
+        // 清算 未成交的委托当日有效 收盘后全部撤销
         self.order_manager.clear_orders();
         // 用户持仓变可用
         self.user_manager.reset_positions();
-        // 设置当前时间戳
-        self.config.set_current_timestamp(0);
+        // 以收盘价作为下一交易日的昨收价 重新计算涨跌停价
+        for stock_code in self.stock_manager.get_stock_codes() {
+            let stock = self.stock_manager.get_stock(&stock_code).unwrap();
+            let (price_limit_start, price_limit_end) =
+                self.price_limits(stock.board, stock.current_price);
+            let stock = self.stock_manager.get_stock_mut(&stock_code).unwrap();
+            stock.set_closing_price();
+            stock.clear_orders();
+            stock.start_price = stock.current_price;
+            stock.reset_daily_info();
+            stock.update_price_limit(price_limit_start, price_limit_end);
+        }
+
+        // 设置当前时间戳为新交易日零点
+        let previous_period = self.config.get_current_period().cloned();
+        self.config
+            .set_current_timestamp(Timestamp::start_of_day(trade_day));
+
+        self.event_queue
+            .push(ExchangeEvent::DayRollover { trade_day });
+        self.publish_clock_events(previous_period);
+    }
+
+    /** 记录时钟及交易时段切换事件 */
+    fn publish_clock_events(&mut self, previous_period: Option<TradingPeriod>) {
+        let timestamp = self.config.current_timestamp;
+        self.event_queue
+            .push(ExchangeEvent::ClockTick { timestamp });
+
+        let current_period = self.config.get_current_period().cloned();
+        let changed = match (&previous_period, &current_period) {
+            (Some(previous), Some(current)) => previous.start_tick != current.start_tick,
+            (None, None) => false,
+            _ => true,
+        };
+        if !changed {
+            return;
+        }
+
+        self.event_queue.push(ExchangeEvent::PeriodChanged {
+            timestamp,
+            from: previous_period.as_ref().map(|period| period.name.clone()),
+            to: current_period.as_ref().map(|period| period.name.clone()),
+            period_type: current_period
+                .as_ref()
+                .map(|period| period.period_type.clone()),
+        });
+
+        // 进入不可下单的时段或闭市时发出停止交易公告
+        let halt_reason = match &current_period {
+            Some(period) if !period.allow_order => Some(period.name.clone()),
+            None => Some("闭市".to_string()),
+            _ => None,
+        };
+        if let Some(reason) = halt_reason {
+            self.event_queue
+                .push(ExchangeEvent::Halt { timestamp, reason });
+        }
+    }
+
+    /** 取出待推送的交易所事件 */
+    pub fn drain_events(&mut self) -> Vec<ExchangeEvent> {
+        self.event_queue.drain()
     }
 
     pub fn get_config(&self) -> ExchangeConfig {
@@ -188,6 +438,11 @@ impl Exchange {
         self.config.current_timestamp
     }
 
+    /** 当前交易日 */
+    pub fn current_trade_day(&self) -> NaiveDate {
+        self.config.current_timestamp.date()
+    }
+
     fn check_tick_allowed(&self, action: &str) -> Result<(), ExchangeError> {
         let is_allowed = match action {
             "order" => self.config.is_allow_order(),
@@ -198,9 +453,10 @@ impl Exchange {
         if !is_allowed {
             Err(ExchangeError::ActionNotAllowed {
                 action: action.to_string(),
-                time: timestamp_to_string(self.config.current_timestamp)
-                    + " in "
-                    + &self.config.get_current_period().unwrap().to_string(),
+                time: match self.config.get_current_period() {
+                    Some(period) => format!("{} in {}", self.config.current_timestamp, period),
+                    None => format!("{} (market closed)", self.config.current_timestamp),
+                },
             })
         } else {
             Ok(())
@@ -216,48 +472,157 @@ impl Exchange {
         price: Price,
         quantity: Quantity,
     ) -> Result<u64, ExchangeError> {
+        let command = Command::Submit {
+            user_id,
+            stock_code: stock_code.clone(),
+            order_type,
+            price,
+            quantity,
+        };
+        self.journaled(command, |exchange| {
+            exchange.submit_order_inner(user_id, stock_code, order_type, price, quantity)
+        })
+    }
+
+    fn submit_order_inner(
+        &mut self,
+        user_id: UserId,
+        stock_code: StockCode,
+        order_type: OrderType,
+        price: Price,
+        quantity: Quantity,
+    ) -> Result<u64, ExchangeError> {
+        let frozen_fee =
+            self.check_order(user_id, &stock_code, order_type, price, quantity, None)?;
+
+        // 冻结现金或持仓
+        let user = self.user_manager.get_user_mut(user_id).unwrap();
+        match order_type {
+            OrderType::Buy => user.freeze_balance(price * quantity + frozen_fee)?,
+            OrderType::Sell => user.freeze_holding(&stock_code, quantity)?,
+        }
+
+        // 创建订单
+        let order = self.order_manager.create_order(
+            user_id,
+            stock_code,
+            order_type,
+            price,
+            quantity,
+            self.config.current_timestamp,
+        );
+        if !frozen_fee.is_zero() {
+            self.order_manager
+                .get_order_mut(order.id)
+                .unwrap()
+                .frozen_fee = frozen_fee;
+        }
+
+        self.stock_manager
+            .get_stock_mut(&order.stock_code)
+            .unwrap()
+            .add_order(&order);
+        self.records
+            .push_order(&order, OrderStatus::New, order.timestamp);
+
+        Ok(order.id)
+    }
+
+    /**
+     * 校验委托 返回买入需冻结的预估费用
+     *
+     * replacing 为改单时被替换的原委托 其冻结的现金或持仓计入可用
+     */
+    fn check_order(
+        &self,
+        user_id: UserId,
+        stock_code: &StockCode,
+        order_type: OrderType,
+        price: Price,
+        quantity: Quantity,
+        replacing: Option<&Order>,
+    ) -> Result<Money, ExchangeError> {
         self.check_tick_allowed("order")?;
 
         let user = self
             .user_manager
-            .get_user_mut(user_id)
+            .get_user(user_id)
             .ok_or(ExchangeError::UserNotFound(user_id))?;
+        // 撤销原委托释放的现金及持仓
+        let (released_balance, released_quantity) = match replacing {
+            Some(order) if order.order_type == OrderType::Buy => {
+                (order.price * order.remaining_quantity + order.frozen_fee, 0)
+            }
+            Some(order) => (Money::ZERO, order.remaining_quantity as u64),
+            None => (Money::ZERO, 0),
+        };
+        // 买入需冻结委托金额及预估费用
+        let frozen_fee = match order_type {
+            OrderType::Buy => self.config.fee_schedule.estimate(
+                OrderType::Buy,
+                price * quantity,
+                user.fee_tier.as_deref(),
+            ),
+            OrderType::Sell => Money::ZERO,
+        };
         // 判断用户现金是否足够
-        if order_type == OrderType::Buy && !user.has_enough_balance(price, quantity) {
-            println!(
-                "submit_order failed!!! user: {} has_enough_balance: {}",
+        if order_type == OrderType::Buy
+            && user.available_balance() + released_balance < price * quantity + frozen_fee
+        {
+            log::debug!(
+                "submit_order failed!!! user: {} available_balance: {}",
                 user.id,
-                user.has_enough_balance(price, quantity)
+                user.available_balance() + released_balance
             );
             return Err(ExchangeError::InsufficientBalance);
         }
+        // 判断用户可卖持仓是否足够
+        let available = user
+            .holdings
+            .get(stock_code)
+            .map(|holding| holding.available_quantity + released_quantity);
+        if order_type == OrderType::Sell
+            && available.is_none_or(|available| available < quantity as u64)
+        {
+            return Err(ExchangeError::InsufficientStock);
+        }
         // 判断股票是否存在
         let stock = self
             .stock_manager
-            .get_stock_mut(&stock_code)
+            .get_stock(stock_code)
             .ok_or(ExchangeError::StockNotFound(stock_code.to_string()))?;
 
         // 判断价格是否在限制范围内
         if price < stock.price_limit.lower || price > stock.price_limit.upper {
-            println!(
+            log::debug!(
                 "submit_order failed!!! stock: {} price: {} limit: {:?}",
-                stock_code, price, stock.price_limit
+                stock_code,
+                price,
+                stock.price_limit
             );
             return Err(ExchangeError::PriceOutOfLimit(stock_code.to_string()));
         }
 
-        // 创建订单
-        let order = self
-            .order_manager
-            .create_order(user_id, stock_code, order_type, price, quantity);
-
-        stock.add_order(&order);
-
-        Ok(order.id)
+        // 判断委托是否符合板块交易规则
+        if let Some(rules) = self.config.instrument_rules.get(stock.board) {
+            rules
+                .validate(order_type, price, quantity, available.unwrap_or(0))
+                .map_err(|violation| ExchangeError::OrderRuleViolation {
+                    stock_code: stock_code.to_string(),
+                    violation,
+                })?;
+        }
+        Ok(frozen_fee)
     }
 
     /** 撤单 */
     pub fn cancel_order(&mut self, order_id: u64) -> Result<(), ExchangeError> {
+        self.journaled(Command::Cancel { order_id }, |exchange| {
+            exchange.cancel_order_inner(order_id)
+        })
+    }
+
+    fn cancel_order_inner(&mut self, order_id: u64) -> Result<(), ExchangeError> {
         self.check_tick_allowed("cancel")?;
 
         // 判断订单是否存在
@@ -270,16 +635,72 @@ impl Exchange {
             return Err(ExchangeError::OrderNotCancellable(order_id));
         }
 
-        order.cancel();
+        let cancelled_quantity = order.cancel();
+        if let Some(user) = self.user_manager.get_user_mut(order.user_id) {
+            match order.order_type {
+                OrderType::Buy => {
+                    let released = order.price * cancelled_quantity
+                        + order.release_frozen_fee(
+                            cancelled_quantity,
+                            cancelled_quantity,
+                            Money::ZERO,
+                        );
+                    user.unfreeze_balance(released)
+                }
+                OrderType::Sell => user.unfreeze_holding(&order.stock_code, cancelled_quantity),
+            }
+        }
         let stock = self
             .stock_manager
             .get_stock_mut(&order.stock_code)
             .ok_or(ExchangeError::StockNotFound(order.stock_code.to_string()))?;
         stock.remove_order(order);
+        self.records
+            .push_order(order, order.status(), self.config.current_timestamp);
 
         Ok(())
     }
 
+    /**
+     * 改单 撤销原委托未成交的部分 以新的价格及数量重新委托 返回新委托的编号
+     *
+     * 新委托排在同价位队尾 先按撤销原委托后的可用现金及持仓校验新委托 校验失败时原委托保持不变
+     */
+    pub fn amend_order(
+        &mut self,
+        order_id: OrderId,
+        price: Price,
+        quantity: Quantity,
+    ) -> Result<OrderId, ExchangeError> {
+        let command = Command::Amend {
+            order_id,
+            price,
+            quantity,
+        };
+        self.journaled(command, |exchange| {
+            let order = exchange
+                .order_manager
+                .get_order(order_id)
+                .ok_or(ExchangeError::OrderNotFound(order_id))?;
+            let (user_id, stock_code, order_type) =
+                (order.user_id, order.stock_code.clone(), order.order_type);
+            exchange.check_tick_allowed("cancel")?;
+            if !order.is_cancellable() {
+                return Err(ExchangeError::OrderNotCancellable(order_id));
+            }
+            exchange.check_order(
+                user_id,
+                &stock_code,
+                order_type,
+                price,
+                quantity,
+                Some(order),
+            )?;
+            exchange.cancel_order_inner(order_id)?;
+            exchange.submit_order_inner(user_id, stock_code, order_type, price, quantity)
+        })
+    }
+
     /** 添加股票 */
     pub fn add_stock(
         &mut self,
@@ -287,18 +708,46 @@ impl Exchange {
         stock_name: &str,
         start_price: Price,
     ) -> Result<(), ExchangeError> {
-        let price_limit_start =
-            (start_price as f64 * (1.0 - self.config.price_limit_percentage)).round() as Price;
-        let price_limit_end =
-            (start_price as f64 * (1.0 + self.config.price_limit_percentage)).round() as Price;
-        self.stock_manager.add_stock(
-            stock_code.to_string(),
-            stock_name.to_string(),
-            start_price,
-            price_limit_start,
-            price_limit_end,
-        )?;
-        Ok(())
+        let command = Command::AddStock {
+            code: stock_code.to_string(),
+            name: stock_name.to_string(),
+            price: start_price,
+        };
+        self.journaled(command, |exchange| {
+            let (price_limit_start, price_limit_end) =
+                exchange.price_limits(Board::from_code(stock_code), start_price);
+            exchange.stock_manager.add_stock(
+                stock_code.to_string(),
+                stock_name.to_string(),
+                start_price,
+                price_limit_start,
+                price_limit_end,
+            )?;
+            Ok(())
+        })
+    }
+
+    /** 以昨日收盘价计算涨跌停价 按板块最小价格变动单位四舍五入 */
+    pub(crate) fn price_limits(&self, board: Board, start_price: Price) -> (Price, Price) {
+        let tick_size = self
+            .config
+            .instrument_rules
+            .get(board)
+            .map_or(Price::from_raw(1), |rules| rules.tick_size);
+        match self.config.price_limit_percentage_for(board) {
+            Some(percentage) => (
+                start_price.scale(1.0 - percentage).round_to(tick_size),
+                start_price.scale(1.0 + percentage).round_to(tick_size),
+            ),
+            // 不设涨跌幅限制
+            None => (Price::ZERO, Price::MAX),
+        }
+    }
+
+    /** 股票所属板块的交易规则 */
+    pub fn get_instrument_rules(&self, stock_code: &str) -> Option<&InstrumentRules> {
+        let stock = self.stock_manager.get_stock(&stock_code.to_string())?;
+        self.config.instrument_rules.get(stock.board)
     }
 
     /** 获取股票列表 */
@@ -313,6 +762,7 @@ impl Exchange {
             .map(|stock| StockInfo {
                 code: stock.code.clone(),
                 name: stock.name.clone(),
+                board: stock.board,
                 start_price: stock.start_price,
                 current_price: stock.current_price,
                 price_limit: stock.price_limit.clone(),
@@ -336,23 +786,187 @@ impl Exchange {
         self.log_manager.page_logs(stock_code, page, page_size)
     }
 
+    /** 按股票、用户、时间范围查询交易记录 游标分页 */
+    pub fn query_trade_logs(&self, query: &TradeLogQuery) -> TradeLogPage {
+        self.log_manager.query(query)
+    }
+
+    /** 开始保留超出保留策略而移除的交易记录 由外部取出写入文件 */
+    pub fn enable_log_spill(&mut self) {
+        self.log_manager.enable_spill();
+    }
+
+    /** 取出移除的交易记录 */
+    pub fn drain_spilled_logs(&mut self) -> Vec<TradeLog> {
+        self.log_manager.drain_spilled()
+    }
+
     pub fn add_user(
         &mut self,
         user_name: &str,
-        initial_balance: u64,
+        initial_balance: Money,
     ) -> Result<UserId, ExchangeError> {
-        let user_id = self
+        let command = Command::AddUser {
+            username: user_name.to_string(),
+            balance: initial_balance,
+        };
+        self.journaled(command, |exchange| {
+            let user_id = exchange
+                .user_manager
+                .create_user(user_name.to_string(), initial_balance);
+            if exchange.records.is_enabled() {
+                exchange.records.push(ExchangeRecord::User {
+                    user_id,
+                    username: user_name.to_string(),
+                    balance: initial_balance,
+                    timestamp: exchange.config.current_timestamp,
+                });
+            }
+            Ok(user_id)
+        })
+    }
+
+    /** 入金 */
+    pub fn deposit(&mut self, user_id: UserId, amount: Money) -> Result<(), ExchangeError> {
+        self.journaled(Command::Deposit { user_id, amount }, |exchange| {
+            let user = exchange
+                .user_manager
+                .get_user_mut(user_id)
+                .ok_or(ExchangeError::UserNotFound(user_id))?;
+            user.deposit(amount);
+            Ok(())
+        })
+    }
+
+    /** 账户信息 持仓按股票当前价格估值 */
+    pub fn get_account(&self, user_id: UserId) -> Result<AccountInfo, ExchangeError> {
+        let user = self
+            .user_manager
+            .get_user(user_id)
+            .ok_or(ExchangeError::UserNotFound(user_id))?;
+
+        let mut positions: Vec<PositionInfo> = user
+            .holdings
+            .iter()
+            .map(|(stock_code, holding)| {
+                let current_price = self
+                    .stock_manager
+                    .get_stock(stock_code)
+                    .map_or(Price::ZERO, |stock| stock.current_price);
+                PositionInfo {
+                    stock_code: stock_code.clone(),
+                    quantity: holding.quantity,
+                    available_quantity: holding.available_quantity,
+                    frozen_quantity: holding.frozen_quantity,
+                    cost: holding.cost,
+                    average_cost: holding.average_cost(),
+                    current_price,
+                    market_value: current_price * holding.quantity,
+                    unrealized_pnl: holding.unrealized_pnl(current_price),
+                    realized_pnl: user
+                        .realized_pnl
+                        .get(stock_code)
+                        .cloned()
+                        .unwrap_or(Money::ZERO),
+                }
+            })
+            .collect();
+        positions.sort_by(|a, b| a.stock_code.cmp(&b.stock_code));
+
+        let market_value = positions.iter().map(|position| position.market_value).sum();
+        let unrealized_pnl = positions
+            .iter()
+            .map(|position| position.unrealized_pnl)
+            .sum();
+        Ok(AccountInfo {
+            user_id,
+            username: user.username.clone(),
+            balance: user.balance,
+            available_balance: user.available_balance(),
+            frozen_balance: user.frozen_balance,
+            market_value,
+            total_equity: user.balance + market_value,
+            realized_pnl: user.total_realized_pnl(),
+            unrealized_pnl,
+            positions,
+        })
+    }
+
+    /** 记录所有用户的收盘盈亏快照 */
+    fn record_pnl_snapshots(&mut self) {
+        let trade_day = self.current_trade_day();
+        for user_id in self.user_manager.get_user_ids() {
+            let Ok(account) = self.get_account(user_id) else {
+                continue;
+            };
+            let user = self.user_manager.get_user_mut(user_id).unwrap();
+            user.record_pnl_snapshot(trade_day, &account);
+        }
+    }
+
+    /** 每日盈亏快照 */
+    pub fn get_pnl_history(&self, user_id: UserId) -> Result<Vec<PnlSnapshot>, ExchangeError> {
+        self.user_manager
+            .get_user(user_id)
+            .map(|user| user.pnl_history.clone())
+            .ok_or(ExchangeError::UserNotFound(user_id))
+    }
+
+    /** 用户累计交易费用 */
+    pub fn get_fee_report(&self, user_id: UserId) -> Result<FeeReport, ExchangeError> {
+        self.user_manager
+            .get_user(user_id)
+            .map(|user| user.fee_report.clone())
+            .ok_or(ExchangeError::UserNotFound(user_id))
+    }
+
+    /** 设置用户佣金档位 None 表示使用默认费率 */
+    pub fn set_user_fee_tier(
+        &mut self,
+        user_id: UserId,
+        tier: Option<String>,
+    ) -> Result<(), ExchangeError> {
+        let command = Command::SetFeeTier {
+            user_id,
+            tier: tier.clone(),
+        };
+        self.journaled(command, |exchange| {
+            let user = exchange
+                .user_manager
+                .get_user_mut(user_id)
+                .ok_or(ExchangeError::UserNotFound(user_id))?;
+            user.fee_tier = tier;
+            Ok(())
+        })
+    }
+
+    /** 按累计总盈亏排名 浮动盈亏按股票当前价格计算 */
+    pub fn get_pnl_ranking(&self) -> Vec<PnlRanking> {
+        let mut ranking: Vec<PnlRanking> = self
             .user_manager
-            .create_user(user_name.to_string(), initial_balance);
-        Ok(user_id)
+            .get_user_ids()
+            .into_iter()
+            .filter_map(|user_id| self.get_account(user_id).ok())
+            .map(|account| PnlRanking {
+                rank: 0,
+                user_id: account.user_id,
+                username: account.username,
+                is_robot: self.bot_manager.is_bot(account.user_id),
+                realized_pnl: account.realized_pnl,
+                unrealized_pnl: account.unrealized_pnl,
+                total_pnl: account.realized_pnl + account.unrealized_pnl,
+                total_equity: account.total_equity,
+            })
+            .collect();
+        ranking.sort_by_key(|entry| std::cmp::Reverse(entry.total_pnl));
+        for (index, entry) in ranking.iter_mut().enumerate() {
+            entry.rank = index + 1;
+        }
+        ranking
     }
 
     /** 买卖队列 */
-    pub fn get_order_queue(
-        &self,
-        stock_code: StockCode,
-        limit: usize,
-    ) -> (Vec<(Price, u64)>, Vec<(Price, u64)>) {
+    pub fn get_order_queue(&self, stock_code: StockCode, limit: usize) -> OrderQueue {
         let stock = self.stock_manager.get_stock(&stock_code).unwrap();
         let (buy_orders, sell_orders) = &stock.order_queue;
         let buy_orders: Vec<(Price, u64)> = buy_orders.iter().take(limit).cloned().collect();
@@ -364,43 +978,126 @@ impl Exchange {
     pub fn add_robot(
         &mut self,
         user_name: &str,
-        initial_balance: u64,
+        initial_balance: Money,
         strategy: TradingStrategy,
         initial_holdings: Vec<(&str, u64)>,
     ) -> Result<UserId, ExchangeError> {
-        let user_id = self.add_user(user_name, initial_balance)?;
-        let user = self.user_manager.get_user_mut(user_id).unwrap();
-        for (stock_code, quantity) in initial_holdings {
-            user.add_holding(stock_code.to_string(), quantity);
-        }
-        self.bot_manager.add_bot(user_id, strategy);
-        Ok(user_id)
+        let command = Command::AddRobot {
+            username: user_name.to_string(),
+            balance: initial_balance,
+            strategy: strategy.clone(),
+            holdings: initial_holdings
+                .iter()
+                .map(|(stock_code, quantity)| (stock_code.to_string(), *quantity))
+                .collect(),
+        };
+        self.journaled(command, |exchange| {
+            let user_id = exchange.add_user(user_name, initial_balance)?;
+            let user = exchange.user_manager.get_user_mut(user_id).unwrap();
+            for (stock_code, quantity) in initial_holdings {
+                // 初始持仓按股票当前价格计算成本
+                let price = exchange
+                    .stock_manager
+                    .get_stock(&stock_code.to_string())
+                    .map_or(Price::ZERO, |stock| stock.current_price);
+                user.add_holding(stock_code.to_string(), quantity, price * quantity);
+            }
+            let rng = derive_rng(exchange.config.seed(), user_id);
+            exchange.bot_manager.add_bot(user_id, strategy, rng);
+            Ok(user_id)
+        })
     }
 
-    // You might want to add a method to execute all bot strategies
+    /** 执行所有机器人的策略 */
     pub fn execute_robot_strategies(&mut self) -> Result<(), ExchangeError> {
-        let bot_actions = self.bot_manager.execute_strategy(self);
+        // 机器人决策需要读取交易所状态 执行期间暂时取出
+        let mut bot_manager = std::mem::take(&mut self.bot_manager);
+        let bot_actions = bot_manager.execute_strategy(self);
+        self.bot_manager = bot_manager;
 
+        let mut results: BTreeMap<UserId, Vec<ActionResult>> = BTreeMap::new();
         for (user_id, action) in bot_actions {
-            match action {
-                TradingAction::Buy {
-                    stock_code,
-                    price,
-                    quantity,
-                } => {
-                    let _ = self.submit_order(user_id, stock_code, OrderType::Buy, price, quantity);
-                }
-                TradingAction::Sell {
-                    stock_code,
-                    price,
-                    quantity,
-                } => {
-                    let _ =
-                        self.submit_order(user_id, stock_code, OrderType::Sell, price, quantity);
-                }
-                TradingAction::Hold => {}
+            if self.recorder.is_enabled() {
+                self.recorder.record_derived(Command::BotAction {
+                    user_id,
+                    action: action.clone(),
+                });
             }
+            let outcome = match self.execute_bot_action(user_id, &action) {
+                Ok(outcome) => outcome,
+                Err(err) => ActionOutcome::Rejected {
+                    reason: err.to_string(),
+                },
+            };
+            results
+                .entry(user_id)
+                .or_default()
+                .push(ActionResult { action, outcome });
         }
+        self.bot_manager.report_results(results);
         Ok(())
     }
+
+    fn execute_bot_action(
+        &mut self,
+        user_id: UserId,
+        action: &TradingAction,
+    ) -> Result<ActionOutcome, ExchangeError> {
+        match action {
+            TradingAction::Buy {
+                stock_code,
+                price,
+                quantity,
+            } => self
+                .submit_order(
+                    user_id,
+                    stock_code.clone(),
+                    OrderType::Buy,
+                    *price,
+                    *quantity,
+                )
+                .map(|order_id| ActionOutcome::Submitted { order_id }),
+            TradingAction::Sell {
+                stock_code,
+                price,
+                quantity,
+            } => self
+                .submit_order(
+                    user_id,
+                    stock_code.clone(),
+                    OrderType::Sell,
+                    *price,
+                    *quantity,
+                )
+                .map(|order_id| ActionOutcome::Submitted { order_id }),
+            TradingAction::Cancel { order_id } => {
+                self.check_order_owner(user_id, *order_id)?;
+                self.cancel_order(*order_id)
+                    .map(|_| ActionOutcome::Cancelled)
+            }
+            TradingAction::Amend {
+                order_id,
+                price,
+                quantity,
+            } => {
+                self.check_order_owner(user_id, *order_id)?;
+                self.amend_order(*order_id, *price, *quantity)
+                    .map(|order_id| ActionOutcome::Amended { order_id })
+            }
+        }
+    }
+
+    /** 只能撤销或修改自己的委托 其他用户的委托视为不存在 */
+    fn check_order_owner(&self, user_id: UserId, order_id: OrderId) -> Result<(), ExchangeError> {
+        self.order_manager
+            .get_order(order_id)
+            .filter(|order| order.user_id == user_id)
+            .map(|_| ())
+            .ok_or(ExchangeError::OrderNotFound(order_id))
+    }
+}
+
+/** 允许撮合的收盘竞价时段 */
+fn is_matching_closing_auction(period: &TradingPeriod) -> bool {
+    period.period_type == TradingPeriodType::ClosingAuction && period.allow_matching
 }
@@ -1,3 +1,4 @@
+use crate::instrument::RuleViolation;
 use thiserror::Error;
 
 // 错误类型
@@ -41,6 +42,12 @@ pub enum ExchangeError {
 
     #[error("Price out of limit: {0}")]
     PriceOutOfLimit(String),
+
+    #[error("Order rule violation for {stock_code}: {violation}")]
+    OrderRuleViolation {
+        stock_code: String,
+        violation: RuleViolation,
+    },
 }
 
 pub type ExchangeResult<T> = Result<T, ExchangeError>;
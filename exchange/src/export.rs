@@ -0,0 +1,466 @@
+use crate::exchange::Exchange;
+use crate::exchange_error::ExchangeError;
+use crate::order::{Order, OrderStatus, OrderType};
+use crate::stock::PriceHistoryInfo;
+use crate::types::*;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("failed to write export: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("failed to write CSV: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("failed to write JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Exchange(#[from] ExchangeError),
+}
+
+pub type ExportResult<T> = Result<T, ExportError>;
+
+/** 导出格式 */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    #[default]
+    #[serde(rename = "csv")]
+    Csv,
+    /** 每行一个 JSON 对象 */
+    #[serde(rename = "jsonl")]
+    JsonLines,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::JsonLines => "jsonl",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::JsonLines => "application/x-ndjson",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" => Ok(ExportFormat::JsonLines),
+            _ => Err(format!(
+                "unknown export format {}, expected csv or jsonl",
+                s
+            )),
+        }
+    }
+}
+
+enum ExportSink<W: Write> {
+    Csv(Box<csv::Writer<W>>),
+    JsonLines(BufWriter<W>),
+}
+
+/**
+ * 逐行写入导出数据 CSV 的表头由第一行的字段名生成
+ *
+ * 数据按行写入底层的 writer 不在内存中汇总
+ */
+pub struct ExportWriter<W: Write> {
+    sink: ExportSink<W>,
+    rows: usize,
+}
+
+impl ExportWriter<File> {
+    pub fn create(path: impl AsRef<Path>, format: ExportFormat) -> ExportResult<Self> {
+        Ok(Self::new(File::create(path)?, format))
+    }
+}
+
+impl<W: Write> ExportWriter<W> {
+    pub fn new(writer: W, format: ExportFormat) -> Self {
+        let sink = match format {
+            ExportFormat::Csv => ExportSink::Csv(Box::new(csv::Writer::from_writer(writer))),
+            ExportFormat::JsonLines => ExportSink::JsonLines(BufWriter::new(writer)),
+        };
+        ExportWriter { sink, rows: 0 }
+    }
+
+    pub fn write_row<T: Serialize>(&mut self, row: &T) -> ExportResult<()> {
+        match &mut self.sink {
+            ExportSink::Csv(writer) => writer.serialize(row)?,
+            ExportSink::JsonLines(writer) => {
+                serde_json::to_writer(&mut *writer, row)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /** 已写入的行数 */
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn flush(&mut self) -> ExportResult<()> {
+        match &mut self.sink {
+            ExportSink::Csv(writer) => writer.flush()?,
+            ExportSink::JsonLines(writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+
+    /** 写入缓冲的数据 返回底层的 writer */
+    pub fn finish(self) -> ExportResult<W> {
+        let writer = match self.sink {
+            ExportSink::Csv(writer) => writer.into_inner().map_err(|err| err.into_error())?,
+            ExportSink::JsonLines(writer) => writer.into_inner().map_err(|err| err.into_error())?,
+        };
+        Ok(writer)
+    }
+}
+
+/** K线周期 */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandleInterval {
+    Minute,
+    #[default]
+    Day,
+}
+
+/** K线 日线的时间为当日零点 分钟线的开盘价为上一分钟的收盘价 */
+#[derive(Clone, Debug, Serialize)]
+pub struct Candle {
+    pub stock_code: StockCode,
+    pub timestamp: Timestamp,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: u64,
+    pub amount: Money,
+}
+
+/** 委托及成交明细 每笔成交一行 没有成交的委托输出一行 成交字段为空 */
+#[derive(Clone, Debug, Serialize)]
+pub struct OrderRow {
+    pub order_id: OrderId,
+    pub user_id: UserId,
+    pub stock_code: StockCode,
+    pub order_type: OrderType,
+    pub price: Price,
+    pub quantity: Quantity,
+    pub remaining_quantity: Quantity,
+    pub status: OrderStatus,
+    pub submitted_at: Timestamp,
+    pub execution_time: Option<Timestamp>,
+    pub execution_price: Option<Price>,
+    pub execution_quantity: Option<Quantity>,
+    pub commission: Option<Money>,
+    pub stamp_duty: Option<Money>,
+    pub transfer_fee: Option<Money>,
+}
+
+impl OrderRow {
+    /** 委托的全部行 */
+    pub fn rows(order: &Order, status: OrderStatus) -> Vec<OrderRow> {
+        let row = OrderRow {
+            order_id: order.id,
+            user_id: order.user_id,
+            stock_code: order.stock_code.clone(),
+            order_type: order.order_type,
+            price: order.price,
+            quantity: order.quantity,
+            remaining_quantity: order.remaining_quantity,
+            status,
+            submitted_at: order.timestamp,
+            execution_time: None,
+            execution_price: None,
+            execution_quantity: None,
+            commission: None,
+            stamp_duty: None,
+            transfer_fee: None,
+        };
+        if order.executions.is_empty() {
+            return vec![row];
+        }
+        order
+            .executions
+            .iter()
+            .map(|execution| OrderRow {
+                execution_time: Some(execution.timestamp),
+                execution_price: Some(execution.price),
+                execution_quantity: Some(execution.quantity),
+                commission: Some(execution.fees.commission),
+                stamp_duty: Some(execution.fees.stamp_duty),
+                transfer_fee: Some(execution.fees.transfer_fee),
+                ..row.clone()
+            })
+            .collect()
+    }
+}
+
+/** 每日对账单 由收盘时的盈亏快照生成 */
+#[derive(Clone, Debug, Serialize)]
+pub struct AccountStatement {
+    pub trade_day: NaiveDate,
+    pub user_id: UserId,
+    pub username: String,
+    pub cash: Money,
+    pub market_value: Money,
+    pub total_equity: Money,
+    pub realized_pnl: Money,
+    pub unrealized_pnl: Money,
+    pub total_pnl: Money,
+    pub daily_pnl: Money,
+    /** 累计交易费用 */
+    pub fees: Money,
+}
+
+/** 写入收盘清除的委托 收盘时仍未成交的部分记为过期 */
+pub fn export_archived_orders<W: Write>(
+    orders: &[Order],
+    writer: &mut ExportWriter<W>,
+) -> ExportResult<()> {
+    for order in orders {
+        let status = if order.remaining_quantity > 0 {
+            OrderStatus::Expired
+        } else {
+            order.status()
+        };
+        for row in OrderRow::rows(order, status) {
+            writer.write_row(&row)?;
+        }
+    }
+    Ok(())
+}
+
+/**
+ * 从保留委托的文件 (JSON Lines 每行一条委托) 导出 位置为文件中的字节偏移
+ *
+ * 文件由 SpillWriter 写入收盘清除的委托 文件不存在时视为没有委托
+ */
+pub fn export_order_archive<W: Write>(
+    path: &Path,
+    user_id: Option<UserId>,
+    from: u64,
+    limit: usize,
+    writer: &mut ExportWriter<W>,
+) -> ExportResult<Option<u64>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let mut reader = BufReader::new(file);
+    reader.seek(SeekFrom::Start(from))?;
+    let mut offset = from;
+    let mut line = String::new();
+    for _ in 0..limit {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        // 只读取完整的行
+        if read == 0 || !line.ends_with('\n') {
+            return Ok(None);
+        }
+        offset += read as u64;
+        let order: Order = serde_json::from_str(&line)?;
+        if user_id.is_none_or(|user_id| order.user_id == user_id) {
+            export_archived_orders(std::slice::from_ref(&order), writer)?;
+        }
+    }
+    Ok(Some(offset))
+}
+
+/**
+ * 按批导出 数据量大时可分多批写入 批次之间不需要持有交易所
+ *
+ * from 为本批的起始位置 limit 为本批最多读取的记录数 返回下一批的起始位置 全部导出后返回 None
+ */
+impl Exchange {
+    /** 导出内存中的成交记录 按编号递增 位置为成交编号 */
+    pub fn export_trades<W: Write>(
+        &self,
+        stock_code: Option<&str>,
+        user_id: Option<UserId>,
+        from: TradeId,
+        limit: usize,
+        writer: &mut ExportWriter<W>,
+    ) -> ExportResult<Option<TradeId>> {
+        for (count, log) in self.log_manager.iter_from(from).enumerate() {
+            if count == limit {
+                return Ok(Some(log.id));
+            }
+            let stock_matches = stock_code.is_none_or(|code| log.stock_code == code);
+            let user_matches =
+                user_id.is_none_or(|user_id| log.buyer_id == user_id || log.seller_id == user_id);
+            if stock_matches && user_matches {
+                writer.write_row(log)?;
+            }
+        }
+        Ok(None)
+    }
+
+    /** 导出股票的K线 位置为价格历史的下标 */
+    pub fn export_candles<W: Write>(
+        &self,
+        stock_code: &str,
+        interval: CandleInterval,
+        from: u64,
+        limit: usize,
+        writer: &mut ExportWriter<W>,
+    ) -> ExportResult<Option<u64>> {
+        let stock = self
+            .stock_manager
+            .get_stock(&stock_code.to_string())
+            .ok_or_else(|| ExchangeError::StockNotFound(stock_code.to_string()))?;
+        let history = &stock.price_history;
+        let mut index = from as usize;
+        let mut count = 0;
+        while index < history.len() {
+            if count == limit {
+                return Ok(Some(index as u64));
+            }
+            let (candle, next) = match interval {
+                CandleInterval::Minute => (minute_candle(stock_code, history, index), index + 1),
+                CandleInterval::Day => daily_candle(stock_code, history, index),
+            };
+            writer.write_row(&candle)?;
+            index = next;
+            count += 1;
+        }
+        Ok(None)
+    }
+
+    /** 导出当日委托及成交明细 位置为委托编号 收盘清除的委托通过 drain_archived_orders 取出 */
+    pub fn export_orders<W: Write>(
+        &self,
+        user_id: Option<UserId>,
+        from: OrderId,
+        limit: usize,
+        writer: &mut ExportWriter<W>,
+    ) -> ExportResult<Option<OrderId>> {
+        for (count, order) in self.order_manager.iter_from(from).enumerate() {
+            if count == limit {
+                return Ok(Some(order.id));
+            }
+            if user_id.is_none_or(|user_id| order.user_id == user_id) {
+                for row in OrderRow::rows(order, order.status()) {
+                    writer.write_row(&row)?;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /** 导出每日对账单 按用户及日期排列 位置为用户ID */
+    pub fn export_statements<W: Write>(
+        &self,
+        user_id: Option<UserId>,
+        from: UserId,
+        limit: usize,
+        writer: &mut ExportWriter<W>,
+    ) -> ExportResult<Option<UserId>> {
+        let user_ids = match user_id {
+            Some(user_id) => vec![user_id],
+            None => self.user_manager.get_user_ids(),
+        };
+        for (count, user_id) in user_ids
+            .into_iter()
+            .filter(|user_id| *user_id >= from)
+            .enumerate()
+        {
+            if count == limit {
+                return Ok(Some(user_id));
+            }
+            let user = self
+                .user_manager
+                .get_user(user_id)
+                .ok_or(ExchangeError::UserNotFound(user_id))?;
+            for snapshot in &user.pnl_history {
+                writer.write_row(&AccountStatement {
+                    trade_day: snapshot.trade_day,
+                    user_id,
+                    username: user.username.clone(),
+                    cash: snapshot.cash,
+                    market_value: snapshot.market_value,
+                    total_equity: snapshot.total_equity,
+                    realized_pnl: snapshot.realized_pnl,
+                    unrealized_pnl: snapshot.unrealized_pnl,
+                    total_pnl: snapshot.total_pnl,
+                    daily_pnl: snapshot.daily_pnl,
+                    fees: snapshot.fees,
+                })?;
+            }
+        }
+        Ok(None)
+    }
+
+    /** 开始保留收盘清除的委托 由外部取出导出 */
+    pub fn enable_order_archive(&mut self) {
+        self.order_manager.enable_archive();
+    }
+
+    /** 取出收盘清除的委托 */
+    pub fn drain_archived_orders(&mut self) -> Vec<Order> {
+        self.order_manager.drain_archived()
+    }
+}
+
+fn minute_candle(stock_code: &str, history: &[PriceHistoryInfo], index: usize) -> Candle {
+    let item = &history[index];
+    Candle {
+        stock_code: stock_code.to_string(),
+        timestamp: item.timestamp,
+        open: item.open_price,
+        high: item.max_price,
+        low: item.min_price,
+        close: item.price,
+        volume: item.volume as u64,
+        amount: item.amount,
+    }
+}
+
+/** 汇总从 start 起同一日期的价格历史 返回日K线及下一日的起始下标 */
+fn daily_candle(stock_code: &str, history: &[PriceHistoryInfo], start: usize) -> (Candle, usize) {
+    let first = &history[start];
+    let mut candle = Candle {
+        stock_code: stock_code.to_string(),
+        timestamp: Timestamp::start_of_day(first.timestamp.date()),
+        open: first.open_price,
+        high: first.max_price,
+        low: first.min_price,
+        close: first.price,
+        volume: 0,
+        amount: Money::ZERO,
+    };
+    let mut index = start;
+    while let Some(item) = history
+        .get(index)
+        .filter(|item| item.timestamp.date() == first.timestamp.date())
+    {
+        candle.high = candle.high.max(item.max_price);
+        candle.low = candle.low.min(item.min_price);
+        candle.close = item.price;
+        candle.volume += item.volume as u64;
+        candle.amount += item.amount;
+        index += 1;
+    }
+    (candle, index)
+}
@@ -0,0 +1,137 @@
+use crate::order::OrderType;
+use crate::types::Money;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/** 费用表 */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    /** 佣金费率 */
+    pub commission_rate: f64,
+    /** 每笔委托最低佣金 */
+    pub min_commission: Money,
+    /** 印花税率 仅卖出收取 */
+    pub stamp_duty_rate: f64,
+    /** 过户费率 */
+    pub transfer_fee_rate: f64,
+    /** 用户佣金档位 按档位名称索引 */
+    pub tiers: HashMap<String, FeeTier>,
+}
+
+/** 佣金档位 */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub commission_rate: f64,
+    pub min_commission: Money,
+}
+
+/** 单笔成交的费用 */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fees {
+    /** 佣金 */
+    pub commission: Money,
+    /** 印花税 */
+    pub stamp_duty: Money,
+    /** 过户费 */
+    pub transfer_fee: Money,
+}
+
+impl Fees {
+    pub fn total(&self) -> Money {
+        self.commission + self.stamp_duty + self.transfer_fee
+    }
+}
+
+/** 用户费用汇总 */
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FeeReport {
+    pub commission: Money,
+    pub stamp_duty: Money,
+    pub transfer_fee: Money,
+    pub total: Money,
+    /** 成交笔数 */
+    pub execution_count: u64,
+}
+
+impl FeeReport {
+    pub fn add(&mut self, fees: &Fees) {
+        self.commission += fees.commission;
+        self.stamp_duty += fees.stamp_duty;
+        self.transfer_fee += fees.transfer_fee;
+        self.total += fees.total();
+        self.execution_count += 1;
+    }
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self::a_share()
+    }
+}
+
+impl FeeSchedule {
+    /** A股费用 佣金万分之2.5 最低5元 印花税卖出千分之0.5 过户费十万分之1 */
+    pub fn a_share() -> Self {
+        FeeSchedule {
+            commission_rate: 0.00025,
+            min_commission: Money::from_yuan(5),
+            stamp_duty_rate: 0.0005,
+            transfer_fee_rate: 0.00001,
+            tiers: HashMap::new(),
+        }
+    }
+
+    /** 不收取任何费用 */
+    pub fn zero() -> Self {
+        FeeSchedule {
+            commission_rate: 0.0,
+            min_commission: Money::ZERO,
+            stamp_duty_rate: 0.0,
+            transfer_fee_rate: 0.0,
+            tiers: HashMap::new(),
+        }
+    }
+
+    /** 用户适用的佣金费率及最低佣金 未设置档位或档位不存在时使用默认值 */
+    fn commission_terms(&self, tier: Option<&str>) -> (f64, Money) {
+        tier.and_then(|name| self.tiers.get(name))
+            .map_or((self.commission_rate, self.min_commission), |tier| {
+                (tier.commission_rate, tier.min_commission)
+            })
+    }
+
+    /**
+     * 计算一笔成交的费用
+     *
+     * 最低佣金按委托计算: 委托累计佣金为 max(累计成交额 * 费率, 最低佣金)，
+     * 每笔成交只收取与已收佣金的差额
+     */
+    pub fn calculate(
+        &self,
+        order_type: OrderType,
+        amount: Money,
+        filled_amount_before: Money,
+        commission_paid: Money,
+        tier: Option<&str>,
+    ) -> Fees {
+        let (commission_rate, min_commission) = self.commission_terms(tier);
+        let total_commission = (filled_amount_before + amount)
+            .mul_rate(commission_rate)
+            .max(min_commission);
+        let stamp_duty = match order_type {
+            OrderType::Buy => Money::ZERO,
+            OrderType::Sell => amount.mul_rate(self.stamp_duty_rate),
+        };
+        Fees {
+            commission: total_commission.saturating_sub(commission_paid),
+            stamp_duty,
+            transfer_fee: amount.mul_rate(self.transfer_fee_rate),
+        }
+    }
+
+    /** 按委托金额预估费用上限 用于买入委托冻结资金 */
+    pub fn estimate(&self, order_type: OrderType, amount: Money, tier: Option<&str>) -> Money {
+        self.calculate(order_type, amount, Money::ZERO, Money::ZERO, tier)
+            .total()
+    }
+}
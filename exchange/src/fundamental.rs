@@ -0,0 +1,246 @@
+use crate::config::TradingPeriodType;
+use crate::exchange::Exchange;
+use crate::exchange_error::{ExchangeError, ExchangeResult};
+use crate::journal::Command;
+use crate::random::{derive_rng, poisson, standard_normal, SimRng, FUNDAMENTAL_STREAM};
+use crate::simulation::ROBOT_INTERVAL;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/**
+ * 基本面价值过程参数 几何布朗运动叠加对数正态跳跃
+ *
+ * 时间以交易日计 价值只在连续竞价期间变化
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FundamentalParams {
+    /** 每个交易日的对数漂移 */
+    pub drift: f64,
+    /** 每个交易日的波动率 */
+    pub volatility: f64,
+    /** 每个交易日跳跃次数的期望 */
+    pub jump_intensity: f64,
+    /** 跳跃幅度 对数收益的均值 */
+    pub jump_mean: f64,
+    /** 跳跃幅度 对数收益的标准差 */
+    pub jump_volatility: f64,
+}
+
+/** 每分钟的基本面价值及市场价格 */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FundamentalSample {
+    pub timestamp: Timestamp,
+    pub value: Price,
+    pub market_price: Price,
+}
+
+/** 一只股票的基本面价值及定价误差统计 */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FundamentalValue {
+    /** 当前价值 按元计 */
+    value: f64,
+    /** 按分钟记录 */
+    history: Vec<FundamentalSample>,
+    samples: u64,
+    /** 市场价格相对价值偏离比例的绝对值之和 */
+    absolute_error: f64,
+    /** 对数偏离的平方和 */
+    squared_log_error: f64,
+}
+
+/**
+ * 市场效率报告 市场价格偏离基本面价值的程度
+ *
+ * 误差越小 价格发现越有效
+ */
+#[derive(Clone, Debug, Serialize)]
+pub struct EfficiencyReport {
+    pub stock_code: StockCode,
+    pub fundamental_value: Price,
+    pub market_price: Price,
+    /** 采样次数 每个机器人执行间隔一次 */
+    pub samples: u64,
+    /** 平均绝对偏离比例 */
+    pub mean_absolute_error: f64,
+    /** 对数偏离的均方根 */
+    pub root_mean_squared_log_error: f64,
+}
+
+/** 各股票的基本面价值过程 随快照保存 */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FundamentalProcess {
+    pub params: FundamentalParams,
+    rng: SimRng,
+    values: BTreeMap<StockCode, FundamentalValue>,
+}
+
+impl FundamentalProcess {
+    pub fn value(&self, stock_code: &str) -> Option<Price> {
+        self.values
+            .get(stock_code)
+            .map(|value| to_price(value.value))
+    }
+
+    /** 每分钟的价值及市场价格 */
+    pub fn history(&self, stock_code: &str) -> Option<&[FundamentalSample]> {
+        self.values
+            .get(stock_code)
+            .map(|value| value.history.as_slice())
+    }
+
+    /** 推进 dt 个交易日 */
+    fn step(&mut self, dt: f64) {
+        let params = &self.params;
+        let diffusion = (params.drift - params.volatility.powi(2) / 2.0) * dt;
+        for value in self.values.values_mut() {
+            let mut log_return =
+                diffusion + params.volatility * dt.sqrt() * standard_normal(&mut self.rng);
+            if params.jump_intensity > 0.0 {
+                for _ in 0..poisson(&mut self.rng, params.jump_intensity * dt) {
+                    log_return +=
+                        params.jump_mean + params.jump_volatility * standard_normal(&mut self.rng);
+                }
+            }
+            value.value *= log_return.exp();
+        }
+    }
+
+    /** 记录市场价格与价值的偏离 */
+    fn sample(&mut self, stock_code: &str, timestamp: Timestamp, market_price: Price) {
+        let Some(value) = self.values.get_mut(stock_code) else {
+            return;
+        };
+        let fundamental = to_price(value.value);
+        if !market_price.is_zero() && value.value > 0.0 {
+            let ratio = market_price.as_f64() / value.value;
+            value.samples += 1;
+            value.absolute_error += (ratio - 1.0).abs();
+            value.squared_log_error += ratio.ln().powi(2);
+        }
+
+        let minute = timestamp.floor_to_minute();
+        let sample = FundamentalSample {
+            timestamp: minute,
+            value: fundamental,
+            market_price,
+        };
+        match value.history.last_mut() {
+            Some(last) if last.timestamp == minute => *last = sample,
+            _ => value.history.push(sample),
+        }
+    }
+}
+
+fn to_price(value: f64) -> Price {
+    Price::from_raw(
+        (value * PRICE_SCALE as f64)
+            .round()
+            .clamp(0.0, u32::MAX as f64) as u32,
+    )
+}
+
+impl Exchange {
+    /**
+     * 为股票启用基本面价值过程 初始价值为当前价格
+     *
+     * 随机数由主种子派生 相同种子的价值路径相同
+     */
+    pub fn start_fundamental(
+        &mut self,
+        params: FundamentalParams,
+        stock_codes: Vec<StockCode>,
+    ) -> ExchangeResult<()> {
+        let command = Command::StartFundamental {
+            params: params.clone(),
+            stock_codes: stock_codes.clone(),
+        };
+        self.journaled(command, |exchange| {
+            let mut values = BTreeMap::new();
+            for stock_code in stock_codes {
+                let stock = exchange
+                    .stock_manager
+                    .get_stock(&stock_code)
+                    .ok_or_else(|| ExchangeError::StockNotFound(stock_code.clone()))?;
+                let value = FundamentalValue {
+                    value: stock.current_price.as_f64(),
+                    history: Vec::new(),
+                    samples: 0,
+                    absolute_error: 0.0,
+                    squared_log_error: 0.0,
+                };
+                values.insert(stock_code, value);
+            }
+            exchange.fundamental = Some(FundamentalProcess {
+                params,
+                rng: derive_rng(exchange.config.seed(), FUNDAMENTAL_STREAM),
+                values,
+            });
+            Ok(())
+        })
+    }
+
+    /** 某只股票当前的基本面价值 未启用时返回 None */
+    pub fn fundamental_value(&self, stock_code: &str) -> Option<Price> {
+        self.fundamental.as_ref()?.value(stock_code)
+    }
+
+    /** 各股票的市场效率 按股票代码排列 */
+    pub fn market_efficiency(&self) -> Vec<EfficiencyReport> {
+        let Some(fundamental) = &self.fundamental else {
+            return Vec::new();
+        };
+        fundamental
+            .values
+            .iter()
+            .map(|(stock_code, value)| {
+                let samples = value.samples.max(1) as f64;
+                EfficiencyReport {
+                    stock_code: stock_code.clone(),
+                    fundamental_value: to_price(value.value),
+                    market_price: self
+                        .stock_manager
+                        .get_stock(stock_code)
+                        .map_or(Price::ZERO, |stock| stock.current_price),
+                    samples: value.samples,
+                    mean_absolute_error: value.absolute_error / samples,
+                    root_mean_squared_log_error: (value.squared_log_error / samples).sqrt(),
+                }
+            })
+            .collect()
+    }
+
+    /**
+     * 基本面价值推进一个机器人执行间隔 连续竞价期间在机器人决策前执行
+     *
+     * 推进后记录各股票市场价格与价值的偏离
+     */
+    pub(crate) fn update_fundamental(&mut self) {
+        let Some(mut fundamental) = self.fundamental.take() else {
+            return;
+        };
+        let continuous = self
+            .config
+            .get_current_period()
+            .is_some_and(|period| period.period_type == TradingPeriodType::ContinuousTrading);
+        // 一个交易日按连续竞价的总时长计
+        let day_length: TimeOfDay = self
+            .config
+            .trading_periods
+            .iter()
+            .filter(|period| period.period_type == TradingPeriodType::ContinuousTrading)
+            .map(|period| period.end_tick - period.start_tick)
+            .sum();
+        if continuous && day_length > 0 {
+            fundamental.step(ROBOT_INTERVAL as f64 / day_length as f64);
+            let timestamp = self.config.current_timestamp;
+            let stock_codes: Vec<StockCode> = fundamental.values.keys().cloned().collect();
+            for stock_code in stock_codes {
+                if let Some(stock) = self.stock_manager.get_stock(&stock_code) {
+                    fundamental.sample(&stock_code, timestamp, stock.current_price);
+                }
+            }
+        }
+        self.fundamental = Some(fundamental);
+    }
+}
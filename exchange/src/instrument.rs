@@ -0,0 +1,275 @@
+use crate::order::OrderType;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/** 交易板块 */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Board {
+    /** 主板 */
+    Main,
+    /** 创业板 */
+    ChiNext,
+    /** 科创板 */
+    Star,
+}
+
+impl Board {
+    /** 根据股票代码前缀判断所属板块 */
+    pub fn from_code(code: &str) -> Self {
+        if code.starts_with("688") || code.starts_with("689") {
+            Board::Star
+        } else if code.starts_with("300") || code.starts_with("301") {
+            Board::ChiNext
+        } else {
+            Board::Main
+        }
+    }
+}
+
+/** 零股处理方式 */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OddLotRule {
+    /** 零股只能一次性全部卖出 */
+    SellAllAtOnce,
+    /** 不允许零股委托 */
+    Reject,
+}
+
+/** 交易规则 */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstrumentRules {
+    /** 最小价格变动单位 */
+    pub tick_size: Price,
+    /** 单笔最小委托数量 */
+    pub min_quantity: Quantity,
+    /** 单笔最大委托数量 */
+    pub max_quantity: Quantity,
+    /** 超过最小委托数量后的递增单位 */
+    pub quantity_step: Quantity,
+    pub odd_lot: OddLotRule,
+}
+
+/** 违反交易规则的原因 */
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum RuleViolation {
+    #[error("price {price} is not a multiple of tick size {tick_size}")]
+    InvalidTick { price: Price, tick_size: Price },
+
+    #[error("quantity {quantity} is below minimum {min_quantity}")]
+    BelowMinimum {
+        quantity: Quantity,
+        min_quantity: Quantity,
+    },
+
+    #[error("quantity {quantity} exceeds maximum {max_quantity}")]
+    AboveMaximum {
+        quantity: Quantity,
+        max_quantity: Quantity,
+    },
+
+    #[error("quantity {quantity} is not a multiple of {quantity_step}")]
+    InvalidStep {
+        quantity: Quantity,
+        quantity_step: Quantity,
+    },
+
+    #[error("odd lot of {odd_lot} must be sold at once")]
+    OddLotNotWhole { odd_lot: Quantity },
+}
+
+impl InstrumentRules {
+    /** 主板 100股整数倍 单笔不超过100万股 */
+    pub fn main_board() -> Self {
+        InstrumentRules {
+            tick_size: Price::from_fen(1),
+            min_quantity: 100,
+            max_quantity: 1_000_000,
+            quantity_step: 100,
+            odd_lot: OddLotRule::SellAllAtOnce,
+        }
+    }
+
+    /** 创业板 100股整数倍 单笔不超过30万股 */
+    pub fn chinext() -> Self {
+        InstrumentRules {
+            max_quantity: 300_000,
+            ..Self::main_board()
+        }
+    }
+
+    /** 科创板 最少200股 以1股递增 单笔不超过10万股 */
+    pub fn star_market() -> Self {
+        InstrumentRules {
+            tick_size: Price::from_fen(1),
+            min_quantity: 200,
+            max_quantity: 100_000,
+            quantity_step: 1,
+            odd_lot: OddLotRule::SellAllAtOnce,
+        }
+    }
+
+    /** 港股 以每手100股为例 零股不能在主板申报 */
+    pub fn hkex() -> Self {
+        InstrumentRules {
+            odd_lot: OddLotRule::Reject,
+            ..Self::main_board()
+        }
+    }
+
+    /** 美股 以1股为单位 */
+    pub fn us_equity() -> Self {
+        InstrumentRules {
+            tick_size: Price::from_fen(1),
+            min_quantity: 1,
+            max_quantity: 1_000_000,
+            quantity_step: 1,
+            odd_lot: OddLotRule::SellAllAtOnce,
+        }
+    }
+
+    /** 加密货币 以最小单位计数 价格精确到厘 */
+    pub fn crypto() -> Self {
+        InstrumentRules {
+            tick_size: Price::from_raw(1),
+            max_quantity: Quantity::MAX,
+            ..Self::us_equity()
+        }
+    }
+
+    /** 是否为符合最小数量及递增单位的整手数量 */
+    fn is_round_quantity(&self, quantity: Quantity) -> bool {
+        quantity >= self.min_quantity
+            && (quantity - self.min_quantity).is_multiple_of(self.quantity_step)
+    }
+
+    /** 可卖持仓中无法按整手卖出的零股数量 */
+    pub fn odd_lot(&self, available: u64) -> Quantity {
+        let available = available.min(Quantity::MAX as u64) as Quantity;
+        if available < self.min_quantity {
+            available
+        } else {
+            (available - self.min_quantity) % self.quantity_step.max(1)
+        }
+    }
+
+    /** 校验委托价格及数量 卖出时需传入可卖持仓用于判断零股 */
+    pub fn validate(
+        &self,
+        order_type: OrderType,
+        price: Price,
+        quantity: Quantity,
+        available: u64,
+    ) -> Result<(), RuleViolation> {
+        if self.tick_size.raw() > 1 && !price.raw().is_multiple_of(self.tick_size.raw()) {
+            return Err(RuleViolation::InvalidTick {
+                price,
+                tick_size: self.tick_size,
+            });
+        }
+        if quantity > self.max_quantity {
+            return Err(RuleViolation::AboveMaximum {
+                quantity,
+                max_quantity: self.max_quantity,
+            });
+        }
+        if self.is_round_quantity(quantity) {
+            return Ok(());
+        }
+
+        // 卖出时零股需一次性卖出 可与整手部分合并申报
+        let odd_lot = self.odd_lot(available);
+        if order_type == OrderType::Sell
+            && self.odd_lot == OddLotRule::SellAllAtOnce
+            && odd_lot > 0
+            && quantity >= odd_lot
+        {
+            let round_part = quantity - odd_lot;
+            if round_part == 0 || self.is_round_quantity(round_part) {
+                return Ok(());
+            }
+            if quantity < odd_lot + self.min_quantity {
+                return Err(RuleViolation::OddLotNotWhole { odd_lot });
+            }
+        }
+
+        if quantity < self.min_quantity {
+            if order_type == OrderType::Sell && odd_lot > 0 {
+                return Err(RuleViolation::OddLotNotWhole { odd_lot });
+            }
+            return Err(RuleViolation::BelowMinimum {
+                quantity,
+                min_quantity: self.min_quantity,
+            });
+        }
+        Err(RuleViolation::InvalidStep {
+            quantity,
+            quantity_step: self.quantity_step,
+        })
+    }
+
+    /** 将价格向下对齐到最小价格变动单位 */
+    pub fn align_price(&self, price: Price) -> Price {
+        let tick_size = self.tick_size.raw().max(1);
+        Price::from_raw(price.raw() - price.raw() % tick_size)
+    }
+
+    /** 将买入数量向下调整为合法数量 不足最小委托数量时返回0 */
+    pub fn round_buy_quantity(&self, quantity: Quantity) -> Quantity {
+        let quantity = quantity.min(self.max_quantity);
+        if quantity < self.min_quantity {
+            return 0;
+        }
+        quantity - (quantity - self.min_quantity) % self.quantity_step.max(1)
+    }
+
+    /** 将卖出数量调整为合法数量 卖出全部可卖持仓时包含零股 */
+    pub fn round_sell_quantity(&self, quantity: Quantity, available: u64) -> Quantity {
+        let odd_lot = self.odd_lot(available);
+        if quantity as u64 >= available && quantity <= self.max_quantity {
+            return available as Quantity;
+        }
+        match self.round_buy_quantity(quantity) {
+            0 => odd_lot,
+            quantity => quantity,
+        }
+    }
+}
+
+/** 各板块交易规则表 */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstrumentRulesTable {
+    pub boards: HashMap<Board, InstrumentRules>,
+}
+
+impl Default for InstrumentRulesTable {
+    fn default() -> Self {
+        Self::a_share()
+    }
+}
+
+impl InstrumentRulesTable {
+    /** A股主板 创业板 科创板规则 */
+    pub fn a_share() -> Self {
+        let mut boards = HashMap::new();
+        boards.insert(Board::Main, InstrumentRules::main_board());
+        boards.insert(Board::ChiNext, InstrumentRules::chinext());
+        boards.insert(Board::Star, InstrumentRules::star_market());
+        InstrumentRulesTable { boards }
+    }
+
+    /** 所有板块使用同一规则 */
+    pub fn uniform(rules: InstrumentRules) -> Self {
+        let boards = [Board::Main, Board::ChiNext, Board::Star]
+            .into_iter()
+            .map(|board| (board, rules.clone()))
+            .collect();
+        InstrumentRulesTable { boards }
+    }
+
+    /** 板块规则 未配置的板块不做限制 */
+    pub fn get(&self, board: Board) -> Option<&InstrumentRules> {
+        self.boards.get(&board)
+    }
+}
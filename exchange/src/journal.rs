@@ -0,0 +1,410 @@
+use crate::clock::SimulationClock;
+use crate::exchange::Exchange;
+use crate::exchange_error::ExchangeResult;
+use crate::fundamental::FundamentalParams;
+use crate::market_data::MarketData;
+use crate::order::OrderType;
+use crate::trading_strategy::{TradingAction, TradingStrategy};
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use thiserror::Error;
+
+/** 日志文件格式版本 格式不兼容时递增 */
+pub const JOURNAL_VERSION: u32 = 4;
+
+#[derive(Error, Debug)]
+pub enum JournalError {
+    #[error("failed to access journal file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("invalid journal line {line}: {source}")]
+    Format {
+        line: usize,
+        source: serde_json::Error,
+    },
+
+    #[error("journal is empty")]
+    MissingHeader,
+
+    #[error("unsupported journal version {0}, expected {JOURNAL_VERSION}")]
+    UnsupportedVersion(u32),
+
+    #[error("journal entry {found} out of sequence, expected {expected}")]
+    OutOfSequence { expected: u64, found: u64 },
+
+    #[error(
+        "replay diverged at entry {seq}: journal has {expected:?}, replay produced {actual:?}"
+    )]
+    Diverged {
+        seq: u64,
+        expected: Option<Box<Command>>,
+        actual: Option<Box<Command>>,
+    },
+}
+
+/**
+ * 改变交易所状态的命令
+ *
+ * 机器人决策 (BotAction) 由时钟推进产生 仅用于审计 重放时重新产生并与日志比较
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Command {
+    AddStock {
+        code: StockCode,
+        name: String,
+        price: Price,
+    },
+    AddUser {
+        username: String,
+        balance: Money,
+    },
+    AddRobot {
+        username: String,
+        balance: Money,
+        strategy: TradingStrategy,
+        holdings: Vec<(StockCode, u64)>,
+    },
+    Deposit {
+        user_id: UserId,
+        amount: Money,
+    },
+    SetFeeTier {
+        user_id: UserId,
+        tier: Option<String>,
+    },
+    Submit {
+        user_id: UserId,
+        stock_code: StockCode,
+        order_type: OrderType,
+        price: Price,
+        quantity: Quantity,
+    },
+    Cancel {
+        order_id: OrderId,
+    },
+    Amend {
+        order_id: OrderId,
+        price: Price,
+        quantity: Quantity,
+    },
+    AdvanceTo {
+        timestamp: Timestamp,
+    },
+    Rollover,
+    /** 时钟推进一步 记录时钟每步推进的模拟时间 重放时按相同步长推进 */
+    Tick {
+        step: TimeOfDay,
+    },
+    /** 导入历史行情 */
+    ImportMarketData {
+        data: MarketData,
+        replay: bool,
+    },
+    BotAction {
+        user_id: UserId,
+        action: TradingAction,
+    },
+    /** 启用基本面价值过程 */
+    StartFundamental {
+        params: FundamentalParams,
+        stock_codes: Vec<StockCode>,
+    },
+}
+
+impl Command {
+    /** 是否由其他命令产生 */
+    pub fn is_derived(&self) -> bool {
+        matches!(self, Command::BotAction { .. })
+    }
+}
+
+/**
+ * 命令记录 交易所执行命令时写入 由外部定期取出写入日志文件
+ *
+ * 只记录最外层的命令 命令执行过程中的内部调用 (如机器人下单、时钟推进中的换日) 不重复记录
+ */
+#[derive(Default)]
+pub struct CommandRecorder {
+    enabled: bool,
+    depth: u32,
+    pending: Vec<Command>,
+}
+
+impl CommandRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /** 开始执行命令 */
+    pub(crate) fn enter(&mut self, command: Command) {
+        if self.enabled && self.depth == 0 {
+            self.pending.push(command);
+        }
+        self.depth += 1;
+    }
+
+    /** 命令执行结束 */
+    pub(crate) fn exit(&mut self) {
+        self.depth -= 1;
+    }
+
+    /** 记录由当前命令产生的命令 */
+    pub(crate) fn record_derived(&mut self, command: Command) {
+        if self.enabled {
+            self.pending.push(command);
+        }
+    }
+
+    /** 取出待写入的命令 */
+    pub fn drain(&mut self) -> Vec<Command> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+impl Exchange {
+    /** 开始记录命令 */
+    pub fn enable_journal(&mut self) {
+        self.recorder.enable();
+    }
+
+    /** 取出待写入日志的命令 */
+    pub fn drain_journal(&mut self) -> Vec<Command> {
+        self.recorder.drain()
+    }
+
+    /** 执行命令 由其他命令产生的命令不执行 */
+    pub fn apply(&mut self, command: &Command) -> ExchangeResult<()> {
+        match command.clone() {
+            Command::AddStock { code, name, price } => self.add_stock(&code, &name, price),
+            Command::AddUser { username, balance } => self.add_user(&username, balance).map(|_| ()),
+            Command::AddRobot {
+                username,
+                balance,
+                strategy,
+                holdings,
+            } => {
+                let holdings = holdings
+                    .iter()
+                    .map(|(code, quantity)| (code.as_str(), *quantity))
+                    .collect();
+                self.add_robot(&username, balance, strategy, holdings)
+                    .map(|_| ())
+            }
+            Command::Deposit { user_id, amount } => self.deposit(user_id, amount),
+            Command::SetFeeTier { user_id, tier } => self.set_user_fee_tier(user_id, tier),
+            Command::Submit {
+                user_id,
+                stock_code,
+                order_type,
+                price,
+                quantity,
+            } => self
+                .submit_order(user_id, stock_code, order_type, price, quantity)
+                .map(|_| ()),
+            Command::Cancel { order_id } => self.cancel_order(order_id),
+            Command::Amend {
+                order_id,
+                price,
+                quantity,
+            } => self.amend_order(order_id, price, quantity).map(|_| ()),
+            Command::AdvanceTo { timestamp } => {
+                self.advance_to(timestamp);
+                Ok(())
+            }
+            Command::Rollover => {
+                self.next_trade_day();
+                Ok(())
+            }
+            Command::Tick { step } => self.tick(&SimulationClock::with_step(step)).map(|_| ()),
+            Command::ImportMarketData { data, replay } => self.import_market_data(data, replay),
+            Command::BotAction { .. } => Ok(()),
+            Command::StartFundamental {
+                params,
+                stock_codes,
+            } => self.start_fundamental(params, stock_codes),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HeaderRef<'a> {
+    version: u32,
+    exchange: &'a Exchange,
+}
+
+/** 日志首行 记录开始时交易所的状态 */
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+    exchange: Exchange,
+}
+
+#[derive(Serialize)]
+struct EntryRef<'a> {
+    seq: u64,
+    command: &'a Command,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    seq: u64,
+    command: Command,
+}
+
+/**
+ * 只追加的命令日志
+ *
+ * 每行一个 JSON 首行为开始记录时交易所的完整状态 之后每行为一条命令
+ * 命令在返回结果前写入 进程崩溃后可重放恢复
+ */
+pub struct JournalWriter {
+    writer: BufWriter<File>,
+    next_seq: u64,
+}
+
+impl JournalWriter {
+    /** 新建日志 以交易所当前状态作为初始状态 */
+    pub fn create(path: impl AsRef<Path>, exchange: &Exchange) -> Result<Self, JournalError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        let header = HeaderRef {
+            version: JOURNAL_VERSION,
+            exchange,
+        };
+        serde_json::to_writer(&mut writer, &header).map_err(io::Error::from)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        Ok(JournalWriter {
+            writer,
+            next_seq: 1,
+        })
+    }
+
+    /** 在重放过的日志后继续写入 丢弃末尾写入中断的残缺记录 */
+    pub fn resume(path: impl AsRef<Path>, replay: &Replay) -> Result<Self, JournalError> {
+        let file = OpenOptions::new().append(true).open(path)?;
+        file.set_len(replay.valid_len)?;
+        Ok(JournalWriter {
+            writer: BufWriter::new(file),
+            next_seq: replay.entries + 1,
+        })
+    }
+
+    /** 写入命令并刷新到文件 */
+    pub fn write(&mut self, commands: &[Command]) -> Result<(), JournalError> {
+        for command in commands {
+            let entry = EntryRef {
+                seq: self.next_seq,
+                command,
+            };
+            serde_json::to_writer(&mut self.writer, &entry).map_err(io::Error::from)?;
+            self.writer.write_all(b"\n")?;
+            self.next_seq += 1;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/** 读取一行 末尾没有换行的残缺记录视为文件结束 */
+fn read_record(reader: &mut impl BufRead, line: &mut String) -> io::Result<bool> {
+    line.clear();
+    let read = reader.read_line(line)?;
+    Ok(read > 0 && line.ends_with('\n'))
+}
+
+/** 重放结果 */
+pub struct Replay {
+    pub exchange: Exchange,
+    /** 重放的日志条数 */
+    pub entries: u64,
+    /** 完整记录的字节数 */
+    valid_len: u64,
+}
+
+/**
+ * 重放日志 从初始状态依次执行记录的命令 重建交易所状态
+ *
+ * 重放时重新记录命令 与日志逐条比较 不一致时说明撮合或策略的行为与记录时不同
+ * 末尾没有换行的记录视为写入中断 忽略
+ */
+pub fn replay(path: impl AsRef<Path>) -> Result<Replay, JournalError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut line = String::new();
+    let mut valid_len = 0;
+
+    if !read_record(&mut reader, &mut line)? {
+        return Err(JournalError::MissingHeader);
+    }
+    valid_len += line.len() as u64;
+    let header: Header =
+        serde_json::from_str(&line).map_err(|source| JournalError::Format { line: 1, source })?;
+    if header.version != JOURNAL_VERSION {
+        return Err(JournalError::UnsupportedVersion(header.version));
+    }
+    let mut exchange = header.exchange;
+    exchange.enable_journal();
+
+    let mut entries = Vec::new();
+    while read_record(&mut reader, &mut line)? {
+        valid_len += line.len() as u64;
+        let entry: Entry = serde_json::from_str(&line).map_err(|source| JournalError::Format {
+            line: entries.len() + 2,
+            source,
+        })?;
+        let expected = entries.len() as u64 + 1;
+        if entry.seq != expected {
+            return Err(JournalError::OutOfSequence {
+                expected,
+                found: entry.seq,
+            });
+        }
+        entries.push(entry.command);
+    }
+
+    // 执行记录的命令 执行结果 (包括失败) 与记录时相同
+    let mut position = 0;
+    while position < entries.len() {
+        let command = &entries[position];
+        if command.is_derived() {
+            return Err(JournalError::Diverged {
+                seq: position as u64 + 1,
+                expected: Some(Box::new(command.clone())),
+                actual: None,
+            });
+        }
+        let _ = exchange.apply(command);
+        for actual in exchange.drain_journal() {
+            let expected = entries.get(position);
+            if expected != Some(&actual) {
+                return Err(JournalError::Diverged {
+                    seq: position as u64 + 1,
+                    expected: expected.cloned().map(Box::new),
+                    actual: Some(Box::new(actual)),
+                });
+            }
+            position += 1;
+        }
+    }
+
+    Ok(Replay {
+        exchange,
+        entries: entries.len() as u64,
+        valid_len,
+    })
+}
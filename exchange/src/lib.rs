@@ -1,13 +1,29 @@
+pub mod clock;
 pub mod config;
+pub mod config_file;
 pub mod engine;
+pub mod event;
 pub mod exchange;
 pub mod exchange_error;
+pub mod export;
+pub mod fee;
+pub mod fundamental;
+pub mod instrument;
+pub mod journal;
 pub mod log;
+pub mod market_data;
 pub mod order;
+pub mod price;
+pub mod random;
+pub mod record;
+pub mod simulation;
+pub mod snapshot;
 pub mod stock;
+pub mod timestamp;
 pub mod trade_day;
 pub mod trading_bot;
 pub mod trading_strategy;
 pub mod types;
 pub mod user;
 pub mod utils;
+pub mod venue;
@@ -1,10 +1,13 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::types::*;
 use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
 
 // 交易记录结构体
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeLog {
     pub id: TradeId,
     pub buyer_id: UserId,
@@ -18,7 +21,7 @@ pub struct TradeLog {
     pub direction: PriceDirection,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PriceDirection {
     Flat,
     Up,
@@ -44,7 +47,7 @@ impl TradeLog {
             quantity,
             buy_order_id,
             sell_order_id,
-            timestamp: 0,
+            timestamp: Timestamp::default(),
             direction,
         }
     }
@@ -56,72 +59,361 @@ pub enum TradeType {
     Limit,
 }
 
+/** 成交记录保留策略 */
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogRetention {
+    /** 内存中最多保留的成交记录数 超出时移除最早的记录 None 表示不限制 */
+    #[serde(default)]
+    pub max_logs: Option<usize>,
+    /** 移除的记录追加写入的文件 (JSON Lines) 省略时直接丢弃 */
+    #[serde(default)]
+    pub spill_path: Option<PathBuf>,
+}
+
+/** 成交记录查询条件 结果按成交编号倒序 */
+#[derive(Clone, Debug, Default)]
+pub struct TradeLogQuery {
+    pub stock_code: Option<StockCode>,
+    /** 买方或卖方 */
+    pub user_id: Option<UserId>,
+    /** 起始时间 包含 */
+    pub start_time: Option<Timestamp>,
+    /** 结束时间 不包含 */
+    pub end_time: Option<Timestamp>,
+    /** 游标 只返回编号小于游标的记录 */
+    pub before: Option<TradeId>,
+    pub limit: usize,
+}
+
+/** 一页成交记录 next_cursor 用于获取下一页 没有更多记录时为 None */
+#[derive(Debug, Clone)]
+pub struct TradeLogPage {
+    pub logs: Vec<TradeLog>,
+    pub next_cursor: Option<TradeId>,
+}
+
+/**
+ * 成交记录
+ *
+ * 记录按编号顺序保存 编号连续 按编号查找为 O(1)
+ * 按股票及用户建立索引 成交时间随编号递增 时间范围及游标查询使用二分查找
+ */
+#[derive(Serialize, Deserialize)]
+#[serde(from = "LogManagerData")]
 pub struct LogManager {
-    logs: HashMap<TradeId, TradeLog>,
-    id_queue: VecDeque<TradeId>,
+    logs: VecDeque<TradeLog>,
     next_id: TradeId,
+    max_logs: Option<usize>,
+    #[serde(skip)]
+    stock_index: HashMap<StockCode, VecDeque<TradeId>>,
+    #[serde(skip)]
+    user_index: HashMap<UserId, VecDeque<TradeId>>,
+    /** 是否保留移除的记录 由外部取出写入文件 */
+    #[serde(skip)]
+    spill_enabled: bool,
+    #[serde(skip)]
+    spilled: Vec<TradeLog>,
+}
+
+/** 快照中保存的成交记录 索引在恢复时重建 */
+#[derive(Deserialize)]
+struct LogManagerData {
+    logs: VecDeque<TradeLog>,
+    next_id: TradeId,
+    max_logs: Option<usize>,
+}
+
+impl From<LogManagerData> for LogManager {
+    fn from(data: LogManagerData) -> Self {
+        let mut manager = LogManager::with_max_logs(data.max_logs);
+        manager.next_id = data.next_id;
+        for log in &data.logs {
+            manager.index(log);
+        }
+        manager.logs = data.logs;
+        manager
+    }
+}
+
+impl Default for LogManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LogManager {
     pub fn new() -> Self {
+        Self::with_max_logs(None)
+    }
+
+    /** 内存中最多保留 max_logs 条记录 */
+    pub fn with_max_logs(max_logs: Option<usize>) -> Self {
         LogManager {
-            logs: HashMap::new(),
-            id_queue: VecDeque::new(),
+            logs: VecDeque::new(),
             next_id: 1,
+            max_logs,
+            stock_index: HashMap::new(),
+            user_index: HashMap::new(),
+            spill_enabled: false,
+            spilled: Vec::new(),
         }
     }
 
     pub fn add_log(&mut self, mut log: TradeLog) -> TradeId {
         let id = self.next_id;
         log.id = id;
-        self.logs.insert(id, log);
-        self.id_queue.push_front(id); // 在队列前端添加新的 ID
+        self.index(&log);
+        self.logs.push_back(log);
         self.next_id += 1;
+        if let Some(max_logs) = self.max_logs {
+            while self.logs.len() > max_logs {
+                self.evict();
+            }
+        }
         id
     }
 
+    fn index(&mut self, log: &TradeLog) {
+        self.stock_index
+            .entry(log.stock_code.clone())
+            .or_default()
+            .push_back(log.id);
+        self.user_index
+            .entry(log.buyer_id)
+            .or_default()
+            .push_back(log.id);
+        if log.seller_id != log.buyer_id {
+            self.user_index
+                .entry(log.seller_id)
+                .or_default()
+                .push_back(log.id);
+        }
+    }
+
+    /** 移除最早的记录 索引按编号递增 被移除的记录位于各索引的最前面 */
+    fn evict(&mut self) {
+        let Some(log) = self.logs.pop_front() else {
+            return;
+        };
+        if let Some(ids) = self.stock_index.get_mut(&log.stock_code) {
+            ids.pop_front();
+            if ids.is_empty() {
+                self.stock_index.remove(&log.stock_code);
+            }
+        }
+        for user_id in [log.buyer_id, log.seller_id] {
+            if let Some(ids) = self.user_index.get_mut(&user_id) {
+                if ids.front() == Some(&log.id) {
+                    ids.pop_front();
+                }
+                if ids.is_empty() {
+                    self.user_index.remove(&user_id);
+                }
+            }
+        }
+        if self.spill_enabled {
+            self.spilled.push(log);
+        }
+    }
+
+    /** 开始保留移除的记录 */
+    pub fn enable_spill(&mut self) {
+        self.spill_enabled = true;
+    }
+
+    /** 取出移除的记录 按编号顺序 */
+    pub fn drain_spilled(&mut self) -> Vec<TradeLog> {
+        std::mem::take(&mut self.spilled)
+    }
+
+    fn get(&self, id: TradeId) -> Option<&TradeLog> {
+        let first_id = self.logs.front()?.id;
+        let index = id.checked_sub(first_id)?;
+        self.logs.get(index as usize)
+    }
+
     pub fn get_log(&self, id: TradeId) -> Option<TradeLog> {
-        self.logs.get(&id).cloned()
+        self.get(id).cloned()
+    }
+
+    /** 编号不小于 id 的记录 按编号递增 */
+    pub fn iter_from(&self, id: TradeId) -> impl Iterator<Item = &TradeLog> {
+        let first_id = self.logs.front().map_or(id, |log| log.id);
+        self.logs.range(id.saturating_sub(first_id) as usize..)
+    }
+
+    /** 内存中的记录数 */
+    pub fn len(&self) -> usize {
+        self.logs.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.logs.is_empty()
+    }
+
+    /** 累计成交笔数 包括已移除的记录 */
+    pub fn total_logs(&self) -> usize {
+        (self.next_id - 1) as usize
+    }
+
+    /** 内存中的全部记录 按编号倒序 */
     pub fn get_all_logs(&self) -> Vec<TradeLog> {
-        self.id_queue
-            .iter()
-            .filter_map(|&id| self.logs.get(&id))
-            .cloned()
-            .collect()
+        self.logs.iter().rev().cloned().collect()
     }
 
-    /** 倒序获取分页数据 */
+    /** 倒序获取分页数据 返回记录及总页数 */
     pub fn page_logs(
         &self,
         stock_code: &str,
         page: usize,
         page_size: usize,
     ) -> (Vec<TradeLog>, usize) {
-        let start = (page - 1) * page_size;
-        let end = std::cmp::min(page * page_size, self.id_queue.len());
-        let stock_logs = self.get_logs_by_stock(&stock_code.to_string());
-        let logs = stock_logs.iter().skip(start).take(end).cloned().collect();
-        let total_pages = stock_logs.len();
+        let Some(ids) = self.stock_index.get(stock_code) else {
+            return (Vec::new(), 0);
+        };
+        let start = page.saturating_sub(1) * page_size;
+        let logs = ids
+            .iter()
+            .rev()
+            .skip(start)
+            .take(page_size)
+            .filter_map(|&id| self.get(id))
+            .cloned()
+            .collect();
+        let total_pages = ids.len().div_ceil(page_size.max(1));
         (logs, total_pages)
     }
 
     pub fn get_logs_by_user(&self, user_id: UserId) -> Vec<TradeLog> {
-        self.id_queue
-            .iter()
-            .filter_map(|&id| self.logs.get(&id))
-            .filter(|log| log.buyer_id == user_id || log.seller_id == user_id)
-            .cloned()
-            .collect()
+        self.collect_ids(self.user_index.get(&user_id))
     }
 
     pub fn get_logs_by_stock(&self, stock_code: &StockCode) -> Vec<TradeLog> {
-        self.id_queue
-            .iter()
-            .filter_map(|&id| self.logs.get(&id))
-            .filter(|log| log.stock_code == *stock_code)
+        self.collect_ids(self.stock_index.get(stock_code))
+    }
+
+    /** 用户编号大于 after 的成交 按编号递增 */
+    pub fn user_logs_after(
+        &self,
+        user_id: UserId,
+        after: TradeId,
+    ) -> impl Iterator<Item = &TradeLog> {
+        let ids = self.user_index.get(&user_id);
+        let start = ids.map_or(0, |ids| ids.partition_point(|id| *id <= after));
+        ids.into_iter()
+            .flat_map(move |ids| ids.range(start..))
+            .filter_map(|&id| self.get(id))
+    }
+
+    fn collect_ids(&self, ids: Option<&VecDeque<TradeId>>) -> Vec<TradeLog> {
+        ids.into_iter()
+            .flatten()
+            .rev()
+            .filter_map(|&id| self.get(id))
             .cloned()
             .collect()
     }
+
+    /** 按条件查询 同时指定股票和用户时使用用户索引 */
+    pub fn query(&self, query: &TradeLogQuery) -> TradeLogPage {
+        let empty = VecDeque::new();
+        // 多取一条判断是否还有下一页 需在按股票筛选之后截取
+        let limit = query.limit + 1;
+        let mut logs: Vec<TradeLog> = match (&query.stock_code, query.user_id) {
+            (None, None) => self
+                .scan(&self.logs, |log| log, query)
+                .take(limit)
+                .cloned()
+                .collect(),
+            (stock_code, Some(user_id)) => {
+                let ids = self.user_index.get(&user_id).unwrap_or(&empty);
+                self.scan(ids, |&id| self.get(id).unwrap(), query)
+                    .filter(|log| {
+                        stock_code
+                            .as_ref()
+                            .is_none_or(|code| log.stock_code == *code)
+                    })
+                    .take(limit)
+                    .cloned()
+                    .collect()
+            }
+            (Some(stock_code), None) => {
+                let ids = self.stock_index.get(stock_code).unwrap_or(&empty);
+                self.scan(ids, |&id| self.get(id).unwrap(), query)
+                    .take(limit)
+                    .cloned()
+                    .collect()
+            }
+        };
+        let next_cursor = if logs.len() > query.limit {
+            logs.truncate(query.limit);
+            logs.last().map(|log| log.id)
+        } else {
+            None
+        };
+        TradeLogPage { logs, next_cursor }
+    }
+
+    /** 在按编号递增的序列中定位时间范围及游标 倒序遍历 */
+    fn scan<'a, T>(
+        &'a self,
+        items: &'a VecDeque<T>,
+        log_of: impl Fn(&'a T) -> &'a TradeLog + Copy + 'a,
+        query: &TradeLogQuery,
+    ) -> impl Iterator<Item = &'a TradeLog> + 'a {
+        let log_at = |index: usize| log_of(&items[index]);
+        let start = query.start_time.map_or(0, |start_time| {
+            partition_index(items.len(), |index| log_at(index).timestamp < start_time)
+        });
+        let mut end = query.end_time.map_or(items.len(), |end_time| {
+            partition_index(items.len(), |index| log_at(index).timestamp < end_time)
+        });
+        if let Some(before) = query.before {
+            end = end.min(partition_index(items.len(), |index| {
+                log_at(index).id < before
+            }));
+        }
+        items.range(start..end.max(start)).rev().map(log_of)
+    }
+}
+
+/** 满足条件的元素在前 返回第一个不满足条件的位置 */
+fn partition_index(len: usize, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/** 将移除的记录追加写入文件 每行一条 JSON 用于成交记录及收盘清除的委托 */
+pub struct SpillWriter {
+    writer: BufWriter<File>,
+}
+
+impl SpillWriter {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(SpillWriter {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn write<T: Serialize>(&mut self, records: &[T]) -> io::Result<()> {
+        for record in records {
+            serde_json::to_writer(&mut self.writer, record)?;
+            self.writer.write_all(b"\n")?;
+        }
+        self.writer.flush()
+    }
 }
@@ -0,0 +1,619 @@
+use crate::config::{TradingPeriod, TradingPeriodType};
+use crate::exchange::Exchange;
+use crate::exchange_error::ExchangeResult;
+use crate::instrument::InstrumentRules;
+use crate::journal::Command;
+use crate::order::OrderType;
+use crate::simulation::ROBOT_INTERVAL;
+use crate::stock::PriceHistoryInfo;
+use crate::timestamp::MILLIS_PER_SECOND;
+use crate::types::*;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/** 回放代理的用户名 */
+pub const REPLAY_AGENT_NAME: &str = "historical_market";
+
+/** 回放代理准备的最少持仓 成交量缺失的数据也能双边报价 */
+const MIN_AGENT_HOLDING: u64 = 1_000_000;
+
+/** 分钟K线无法由相邻K线推断周期时的默认周期 */
+const DEFAULT_BAR_INTERVAL: TimeOfDay = 60 * MILLIS_PER_SECOND;
+
+#[derive(Error, Debug)]
+pub enum MarketDataError {
+    #[error("failed to read market data {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("failed to parse market data {source_name}: {error}")]
+    Csv {
+        source_name: String,
+        error: csv::Error,
+    },
+
+    #[error("{source_name}: missing column {column}")]
+    MissingColumn {
+        source_name: String,
+        column: &'static str,
+    },
+
+    #[error("{source_name}: no code column and no stock code in the file name")]
+    MissingCode { source_name: String },
+
+    #[error("{source_name} line {line}: {message}")]
+    InvalidRow {
+        source_name: String,
+        line: u64,
+        message: String,
+    },
+}
+
+/**
+ * 一根K线 日线的时间为当日零点 分钟线的时间为该分钟的开始
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MarketBar {
+    pub timestamp: Timestamp,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: u64,
+}
+
+impl MarketBar {
+    pub fn is_daily(&self) -> bool {
+        self.timestamp.time() == 0
+    }
+
+    /**
+     * 按进度 (0-1) 取K线内的价格
+     *
+     * 阳线按 开-低-高-收 阴线按 开-高-低-收 的路径线性插值
+     */
+    pub fn price_at(&self, progress: f64) -> Price {
+        let path = if self.close >= self.open {
+            [self.open, self.low, self.high, self.close]
+        } else {
+            [self.open, self.high, self.low, self.close]
+        };
+        let position = progress.clamp(0.0, 1.0) * (path.len() - 1) as f64;
+        let index = (position.floor() as usize).min(path.len() - 2);
+        let (from, to) = (path[index].raw() as f64, path[index + 1].raw() as f64);
+        let fraction = position - index as f64;
+        Price::from_raw((from + (to - from) * fraction).round() as u32)
+    }
+}
+
+/**
+ * 历史行情 股票代码 -> 按时间排列的K线
+ *
+ * 从 CSV 读取 需要表头 列名不区分大小写
+ * - date/datetime/time/timestamp 日期 (2024-03-01) 或时间 (2024-03-01 09:31:00)
+ * - open/high/low/close 价格
+ * - volume/vol 成交量 (股)
+ * - code/symbol 可选 省略时以文件名 (不含扩展名) 作为股票代码
+ */
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketData {
+    pub series: BTreeMap<StockCode, Vec<MarketBar>>,
+}
+
+impl MarketData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /** 读取单个 CSV 文件或目录下的全部 CSV 文件 */
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MarketDataError> {
+        let path = path.as_ref();
+        let io_error = |source| MarketDataError::Io {
+            path: path.display().to_string(),
+            source,
+        };
+        if !path.is_dir() {
+            return Self::load_file(path);
+        }
+
+        // 按文件名排序 保证合并结果稳定
+        let mut files: Vec<PathBuf> = fs::read_dir(path)
+            .map_err(io_error)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()
+            .map_err(io_error)?;
+        files.retain(|file| {
+            file.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+        });
+        files.sort();
+
+        let mut data = MarketData::new();
+        for file in files {
+            data.merge(Self::load_file(&file)?);
+        }
+        Ok(data)
+    }
+
+    fn load_file(path: &Path) -> Result<Self, MarketDataError> {
+        let file = fs::File::open(path).map_err(|source| MarketDataError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let stem = path.file_stem().and_then(|stem| stem.to_str());
+        Self::from_reader(file, stem, &path.display().to_string())
+    }
+
+    /**
+     * 从 CSV 读取
+     *
+     * default_code 为没有代码列时使用的股票代码 source_name 用于错误信息
+     */
+    pub fn from_reader(
+        reader: impl Read,
+        default_code: Option<&str>,
+        source_name: &str,
+    ) -> Result<Self, MarketDataError> {
+        let csv_error = |error| MarketDataError::Csv {
+            source_name: source_name.to_string(),
+            error,
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        let headers = reader.headers().map_err(csv_error)?.clone();
+        let find = |names: &[&str]| {
+            headers
+                .iter()
+                .position(|header| names.iter().any(|name| header.eq_ignore_ascii_case(name)))
+        };
+        let require = |names: &[&'static str]| {
+            find(names).ok_or(MarketDataError::MissingColumn {
+                source_name: source_name.to_string(),
+                column: names[0],
+            })
+        };
+        let time_column = require(&["date", "datetime", "time", "timestamp"])?;
+        let price_columns = [
+            require(&["open"])?,
+            require(&["high"])?,
+            require(&["low"])?,
+            require(&["close"])?,
+        ];
+        let volume_column = require(&["volume", "vol"])?;
+        let code_column = find(&["code", "symbol", "stock_code"]);
+        if code_column.is_none() && default_code.is_none_or(str::is_empty) {
+            return Err(MarketDataError::MissingCode {
+                source_name: source_name.to_string(),
+            });
+        }
+
+        let mut data = MarketData::new();
+        for record in reader.records() {
+            let record = record.map_err(csv_error)?;
+            let line = record.position().map_or(0, |position| position.line());
+            let invalid = |message: String| MarketDataError::InvalidRow {
+                source_name: source_name.to_string(),
+                line,
+                message,
+            };
+            let field = |column: usize| record.get(column).unwrap_or("");
+
+            let timestamp = parse_timestamp(field(time_column))
+                .ok_or_else(|| invalid(format!("invalid date {:?}", field(time_column))))?;
+            let mut prices = [Price::ZERO; 4];
+            for (price, column) in prices.iter_mut().zip(price_columns) {
+                *price = parse_price(field(column))
+                    .ok_or_else(|| invalid(format!("invalid price {:?}", field(column))))?;
+            }
+            let [open, high, low, close] = prices;
+            let volume = parse_volume(field(volume_column))
+                .ok_or_else(|| invalid(format!("invalid volume {:?}", field(volume_column))))?;
+            if low > open.min(close) || high < open.max(close) {
+                return Err(invalid(format!(
+                    "open {} and close {} must be between low {} and high {}",
+                    open, close, low, high
+                )));
+            }
+
+            let code = match code_column {
+                Some(column) if !field(column).is_empty() => field(column).to_string(),
+                Some(_) => return Err(invalid("empty stock code".to_string())),
+                None => default_code.unwrap_or_default().to_string(),
+            };
+            data.series.entry(code).or_default().push(MarketBar {
+                timestamp,
+                open,
+                high,
+                low,
+                close,
+                volume,
+            });
+        }
+        data.sort();
+        Ok(data)
+    }
+
+    /** 合并另一份行情 同一股票同一时间的K线以后者为准 */
+    pub fn merge(&mut self, other: MarketData) {
+        for (code, bars) in other.series {
+            self.series.entry(code).or_default().extend(bars);
+        }
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        for bars in self.series.values_mut() {
+            // 稳定排序后保留同一时间的最后一根
+            bars.sort_by_key(|bar| bar.timestamp);
+            let mut deduped: Vec<MarketBar> = Vec::with_capacity(bars.len());
+            for bar in bars.drain(..) {
+                match deduped.last_mut() {
+                    Some(last) if last.timestamp == bar.timestamp => *last = bar,
+                    _ => deduped.push(bar),
+                }
+            }
+            *bars = deduped;
+        }
+    }
+
+    pub fn bars(&self, stock_code: &str) -> &[MarketBar] {
+        self.series
+            .get(stock_code)
+            .map_or(&[], |bars| bars.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.values().all(|bars| bars.is_empty())
+    }
+
+    /** 按日期拆分为该日之前的历史及该日起的部分 */
+    pub fn split_at(&self, date: NaiveDate) -> (MarketData, MarketData) {
+        let mut history = MarketData::new();
+        let mut future = MarketData::new();
+        for (code, bars) in &self.series {
+            let split = bars.partition_point(|bar| bar.timestamp.date() < date);
+            if split > 0 {
+                history.series.insert(code.clone(), bars[..split].to_vec());
+            }
+            if split < bars.len() {
+                future.series.insert(code.clone(), bars[split..].to_vec());
+            }
+        }
+        (history, future)
+    }
+}
+
+fn parse_timestamp(value: &str) -> Option<Timestamp> {
+    const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%Y%m%d", "%Y/%m/%d"];
+    const DATETIME_FORMATS: [&str; 4] = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y/%m/%d %H:%M",
+    ];
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+        .map(Timestamp::start_of_day)
+        .or_else(|| {
+            DATETIME_FORMATS
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+                .map(Timestamp::from_datetime)
+        })
+}
+
+/** 价格 超过三位小数的数据 (如 10.2300001) 四舍五入 */
+fn parse_price(value: &str) -> Option<Price> {
+    let price = value.parse::<Price>().ok().or_else(|| {
+        let yuan: f64 = value.parse().ok()?;
+        let raw = (yuan * PRICE_SCALE as f64).round();
+        (raw.is_finite() && raw >= 0.0 && raw <= u32::MAX as f64)
+            .then(|| Price::from_raw(raw as u32))
+    })?;
+    (!price.is_zero()).then_some(price)
+}
+
+fn parse_volume(value: &str) -> Option<u64> {
+    value.parse().ok().or_else(|| {
+        let volume: f64 = value.parse().ok()?;
+        (volume.is_finite() && volume >= 0.0).then(|| volume.round() as u64)
+    })
+}
+
+/**
+ * 行情回放 回放代理按历史K线的价格路径在目标价双边报价
+ *
+ * 目标价低于卖一时代理买入扫掉目标价以下的卖单 高于买一时卖出扫掉目标价以上的买单
+ * 之后在目标价挂卖单 低一个价位挂买单 其他参与者与之成交时成交价跟随历史价格
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MarketReplay {
+    pub agent_id: UserId,
+    /** 当前交易日起待回放的K线 */
+    pub data: MarketData,
+}
+
+/** 回放代理某一时刻的报价 */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayQuote {
+    pub price: Price,
+    /** 每个执行间隔对应的历史成交量 */
+    pub quantity: u64,
+}
+
+impl MarketReplay {
+    /**
+     * 某一时刻的目标价
+     *
+     * 日线按当日连续竞价已进行的时间比例取价格 分钟线按该分钟已进行的时间比例取价格
+     */
+    pub fn quote_at(
+        &self,
+        stock_code: &str,
+        timestamp: Timestamp,
+        trading_periods: &[TradingPeriod],
+    ) -> Option<ReplayQuote> {
+        let bars = self.data.bars(stock_code);
+        let date = timestamp.date();
+        let day_start = bars.partition_point(|bar| bar.timestamp.date() < date);
+        let day_end = bars.partition_point(|bar| bar.timestamp.date() <= date);
+        let day = &bars[day_start..day_end];
+        let first = day.first()?;
+
+        let (bar, elapsed, duration) = if first.is_daily() {
+            let (elapsed, duration) =
+                continuous_trading_progress(trading_periods, timestamp.time());
+            (first, elapsed, duration)
+        } else {
+            let index = day
+                .partition_point(|bar| bar.timestamp <= timestamp)
+                .checked_sub(1)?;
+            let bar = &day[index];
+            let interval =
+                |from: &MarketBar, to: &MarketBar| to.timestamp.time() - from.timestamp.time();
+            let duration = match (day.get(index + 1), index.checked_sub(1)) {
+                (Some(next), _) => interval(bar, next),
+                (None, Some(previous)) => interval(&day[previous], bar),
+                (None, None) => DEFAULT_BAR_INTERVAL,
+            };
+            (bar, timestamp.time() - bar.timestamp.time(), duration)
+        };
+        if duration == 0 {
+            return None;
+        }
+
+        Some(ReplayQuote {
+            price: bar.price_at(elapsed as f64 / duration as f64),
+            quantity: bar.volume * ROBOT_INTERVAL as u64 / duration as u64,
+        })
+    }
+}
+
+/** 当日连续竞价已进行的时长及总时长 */
+fn continuous_trading_progress(
+    trading_periods: &[TradingPeriod],
+    time: TimeOfDay,
+) -> (TimeOfDay, TimeOfDay) {
+    trading_periods
+        .iter()
+        .filter(|period| period.period_type == TradingPeriodType::ContinuousTrading)
+        .fold((0, 0), |(elapsed, duration), period| {
+            let length = period.end_tick - period.start_tick;
+            let passed = time.clamp(period.start_tick, period.end_tick) - period.start_tick;
+            (elapsed + passed, duration + length)
+        })
+}
+
+impl Exchange {
+    /**
+     * 导入历史行情
+     *
+     * 当前交易日之前的K线写入价格历史 最后一根的收盘价作为昨收价 不存在的股票以代码为名称添加
+     * replay 为 true 时添加回放代理 当前交易日起的K线在时钟推进时回放
+     */
+    pub fn import_market_data(&mut self, data: MarketData, replay: bool) -> ExchangeResult<()> {
+        let command = Command::ImportMarketData {
+            data: data.clone(),
+            replay,
+        };
+        self.journaled(command, |exchange| {
+            let (history, future) = data.split_at(exchange.current_trade_day());
+            for (stock_code, bars) in &history.series {
+                exchange.import_history(stock_code, bars)?;
+            }
+            if replay {
+                exchange.start_market_replay(future)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn import_history(&mut self, stock_code: &str, bars: &[MarketBar]) -> ExchangeResult<()> {
+        let last_close = bars.last().map_or(Price::ZERO, |bar| bar.close);
+        if self
+            .stock_manager
+            .get_stock(&stock_code.to_string())
+            .is_none()
+        {
+            self.add_stock(stock_code, stock_code, last_close)?;
+        }
+
+        // 每根K线记录一条 与撮合产生的价格历史相同 每分钟至多一条
+        let mut history = Vec::with_capacity(bars.len());
+        let mut previous_close = bars.first().map_or(Price::ZERO, |bar| bar.open);
+        for bar in bars {
+            let volume = bar.volume.min(Quantity::MAX as u64) as Quantity;
+            // 没有成交额数据 按 (高+低+收)/3 估算均价
+            let average_price = Price::from_raw(
+                ((bar.high.raw() as u64 + bar.low.raw() as u64 + bar.close.raw() as u64) / 3)
+                    as u32,
+            );
+            let price_change = bar.close.diff(previous_close);
+            history.push(PriceHistoryInfo {
+                timestamp: bar.timestamp,
+                open_price: bar.open,
+                price: bar.close,
+                volume,
+                amount: average_price * volume,
+                average_price: if volume > 0 {
+                    average_price
+                } else {
+                    Price::ZERO
+                },
+                price_change,
+                price_change_rate: price_change.as_f64() / previous_close.as_f64() * 100.0,
+                min_price: bar.low,
+                max_price: bar.high,
+            });
+            previous_close = bar.close;
+        }
+
+        let stock = self
+            .stock_manager
+            .get_stock(&stock_code.to_string())
+            .unwrap();
+        let (price_limit_start, price_limit_end) = self.price_limits(stock.board, last_close);
+        let stock = self
+            .stock_manager
+            .get_stock_mut(&stock_code.to_string())
+            .unwrap();
+        history.append(&mut stock.price_history);
+        stock.price_history = history;
+        stock.start_price = last_close;
+        stock.current_price = last_close;
+        stock.update_price_limit(price_limit_start, price_limit_end);
+        Ok(())
+    }
+
+    /** 添加回放代理 按回放期间总成交量的两倍准备持仓及资金 */
+    fn start_market_replay(&mut self, data: MarketData) -> ExchangeResult<()> {
+        let mut holdings = Vec::new();
+        let mut balance = Money::ZERO;
+        for (stock_code, bars) in &data.series {
+            let Some(first) = bars.first() else {
+                continue;
+            };
+            if self.stock_manager.get_stock(stock_code).is_none() {
+                // 没有历史数据时以首日开盘价作为昨收价
+                self.add_stock(stock_code, stock_code, first.open)?;
+            }
+            let volume: u64 = bars.iter().map(|bar| bar.volume).sum();
+            let high = bars.iter().map(|bar| bar.high).max().unwrap_or(first.high);
+            let quantity = (volume * 2).max(MIN_AGENT_HOLDING);
+            balance += high * quantity;
+            holdings.push((stock_code.clone(), quantity));
+        }
+
+        let agent_id = self.add_user(REPLAY_AGENT_NAME, balance)?;
+        let user = self.user_manager.get_user_mut(agent_id).unwrap();
+        for (stock_code, quantity) in holdings {
+            let price = self
+                .stock_manager
+                .get_stock(&stock_code)
+                .map_or(Price::ZERO, |stock| stock.current_price);
+            user.add_holding(stock_code, quantity, price * quantity);
+        }
+        self.market_replay = Some(MarketReplay { agent_id, data });
+        Ok(())
+    }
+
+    /**
+     * 回放代理报价 连续竞价期间在机器人决策前执行
+     *
+     * 先撤销代理的全部委托 再按当前目标价扫单及双边挂单 下单失败 (如资金不足) 时忽略
+     */
+    pub(crate) fn replay_market(&mut self) {
+        let Some(replay) = self.market_replay.take() else {
+            return;
+        };
+        let continuous = self
+            .config
+            .get_current_period()
+            .is_some_and(|period| period.period_type == TradingPeriodType::ContinuousTrading);
+        if continuous && self.config.is_allow_order() {
+            let timestamp = self.config.current_timestamp;
+            let agent_orders: Vec<OrderId> = self
+                .order_manager
+                .get_open_orders()
+                .into_iter()
+                .filter(|order| order.user_id == replay.agent_id)
+                .map(|order| order.id)
+                .collect();
+            for order_id in agent_orders {
+                let _ = self.cancel_order(order_id);
+            }
+            for stock_code in replay.data.series.keys() {
+                if let Some(quote) =
+                    replay.quote_at(stock_code, timestamp, &self.config.trading_periods)
+                {
+                    self.quote_replay_price(replay.agent_id, stock_code, quote);
+                }
+            }
+        }
+        self.market_replay = Some(replay);
+    }
+
+    fn quote_replay_price(&mut self, agent_id: UserId, stock_code: &StockCode, quote: ReplayQuote) {
+        let Some(stock) = self.stock_manager.get_stock(stock_code) else {
+            return;
+        };
+        let rules = self
+            .get_instrument_rules(stock_code)
+            .cloned()
+            .unwrap_or_else(InstrumentRules::main_board);
+        let tick_size = rules.tick_size.max(Price::from_raw(1));
+        let price = quote.price.round_to(tick_size).clamp(
+            stock.price_limit.lower,
+            stock.price_limit.upper.max(tick_size),
+        );
+        let quantity = rules.round_buy_quantity(
+            quote
+                .quantity
+                .clamp(rules.min_quantity as u64, rules.max_quantity as u64)
+                as Quantity,
+        );
+
+        // 其他参与者在目标价之外的委托先成交 价格移动到目标价
+        let sweep = |quantities: &BTreeMap<Price, u64>| {
+            quantities
+                .values()
+                .sum::<u64>()
+                .min(rules.max_quantity as u64) as Quantity
+        };
+        let asks: BTreeMap<Price, u64> = stock
+            .sell_quantities
+            .range(..=price)
+            .map(|(price, quantity)| (*price, *quantity))
+            .collect();
+        let bids: BTreeMap<Price, u64> = stock
+            .buy_quantities
+            .range(price..)
+            .map(|(price, quantity)| (*price, *quantity))
+            .collect();
+        let (buy_sweep, sell_sweep) = (
+            rules.round_buy_quantity(sweep(&asks)),
+            rules.round_buy_quantity(sweep(&bids)),
+        );
+
+        let stock_code = stock_code.to_string();
+        let mut submit = |order_type, price: Price, quantity| {
+            if quantity > 0 && !price.is_zero() {
+                let _ =
+                    self.submit_order(agent_id, stock_code.clone(), order_type, price, quantity);
+            }
+        };
+        submit(OrderType::Buy, price, buy_sweep);
+        submit(OrderType::Sell, price, sell_sweep);
+        submit(OrderType::Sell, price, quantity);
+        submit(OrderType::Buy, price.saturating_sub(tick_size), quantity);
+    }
+}
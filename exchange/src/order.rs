@@ -1,9 +1,11 @@
 use crate::exchange_error::{ExchangeError, ExchangeResult};
+use crate::fee::Fees;
 use crate::types::*;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // 委托单结构体
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Order {
     /** 委托单ID */
     pub id: OrderId,
@@ -23,10 +25,14 @@ pub struct Order {
     pub timestamp: Timestamp,
     /** 执行记录 */
     pub executions: Vec<Execution>,
+    /** 买入委托剩余冻结的预估费用 */
+    pub frozen_fee: Money,
+    /** 已收取的佣金 */
+    pub commission_paid: Money,
 }
 
 // 新增：执行记录结构体
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Execution {
     /** 执行数量 */
     pub quantity: Quantity,
@@ -34,10 +40,12 @@ pub struct Execution {
     pub price: Price,
     /** 执行时间 */
     pub timestamp: Timestamp,
+    /** 执行费用 */
+    pub fees: Fees,
 }
 
 // 订单类型枚举
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum OrderType {
     /** 买入 */
     Buy,
@@ -45,6 +53,34 @@ pub enum OrderType {
     Sell,
 }
 
+/** 委托状态 */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /** 未成交 */
+    New,
+    /** 部分成交 */
+    PartiallyFilled,
+    /** 全部成交 */
+    Filled,
+    /** 已撤单 含部分成交后撤单 */
+    Cancelled,
+    /** 收盘时未成交部分过期 */
+    Expired,
+}
+
+impl OrderStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OrderStatus::New => "new",
+            OrderStatus::PartiallyFilled => "partially_filled",
+            OrderStatus::Filled => "filled",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Expired => "expired",
+        }
+    }
+}
+
 // 新增：订单方法实现
 impl Order {
     /** 创建新订单 */
@@ -63,14 +99,24 @@ impl Order {
             price,
             quantity,
             remaining_quantity: quantity,
-            timestamp: 0,
+            timestamp: Timestamp::default(),
             executions: Vec::new(),
+            frozen_fee: Money::ZERO,
+            commission_paid: Money::ZERO,
         }
     }
 
     /** 总价值 */
-    pub fn value(&self) -> u64 {
-        self.price as u64 * self.quantity as u64
+    pub fn value(&self) -> Money {
+        self.price * self.quantity
+    }
+
+    /** 已成交金额 */
+    pub fn filled_amount(&self) -> Money {
+        self.executions
+            .iter()
+            .map(|execution| execution.price * execution.quantity)
+            .sum()
     }
 
     /** 是否已完全成交 */
@@ -83,6 +129,30 @@ impl Order {
         self.remaining_quantity > 0 && self.remaining_quantity < self.quantity
     }
 
+    /** 已成交数量 */
+    pub fn filled_quantity(&self) -> Quantity {
+        self.executions
+            .iter()
+            .map(|execution| execution.quantity)
+            .sum()
+    }
+
+    /** 委托状态 收盘过期的委托已被清除 不在此列 */
+    pub fn status(&self) -> OrderStatus {
+        let filled = self.filled_quantity();
+        if self.remaining_quantity > 0 {
+            if filled == 0 {
+                OrderStatus::New
+            } else {
+                OrderStatus::PartiallyFilled
+            }
+        } else if filled == self.quantity {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::Cancelled
+        }
+    }
+
     /** 是否可取消 */
     pub fn is_cancellable(&self) -> bool {
         self.remaining_quantity > 0
@@ -91,19 +161,45 @@ impl Order {
     /** 执行委托 */
     pub fn execute(
         &mut self,
-        execution_quantity: u32,
-        execution_price: u32,
+        execution_quantity: Quantity,
+        execution_price: Price,
         execution_time: Timestamp,
+        fees: Fees,
     ) {
         let actual_execution_quantity = execution_quantity.min(self.remaining_quantity);
         self.remaining_quantity -= actual_execution_quantity;
+        self.commission_paid += fees.commission;
         self.executions.push(Execution {
             quantity: actual_execution_quantity,
             price: execution_price,
             timestamp: execution_time,
+            fees,
         });
     }
 
+    /**
+     * 按成交数量比例释放冻结的预估费用 返回释放的金额
+     *
+     * 最低佣金可能在首笔部分成交时全额收取 释放金额不少于本次实际收取的费用
+     */
+    pub fn release_frozen_fee(
+        &mut self,
+        quantity: Quantity,
+        remaining_before: Quantity,
+        charged: Money,
+    ) -> Money {
+        let released = if quantity >= remaining_before {
+            self.frozen_fee
+        } else {
+            self.frozen_fee
+                .mul_div(quantity as u64, remaining_before as u64)
+                .max(charged)
+                .min(self.frozen_fee)
+        };
+        self.frozen_fee -= released;
+        released
+    }
+
     /** 取消委托 */
     pub fn cancel(&mut self) -> u32 {
         let cancelled_quantity = self.remaining_quantity;
@@ -113,16 +209,31 @@ impl Order {
 }
 
 // OrderManager 结构体及其实现
+#[derive(Serialize, Deserialize)]
 pub struct OrderManager {
-    orders: HashMap<u64, Order>,
+    orders: BTreeMap<u64, Order>,
     next_order_id: u64,
+    /** 是否保留收盘清除的委托 */
+    #[serde(skip)]
+    archive_enabled: bool,
+    /** 收盘清除 等待外部取出的委托 */
+    #[serde(skip)]
+    archived: Vec<Order>,
+}
+
+impl Default for OrderManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl OrderManager {
     pub fn new() -> Self {
         OrderManager {
-            orders: HashMap::new(),
+            orders: BTreeMap::new(),
             next_order_id: 1,
+            archive_enabled: false,
+            archived: Vec::new(),
         }
     }
 
@@ -133,11 +244,13 @@ impl OrderManager {
         order_type: OrderType,
         price: Price,
         quantity: Quantity,
+        timestamp: Timestamp,
     ) -> Order {
         let order_id = self.next_order_id;
         self.next_order_id += 1;
         let mut order = Order::new(user_id, stock_code, order_type, price, quantity);
         order.id = order_id;
+        order.timestamp = timestamp;
         self.orders.insert(order_id, order.clone());
         order
     }
@@ -157,11 +270,12 @@ impl OrderManager {
     }
 
     pub fn update_order(&mut self, order: Order) -> ExchangeResult<()> {
-        if self.orders.contains_key(&order.id) {
-            self.orders.insert(order.id, order);
-            Ok(())
-        } else {
-            Err(ExchangeError::OrderNotFound(order.id))
+        match self.orders.get_mut(&order.id) {
+            Some(existing) => {
+                *existing = order;
+                Ok(())
+            }
+            None => Err(ExchangeError::OrderNotFound(order.id)),
         }
     }
 
@@ -179,7 +293,34 @@ impl OrderManager {
             .collect()
     }
 
+    /** 尚未完成的委托 */
+    pub fn get_open_orders(&self) -> Vec<&Order> {
+        self.orders
+            .values()
+            .filter(|order| order.remaining_quantity > 0)
+            .collect()
+    }
+
+    /** 编号不小于 order_id 的委托 按编号递增 */
+    pub fn iter_from(&self, order_id: OrderId) -> impl Iterator<Item = &Order> {
+        self.orders.range(order_id..).map(|(_, order)| order)
+    }
+
+    /** 收盘清除委托 启用保留时移入待取出的列表 */
     pub fn clear_orders(&mut self) {
-        self.orders.clear();
+        let orders = std::mem::take(&mut self.orders);
+        if self.archive_enabled {
+            self.archived.extend(orders.into_values());
+        }
+    }
+
+    /** 开始保留收盘清除的委托 */
+    pub fn enable_archive(&mut self) {
+        self.archive_enabled = true;
+    }
+
+    /** 取出收盘清除的委托 按编号顺序 */
+    pub fn drain_archived(&mut self) -> Vec<Order> {
+        std::mem::take(&mut self.archived)
     }
 }
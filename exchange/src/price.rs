@@ -0,0 +1,392 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+use thiserror::Error;
+
+/** 价格及金额精度 1元 = 1000 厘 */
+pub const PRICE_SCALE: u32 = 1000;
+
+/** 小数位数 与 PRICE_SCALE 对应 */
+const DECIMALS: usize = 3;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseAmountError {
+    #[error("invalid amount: {0}")]
+    Invalid(String),
+
+    #[error("too many decimal places: {0}")]
+    TooPrecise(String),
+
+    #[error("amount out of range: {0}")]
+    OutOfRange(String),
+}
+
+/**
+ * 价格 以厘(0.001元)为单位
+ *
+ * 序列化为定点小数字符串 如 "15.230"
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Price(u32);
+
+/**
+ * 金额 以厘(0.001元)为单位 可为负数 用于现金、成本、费用及盈亏
+ *
+ * 序列化为定点小数字符串 如 "-1200.500"
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Price {
+    pub const ZERO: Price = Price(0);
+    pub const MAX: Price = Price(u32::MAX);
+
+    pub const fn from_raw(raw: u32) -> Self {
+        Price(raw)
+    }
+
+    /** 按厘计的原始值 */
+    pub const fn raw(self) -> u32 {
+        self.0
+    }
+
+    pub const fn from_yuan(yuan: u32) -> Self {
+        Price(yuan * PRICE_SCALE)
+    }
+
+    /** 按分构造 A股报价的最小单位 */
+    pub const fn from_fen(fen: u32) -> Self {
+        Price(fen * (PRICE_SCALE / 100))
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Price) -> Option<Price> {
+        self.0.checked_add(other.0).map(Price)
+    }
+
+    pub fn checked_sub(self, other: Price) -> Option<Price> {
+        self.0.checked_sub(other.0).map(Price)
+    }
+
+    pub fn saturating_sub(self, other: Price) -> Price {
+        Price(self.0.saturating_sub(other.0))
+    }
+
+    /** 四舍五入到最小价格变动单位的整数倍 */
+    pub fn round_to(self, tick_size: Price) -> Price {
+        if tick_size.0 <= 1 {
+            return self;
+        }
+        let ticks = (self.0 as u64 + tick_size.0 as u64 / 2) / tick_size.0 as u64;
+        Price((ticks * tick_size.0 as u64).min(u32::MAX as u64) as u32)
+    }
+
+    /** 成交金额 价格 * 数量 */
+    pub fn checked_mul(self, quantity: u64) -> Option<Money> {
+        (self.0 as i64)
+            .checked_mul(i64::try_from(quantity).ok()?)
+            .map(Money)
+    }
+
+    /** 按比例缩放并四舍五入到厘 用于计算涨跌停价 */
+    pub fn scale(self, factor: f64) -> Price {
+        Price((self.0 as f64 * factor).round() as u32)
+    }
+
+    /** 与另一价格的差额 */
+    pub fn diff(self, other: Price) -> Money {
+        Money(self.0 as i64 - other.0 as i64)
+    }
+
+    /** 按元计的近似值 仅用于比率计算 */
+    pub fn as_f64(self) -> f64 {
+        self.0 as f64 / PRICE_SCALE as f64
+    }
+}
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_raw(raw: i64) -> Self {
+        Money(raw)
+    }
+
+    /** 按厘计的原始值 */
+    pub const fn raw(self) -> i64 {
+        self.0
+    }
+
+    pub const fn from_yuan(yuan: i64) -> Self {
+        Money(yuan * PRICE_SCALE as i64)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.0.checked_sub(other.0).map(Money)
+    }
+
+    /** 相减 结果不小于0 */
+    pub fn saturating_sub(self, other: Money) -> Money {
+        Money(self.0.saturating_sub(other.0).max(0))
+    }
+
+    /** 按费率计算 四舍五入到厘 */
+    pub fn mul_rate(self, rate: f64) -> Money {
+        Money((self.0 as f64 * rate).round() as i64)
+    }
+
+    /** 按 numerator / denominator 比例分摊 向下取整 */
+    pub fn mul_div(self, numerator: u64, denominator: u64) -> Money {
+        if denominator == 0 {
+            return Money::ZERO;
+        }
+        Money((self.0 as i128 * numerator as i128 / denominator as i128) as i64)
+    }
+
+    /** 按价格可买入的数量 */
+    pub fn quantity_at(self, price: Price) -> u64 {
+        if price.0 == 0 || self.0 <= 0 {
+            0
+        } else {
+            self.0 as u64 / price.0 as u64
+        }
+    }
+
+    /** 按数量计算单价 四舍五入到厘 */
+    pub fn per_unit(self, quantity: u64) -> Price {
+        if quantity == 0 || self.0 <= 0 {
+            return Price::ZERO;
+        }
+        let raw = (self.0 as u128 * 2 + quantity as u128) / (quantity as u128 * 2);
+        Price(raw.min(u32::MAX as u128) as u32)
+    }
+
+    /** 按元计的近似值 仅用于比率计算 */
+    pub fn as_f64(self) -> f64 {
+        self.0 as f64 / PRICE_SCALE as f64
+    }
+}
+
+impl Add for Price {
+    type Output = Price;
+
+    fn add(self, other: Price) -> Price {
+        self.checked_add(other).expect("price overflow")
+    }
+}
+
+impl Sub for Price {
+    type Output = Price;
+
+    fn sub(self, other: Price) -> Price {
+        self.checked_sub(other).expect("price underflow")
+    }
+}
+
+impl Mul<u32> for Price {
+    type Output = Money;
+
+    fn mul(self, quantity: u32) -> Money {
+        Money(self.0 as i64 * quantity as i64)
+    }
+}
+
+impl Mul<u64> for Price {
+    type Output = Money;
+
+    fn mul(self, quantity: u64) -> Money {
+        self.checked_mul(quantity).expect("amount overflow")
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        self.checked_add(other).expect("amount overflow")
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        self.checked_sub(other).expect("amount overflow")
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        *self = *self - other;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(self.0.checked_neg().expect("amount overflow"))
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+fn format_scaled(f: &mut fmt::Formatter<'_>, negative: bool, raw: u64) -> fmt::Result {
+    let scale = PRICE_SCALE as u64;
+    let sign = if negative { "-" } else { "" };
+    write!(
+        f,
+        "{}{}.{:0width$}",
+        sign,
+        raw / scale,
+        raw % scale,
+        width = DECIMALS
+    )
+}
+
+/** 解析定点小数 返回按厘计的原始值 */
+fn parse_scaled(s: &str) -> Result<i128, ParseAmountError> {
+    let trimmed = s.trim();
+    let (negative, digits) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if integer.is_empty() || !is_digits(integer) || !is_digits(fraction) {
+        return Err(ParseAmountError::Invalid(s.to_string()));
+    }
+    if fraction.len() > DECIMALS {
+        return Err(ParseAmountError::TooPrecise(s.to_string()));
+    }
+
+    let integer: i128 = integer
+        .parse()
+        .map_err(|_| ParseAmountError::OutOfRange(s.to_string()))?;
+    let fraction: i128 = format!("{:0<width$}", fraction, width = DECIMALS)
+        .parse()
+        .map_err(|_| ParseAmountError::Invalid(s.to_string()))?;
+    let raw = integer
+        .checked_mul(PRICE_SCALE as i128)
+        .and_then(|value| value.checked_add(fraction))
+        .ok_or_else(|| ParseAmountError::OutOfRange(s.to_string()))?;
+    Ok(if negative { -raw } else { raw })
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        format_scaled(f, false, self.0 as u64)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        format_scaled(f, self.0 < 0, self.0.unsigned_abs())
+    }
+}
+
+impl FromStr for Price {
+    type Err = ParseAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = parse_scaled(s)?;
+        u32::try_from(raw)
+            .map(Price)
+            .map_err(|_| ParseAmountError::OutOfRange(s.to_string()))
+    }
+}
+
+impl FromStr for Money {
+    type Err = ParseAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = parse_scaled(s)?;
+        i64::try_from(raw)
+            .map(Money)
+            .map_err(|_| ParseAmountError::OutOfRange(s.to_string()))
+    }
+}
+
+impl Serialize for Price {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/** 反序列化 接受定点小数字符串或按元计的数字 */
+struct ScaledVisitor<T>(std::marker::PhantomData<T>);
+
+impl<'de, T: FromStr<Err = ParseAmountError>> Visitor<'de> for ScaledVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a decimal amount in yuan")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<T, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<T, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<T, E> {
+        self.visit_str(&format!("{:.width$}", value, width = DECIMALS))
+    }
+}
+
+impl<'de> Deserialize<'de> for Price {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ScaledVisitor(std::marker::PhantomData))
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ScaledVisitor(std::marker::PhantomData))
+    }
+}
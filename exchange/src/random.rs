@@ -0,0 +1,43 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::f64::consts::PI;
+
+/** 模拟使用的随机数生成器 相同种子在不同平台上产生相同序列 */
+pub type SimRng = ChaCha8Rng;
+
+/** 交易所自身使用的随机数流 如随机收市 */
+pub const EXCHANGE_STREAM: u64 = u64::MAX;
+
+/** 基本面价值过程使用的随机数流 */
+pub const FUNDAMENTAL_STREAM: u64 = u64::MAX - 1;
+
+/**
+ * 由主种子派生随机数生成器
+ *
+ * 每个流相互独立 机器人以用户ID作为流编号 因此增减机器人不影响其他机器人的随机序列
+ */
+pub fn derive_rng(seed: u64, stream: u64) -> SimRng {
+    let mut rng = SimRng::seed_from_u64(seed);
+    rng.set_stream(stream);
+    rng
+}
+
+/** 标准正态分布随机数 Box-Muller 变换 */
+pub fn standard_normal(rng: &mut SimRng) -> f64 {
+    // 取 (0, 1] 避免对0取对数
+    let u1 = 1.0 - rng.gen::<f64>();
+    let u2 = rng.gen::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+/** 泊松分布随机数 适用于期望较小的情况 */
+pub fn poisson(rng: &mut SimRng, mean: f64) -> u32 {
+    let limit = (-mean).exp();
+    let mut count = 0;
+    let mut product = rng.gen::<f64>();
+    while product > limit {
+        count += 1;
+        product *= rng.gen::<f64>();
+    }
+    count
+}
@@ -0,0 +1,142 @@
+use crate::exchange::Exchange;
+use crate::fee::Fees;
+use crate::log::TradeLog;
+use crate::order::{Order, OrderStatus, OrderType};
+use crate::simulation::DailyBar;
+use crate::types::*;
+
+/**
+ * 需要持久化的记录
+ *
+ * 交易所只负责产生记录 由外部取出后异步写入数据库 不影响撮合
+ */
+#[derive(Clone, Debug)]
+pub enum ExchangeRecord {
+    User {
+        user_id: UserId,
+        username: String,
+        balance: Money,
+        timestamp: Timestamp,
+    },
+    /** 委托状态变化 */
+    Order {
+        order_id: OrderId,
+        user_id: UserId,
+        stock_code: StockCode,
+        order_type: OrderType,
+        price: Price,
+        quantity: Quantity,
+        remaining_quantity: Quantity,
+        status: OrderStatus,
+        submitted_at: Timestamp,
+        timestamp: Timestamp,
+    },
+    Execution {
+        order_id: OrderId,
+        quantity: Quantity,
+        price: Price,
+        fees: Fees,
+        timestamp: Timestamp,
+    },
+    Trade(TradeLog),
+    DailyBar(DailyBar),
+}
+
+impl ExchangeRecord {
+    /** 委托的当前状态 */
+    pub fn order(order: &Order, status: OrderStatus, timestamp: Timestamp) -> Self {
+        ExchangeRecord::Order {
+            order_id: order.id,
+            user_id: order.user_id,
+            stock_code: order.stock_code.clone(),
+            order_type: order.order_type,
+            price: order.price,
+            quantity: order.quantity,
+            remaining_quantity: order.remaining_quantity,
+            status,
+            submitted_at: order.timestamp,
+            timestamp,
+        }
+    }
+}
+
+// 待持久化的记录 未启用时不记录
+#[derive(Default)]
+pub struct RecordQueue {
+    enabled: bool,
+    records: Vec<ExchangeRecord>,
+}
+
+impl RecordQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn push(&mut self, record: ExchangeRecord) {
+        if self.enabled {
+            self.records.push(record);
+        }
+    }
+
+    /** 记录委托状态 */
+    pub fn push_order(&mut self, order: &Order, status: OrderStatus, timestamp: Timestamp) {
+        if self.enabled {
+            self.records
+                .push(ExchangeRecord::order(order, status, timestamp));
+        }
+    }
+
+    /** 委托成交后记录成交及委托状态 */
+    pub fn push_execution(&mut self, order: &Order) {
+        let Some(execution) = order.executions.last().filter(|_| self.enabled) else {
+            return;
+        };
+        self.records.push(ExchangeRecord::order(
+            order,
+            order.status(),
+            execution.timestamp,
+        ));
+        self.records.push(ExchangeRecord::Execution {
+            order_id: order.id,
+            quantity: execution.quantity,
+            price: execution.price,
+            fees: execution.fees,
+            timestamp: execution.timestamp,
+        });
+    }
+
+    /** 取出所有待持久化的记录 */
+    pub fn drain(&mut self) -> Vec<ExchangeRecord> {
+        std::mem::take(&mut self.records)
+    }
+}
+
+impl Exchange {
+    /** 开始产生待持久化的记录 已有的用户同时记录 */
+    pub fn enable_records(&mut self) {
+        self.records.enable();
+        let timestamp = self.get_current_timestamp();
+        for user_id in self.user_manager.get_user_ids() {
+            let user = self.user_manager.get_user(user_id).unwrap();
+            self.records.push(ExchangeRecord::User {
+                user_id,
+                username: user.username.clone(),
+                balance: user.balance,
+                timestamp,
+            });
+        }
+    }
+
+    /** 取出待持久化的记录 */
+    pub fn drain_records(&mut self) -> Vec<ExchangeRecord> {
+        self.records.drain()
+    }
+}
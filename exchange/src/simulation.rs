@@ -0,0 +1,146 @@
+use crate::clock::SimulationClock;
+use crate::exchange::Exchange;
+use crate::exchange_error::ExchangeResult;
+use crate::journal::Command;
+use crate::stock::PriceHistoryInfo;
+use crate::timestamp::MILLIS_PER_SECOND;
+use crate::types::*;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/** 机器人策略执行间隔 模拟时间 */
+pub const ROBOT_INTERVAL: TimeOfDay = 3 * MILLIS_PER_SECOND;
+
+/** 模拟结果摘要 */
+#[derive(Clone, Debug, Serialize)]
+pub struct SimulationSummary {
+    /** 第一个模拟的交易日 */
+    pub start_day: NaiveDate,
+    /** 最后一个模拟的交易日 */
+    pub end_day: NaiveDate,
+    pub trading_days: u32,
+    /** 推进的时钟步数 */
+    pub steps: u64,
+    /** 期间的成交笔数 */
+    pub trades: usize,
+}
+
+/** 日K线 由分钟价格历史汇总 */
+#[derive(Clone, Debug, Serialize)]
+pub struct DailyBar {
+    pub stock_code: StockCode,
+    pub trade_day: NaiveDate,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: u64,
+    pub amount: Money,
+}
+
+impl Exchange {
+    /** 时钟推进一步 到达执行间隔时推进基本面价值 执行行情回放及机器人策略 */
+    pub fn tick(&mut self, clock: &SimulationClock) -> ExchangeResult<Timestamp> {
+        let command = Command::Tick {
+            step: clock.step_size(),
+        };
+        self.journaled(command, |exchange| {
+            let timestamp = clock.step(exchange);
+            if timestamp.time().is_multiple_of(ROBOT_INTERVAL) {
+                exchange.update_fundamental();
+                exchange.replay_market();
+                exchange.execute_robot_strategies()?;
+            }
+            Ok(timestamp)
+        })
+    }
+
+    /**
+     * 不受真实时间限制 以最快速度连续模拟若干个交易日
+     *
+     * 每个交易日收盘后按交易日历切换到下一交易日 结束时时钟停在下一交易日开盘
+     */
+    pub fn run_trading_days(&mut self, days: u32) -> ExchangeResult<SimulationSummary> {
+        let clock = SimulationClock::new();
+        let trades_before = self.log_manager.total_logs();
+        let mut summary = SimulationSummary {
+            start_day: self.current_trade_day(),
+            end_day: self.current_trade_day(),
+            trading_days: 0,
+            steps: 0,
+            trades: 0,
+        };
+
+        let mut trade_day = self.current_trade_day();
+        while summary.trading_days < days {
+            let timestamp = self.tick(&clock)?;
+            summary.steps += 1;
+            if timestamp.date() == trade_day {
+                continue;
+            }
+            // 交易日切换 起始日不是交易日时不计入
+            if self.trade_day_manager.is_trading_day(trade_day) {
+                if summary.trading_days == 0 {
+                    summary.start_day = trade_day;
+                }
+                summary.trading_days += 1;
+                summary.end_day = trade_day;
+            }
+            trade_day = timestamp.date();
+        }
+
+        summary.trades = self.log_manager.total_logs() - trades_before;
+        Ok(summary)
+    }
+
+    /** 各股票的日K线 按股票代码及日期排序 */
+    pub fn daily_bars(&self) -> Vec<DailyBar> {
+        self.collect_daily_bars(|history| history)
+    }
+
+    /** 各股票某一交易日的日K线 */
+    pub fn daily_bars_on(&self, trade_day: NaiveDate) -> Vec<DailyBar> {
+        // 价格历史按时间排列
+        self.collect_daily_bars(|history| {
+            let start = history.partition_point(|item| item.timestamp.date() < trade_day);
+            let end = history.partition_point(|item| item.timestamp.date() <= trade_day);
+            &history[start..end]
+        })
+    }
+
+    fn collect_daily_bars(
+        &self,
+        select: impl Fn(&[PriceHistoryInfo]) -> &[PriceHistoryInfo],
+    ) -> Vec<DailyBar> {
+        let mut stock_codes = self.stock_manager.get_stock_codes();
+        stock_codes.sort();
+
+        let mut bars = Vec::new();
+        for stock_code in stock_codes {
+            let stock = self.stock_manager.get_stock(&stock_code).unwrap();
+            let mut daily: BTreeMap<NaiveDate, DailyBar> = BTreeMap::new();
+            for item in select(&stock.price_history) {
+                let bar = daily
+                    .entry(item.timestamp.date())
+                    .or_insert_with(|| DailyBar {
+                        stock_code: stock_code.clone(),
+                        trade_day: item.timestamp.date(),
+                        open: item.open_price,
+                        high: item.max_price,
+                        low: item.min_price,
+                        close: item.price,
+                        volume: 0,
+                        amount: Money::ZERO,
+                    });
+                bar.high = bar.high.max(item.max_price);
+                bar.low = bar.low.min(item.min_price);
+                bar.close = item.price;
+                bar.volume += item.volume as u64;
+                bar.amount += item.amount;
+            }
+            bars.extend(daily.into_values());
+        }
+        bars
+    }
+}
@@ -0,0 +1,107 @@
+use crate::clock::SimulationClock;
+use crate::exchange::Exchange;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/** 快照文件格式版本 格式不兼容时递增 */
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("failed to access snapshot file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid snapshot: {0}")]
+    Format(#[from] serde_json::Error),
+
+    #[error("unsupported snapshot version {0}, expected {SNAPSHOT_VERSION}")]
+    UnsupportedVersion(u32),
+}
+
+/**
+ * 交易所完整状态的快照
+ *
+ * 包括行情、委托、用户、成交记录、机器人、交易所配置、交易日历及模拟时钟
+ * 未推送的事件不保存
+ */
+#[derive(Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub exchange: Exchange,
+    pub clock: SimulationClock,
+    /** 宿主程序附加的状态 如服务端的登录账号 */
+    #[serde(default)]
+    pub extensions: BTreeMap<String, serde_json::Value>,
+}
+
+/** 与 Snapshot 字段相同 借用状态以免复制整个交易所 */
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    version: u32,
+    exchange: &'a Exchange,
+    clock: &'a SimulationClock,
+    extensions: &'a BTreeMap<String, serde_json::Value>,
+}
+
+/** 仅读取版本号 用于在解析完整内容前检查格式 */
+#[derive(Deserialize)]
+struct SnapshotHeader {
+    version: u32,
+}
+
+/** 序列化快照 */
+pub fn to_bytes(
+    exchange: &Exchange,
+    clock: &SimulationClock,
+    extensions: &BTreeMap<String, serde_json::Value>,
+) -> Result<Vec<u8>, SnapshotError> {
+    let snapshot = SnapshotRef {
+        version: SNAPSHOT_VERSION,
+        exchange,
+        clock,
+        extensions,
+    };
+    Ok(serde_json::to_vec(&snapshot)?)
+}
+
+/** 解析快照 版本不符时返回错误 */
+pub fn from_slice(bytes: &[u8]) -> Result<Snapshot, SnapshotError> {
+    let header: SnapshotHeader = serde_json::from_slice(bytes)?;
+    if header.version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(header.version));
+    }
+    Ok(serde_json::from_slice(bytes)?)
+}
+
+/** 写入快照文件 先写临时文件再重命名 写入中断时不会损坏已有快照 */
+pub fn write_file(path: impl AsRef<Path>, bytes: &[u8]) -> Result<(), SnapshotError> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    fs::write(&temp_path, bytes)?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/** 保存快照到文件 */
+pub fn save(
+    path: impl AsRef<Path>,
+    exchange: &Exchange,
+    clock: &SimulationClock,
+    extensions: &BTreeMap<String, serde_json::Value>,
+) -> Result<(), SnapshotError> {
+    let bytes = to_bytes(exchange, clock, extensions)?;
+    write_file(path, &bytes)
+}
+
+/** 从文件恢复快照 */
+pub fn load(path: impl AsRef<Path>) -> Result<Snapshot, SnapshotError> {
+    let bytes = fs::read(path)?;
+    from_slice(&bytes)
+}
@@ -1,15 +1,17 @@
 use crate::exchange_error::{ExchangeError, ExchangeResult};
+use crate::instrument::Board;
 use crate::order::{Order, OrderType};
 use crate::types::*;
-use serde::Serialize;
-use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // 股票结构体
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Stock {
     // 基本信息
     pub code: StockCode,
     pub name: String,
+    pub board: Board,
 
     // 价格信息
     pub start_price: Price,
@@ -23,29 +25,34 @@ pub struct Stock {
     pub price_history: Vec<PriceHistoryInfo>,
 
     // 订单管理结构
-    pub buy_orders: BTreeMap<u32, Vec<OrderId>>,
-    pub buy_quantities: BTreeMap<u32, u64>,
-    pub sell_orders: BTreeMap<u32, Vec<OrderId>>,
-    pub sell_quantities: BTreeMap<u32, u64>,
+    pub buy_orders: BTreeMap<Price, Vec<OrderId>>,
+    pub buy_quantities: BTreeMap<Price, u64>,
+    pub sell_orders: BTreeMap<Price, Vec<OrderId>>,
+    pub sell_quantities: BTreeMap<Price, u64>,
 
     // 买卖队列
-    pub order_queue: (Vec<(Price, u64)>, Vec<(Price, u64)>),
+    pub order_queue: OrderQueue,
 }
 
-#[derive(Clone, Debug, Serialize)]
+/** 买卖队列 (买盘 价格从高到低, 卖盘 价格从低到高) 每档为 (价格, 委托量) */
+pub type OrderQueue = (Vec<(Price, u64)>, Vec<(Price, u64)>);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PriceHistoryInfo {
     /** 时间戳 */
     pub timestamp: Timestamp,
+    /** 开盘价 该分钟的第一个价格 */
+    pub open_price: Price,
     /** 价格 */
     pub price: Price,
     /** 成交量 */
     pub volume: Quantity,
     /** 成交额 */
-    pub amount: u64,
+    pub amount: Money,
     /** 均价 */
-    pub average_price: u64,
+    pub average_price: Price,
     /** 涨跌额 */
-    pub price_change: i64,
+    pub price_change: Money,
     /** 涨跌幅 */
     pub price_change_rate: f64,
     /** 最低价 */
@@ -58,6 +65,7 @@ pub struct PriceHistoryInfo {
 pub struct StockInfo {
     pub code: StockCode,
     pub name: String,
+    pub board: Board,
     pub start_price: Price,
     pub current_price: Price,
     pub price_limit: PriceLimit,
@@ -65,7 +73,7 @@ pub struct StockInfo {
 }
 
 // 修改：日交易数据结构体
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DailyTradeInfo {
     pub opening_price: Option<Price>,
     pub closing_price: Option<Price>,
@@ -73,10 +81,10 @@ pub struct DailyTradeInfo {
     pub lowest_price: Price,
     pub price_amplitude: f64,
     pub total_volume: u64,
-    pub total_value: u64,
+    pub total_value: Money,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PriceLimit {
     pub upper: Price,
     pub lower: Price,
@@ -86,19 +94,23 @@ impl Stock {
     // 可以添加一些方法来管理订单和更新价格等
     pub fn new(code: String, name: String, start_price: Price) -> Self {
         Stock {
+            board: Board::from_code(&code),
             code,
             name,
             start_price,
             current_price: start_price,
-            price_limit: PriceLimit { upper: 0, lower: 0 },
+            price_limit: PriceLimit {
+                upper: Price::ZERO,
+                lower: Price::ZERO,
+            },
             daily_info: DailyTradeInfo {
                 opening_price: None,
                 closing_price: None,
-                highest_price: 0,
-                lowest_price: 0,
+                highest_price: Price::ZERO,
+                lowest_price: Price::ZERO,
                 price_amplitude: 0.0,
                 total_volume: 0,
-                total_value: 0,
+                total_value: Money::ZERO,
             },
             price_history: Vec::new(),
             buy_orders: BTreeMap::new(),
@@ -110,7 +122,7 @@ impl Stock {
     }
 
     pub fn update_price_limit(&mut self, price_limit_start: Price, price_limit_end: Price) {
-        if self.start_price > 0 {
+        if !self.start_price.is_zero() {
             self.price_limit = PriceLimit {
                 upper: price_limit_end,
                 lower: price_limit_start,
@@ -127,16 +139,16 @@ impl Stock {
         }
 
         daily_info.highest_price = daily_info.highest_price.max(self.current_price);
-        daily_info.lowest_price = if daily_info.lowest_price == 0 {
+        daily_info.lowest_price = if daily_info.lowest_price.is_zero() {
             self.current_price
         } else {
             daily_info.lowest_price.min(self.current_price)
         };
 
-        if daily_info.highest_price > 0 && daily_info.lowest_price > 0 {
-            daily_info.price_amplitude = (daily_info.highest_price as f64
-                - daily_info.lowest_price as f64)
-                / daily_info.lowest_price as f64
+        if !daily_info.highest_price.is_zero() && !daily_info.lowest_price.is_zero() {
+            daily_info.price_amplitude = (daily_info.highest_price.as_f64()
+                - daily_info.lowest_price.as_f64())
+                / daily_info.lowest_price.as_f64()
                 * 100.0;
         }
     }
@@ -145,14 +157,14 @@ impl Stock {
     pub fn update_order_queue(&mut self) {
         let buy_queue = self
             .buy_orders
-            .iter()
+            .keys()
             .rev()
-            .map(|(price, _)| (*price, self.buy_quantities.get(price).unwrap_or(&0).clone()))
+            .map(|price| (*price, *self.buy_quantities.get(price).unwrap_or(&0)))
             .collect();
         let sell_queue = self
             .sell_orders
-            .iter()
-            .map(|(price, _)| (*price, self.sell_quantities.get(price).unwrap_or(&0).clone()))
+            .keys()
+            .map(|price| (*price, *self.sell_quantities.get(price).unwrap_or(&0)))
             .collect();
 
         self.order_queue = (buy_queue, sell_queue);
@@ -179,25 +191,25 @@ impl Stock {
         self.daily_info = DailyTradeInfo {
             opening_price: None,
             closing_price: None,
-            highest_price: 0,
-            lowest_price: 0,
+            highest_price: Price::ZERO,
+            lowest_price: Price::ZERO,
             price_amplitude: 0.0,
             total_volume: 0,
-            total_value: 0,
+            total_value: Money::ZERO,
         };
     }
 
     // 修改：添加价格到价格历史，一分钟内的数据整合到一条数据内
     pub fn add_price_to_history(&mut self, timestamp: Timestamp, price: Price, volume: Quantity) {
-        let minute_timestamp = timestamp - (timestamp % 60); // 将时间戳向下取整到分钟
+        let minute_timestamp = timestamp.floor_to_minute(); // 将时间戳向下取整到分钟
 
         if let Some(last_item) = self.price_history.last_mut() {
             if last_item.timestamp == minute_timestamp {
                 // 更新同一分钟内的数据
                 last_item.volume += volume;
-                last_item.amount += price as u64 * volume as u64;
+                last_item.amount += price * volume;
                 last_item.average_price = if last_item.volume > 0 {
-                    last_item.amount / last_item.volume as u64
+                    last_item.amount.per_unit(last_item.volume as u64)
                 } else {
                     last_item.average_price // 保持原有均价
                 };
@@ -210,9 +222,9 @@ impl Stock {
                 last_item.price = price;
 
                 // 更新涨跌额和涨跌幅
-                last_item.price_change = price as i64 - self.start_price as i64;
-                last_item.price_change_rate = if self.start_price > 0 {
-                    (last_item.price_change as f64 / self.start_price as f64) * 100.0
+                last_item.price_change = price.diff(self.start_price);
+                last_item.price_change_rate = if !self.start_price.is_zero() {
+                    (last_item.price_change.as_f64() / self.start_price.as_f64()) * 100.0
                 } else {
                     0.0
                 };
@@ -222,20 +234,21 @@ impl Stock {
         }
 
         // 如果是新的一分钟或者是第一条记录，则添加新的记录
-        let amount = price as u64 * volume as u64;
-        let price_change = price as i64 - self.start_price as i64;
-        let price_change_rate = if self.start_price > 0 {
-            (price_change as f64 / self.start_price as f64) * 100.0
+        let amount = price * volume;
+        let price_change = price.diff(self.start_price);
+        let price_change_rate = if !self.start_price.is_zero() {
+            (price_change.as_f64() / self.start_price.as_f64()) * 100.0
         } else {
             0.0
         };
 
         self.price_history.push(PriceHistoryInfo {
             timestamp: minute_timestamp,
+            open_price: price,
             price,
             volume,
             amount,
-            average_price: if volume > 0 { price as u64 } else { 0 },
+            average_price: if volume > 0 { price } else { Price::ZERO },
             price_change,
             price_change_rate,
             min_price: price,
@@ -260,6 +273,15 @@ impl Stock {
         *quantities.entry(order.price).or_insert(0) += order.quantity as u64;
     }
 
+    // 清空买卖盘
+    pub fn clear_orders(&mut self) {
+        self.buy_orders.clear();
+        self.buy_quantities.clear();
+        self.sell_orders.clear();
+        self.sell_quantities.clear();
+        self.order_queue = (Vec::new(), Vec::new());
+    }
+
     // 获取最优价格
     pub fn best_price(&self, order_type: OrderType) -> Option<Price> {
         match order_type {
@@ -284,18 +306,33 @@ impl Stock {
                 orders.remove(&order.price);
             }
         }
-        *quantities.entry(order.price).or_insert(0) -= order.quantity as u64;
+        // 已成交部分在成交时已扣除 只扣除仍在队列中的数量
+        let queued = (order.quantity - order.filled_quantity()) as u64;
+        if let Some(quantity) = quantities.get_mut(&order.price) {
+            *quantity = quantity.saturating_sub(queued);
+            if *quantity == 0 {
+                quantities.remove(&order.price);
+            }
+        }
     }
 }
 
+// 按股票代码排序 撮合及机器人选股的顺序固定
+#[derive(Serialize, Deserialize)]
 pub struct StockManager {
-    stocks: HashMap<StockCode, Stock>,
+    stocks: BTreeMap<StockCode, Stock>,
+}
+
+impl Default for StockManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl StockManager {
     pub fn new() -> Self {
         StockManager {
-            stocks: HashMap::new(),
+            stocks: BTreeMap::new(),
         }
     }
 
@@ -367,7 +404,7 @@ impl StockManager {
         &self,
         stock_code: &StockCode,
         order_type: OrderType,
-    ) -> ExchangeResult<Option<u32>> {
+    ) -> ExchangeResult<Option<Price>> {
         let stock = self
             .stocks
             .get(stock_code)
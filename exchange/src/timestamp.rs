@@ -0,0 +1,229 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/** 一天内的时间 距午夜的毫秒数 */
+pub type TimeOfDay = u32;
+
+pub const MILLIS_PER_SECOND: TimeOfDay = 1000;
+pub const MILLIS_PER_MINUTE: TimeOfDay = 60 * MILLIS_PER_SECOND;
+pub const MILLIS_PER_HOUR: TimeOfDay = 60 * MILLIS_PER_MINUTE;
+pub const MILLIS_PER_DAY: TimeOfDay = 24 * MILLIS_PER_HOUR;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseTimestampError {
+    #[error("invalid time: {0}")]
+    InvalidTime(String),
+
+    #[error("invalid date: {0}")]
+    InvalidDate(String),
+}
+
+/**
+ * 时间戳 交易日 + 日内时间 精确到毫秒
+ *
+ * 先按日期再按日内时间排序 序列化为 "2023-01-03T09:30:00.125"
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    date: NaiveDate,
+    time: TimeOfDay,
+}
+
+// 时间戳格式化及解析
+pub trait TimestampBehavior: Sized {
+    fn format(&self) -> String;
+    fn parse(s: &str) -> Result<Self, ParseTimestampError>;
+}
+
+impl Timestamp {
+    /** 日内时间超过一天时截断到当天最后一毫秒 */
+    pub fn new(date: NaiveDate, time: TimeOfDay) -> Self {
+        Timestamp {
+            date,
+            time: time.min(MILLIS_PER_DAY - 1),
+        }
+    }
+
+    /** 当天零点 */
+    pub fn start_of_day(date: NaiveDate) -> Self {
+        Self::new(date, 0)
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    pub fn time(&self) -> TimeOfDay {
+        self.time
+    }
+
+    /** 同一交易日的另一时间 */
+    pub fn with_time(&self, time: TimeOfDay) -> Self {
+        Self::new(self.date, time)
+    }
+
+    /** 向下取整到分钟 */
+    pub fn floor_to_minute(&self) -> Self {
+        self.with_time(self.time - self.time % MILLIS_PER_MINUTE)
+    }
+
+    pub fn to_datetime(&self) -> NaiveDateTime {
+        let time = NaiveTime::from_num_seconds_from_midnight_opt(
+            self.time / MILLIS_PER_SECOND,
+            (self.time % MILLIS_PER_SECOND) * 1_000_000,
+        )
+        .unwrap_or(NaiveTime::MIN);
+        self.date.and_time(time)
+    }
+
+    /** 由日期时间构造 毫秒以下的部分舍去 */
+    pub fn from_datetime(datetime: NaiveDateTime) -> Self {
+        let time = datetime.time();
+        let millis = time.num_seconds_from_midnight() * MILLIS_PER_SECOND
+            + (time.nanosecond() / 1_000_000).min(MILLIS_PER_SECOND - 1);
+        Self::new(datetime.date(), millis)
+    }
+}
+
+impl TimestampBehavior for TimeOfDay {
+    /** 整秒时输出 "09:30:00" 否则输出 "09:30:00.125" */
+    fn format(&self) -> String {
+        let hours = self / MILLIS_PER_HOUR;
+        let minutes = (self % MILLIS_PER_HOUR) / MILLIS_PER_MINUTE;
+        let seconds = (self % MILLIS_PER_MINUTE) / MILLIS_PER_SECOND;
+        let millis = self % MILLIS_PER_SECOND;
+        if millis == 0 {
+            format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+        } else {
+            format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, seconds, millis)
+        }
+    }
+
+    /** 接受 "9:30" "09:30:00" 及 "09:30:00.125" */
+    fn parse(s: &str) -> Result<Self, ParseTimestampError> {
+        let invalid = || ParseTimestampError::InvalidTime(s.to_string());
+        let (clock, fraction) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+        let parts: Vec<&str> = clock.split(':').collect();
+        if parts.len() < 2 || parts.len() > 3 || fraction.len() > 3 {
+            return Err(invalid());
+        }
+        if !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let hours: u32 = parts[0].parse().map_err(|_| invalid())?;
+        let minutes: u32 = parts[1].parse().map_err(|_| invalid())?;
+        let seconds: u32 = match parts.get(2) {
+            Some(seconds) => seconds.parse().map_err(|_| invalid())?,
+            None => 0,
+        };
+        let millis: u32 = if fraction.is_empty() {
+            0
+        } else {
+            format!("{:0<3}", fraction).parse().map_err(|_| invalid())?
+        };
+        if hours > 23 || minutes > 59 || seconds > 59 {
+            return Err(invalid());
+        }
+
+        Ok(hours * MILLIS_PER_HOUR
+            + minutes * MILLIS_PER_MINUTE
+            + seconds * MILLIS_PER_SECOND
+            + millis)
+    }
+}
+
+impl TimestampBehavior for Timestamp {
+    fn format(&self) -> String {
+        self.to_string()
+    }
+
+    /** 接受 "2023-01-03T09:30:00.125" 或以空格分隔日期与时间 */
+    fn parse(s: &str) -> Result<Self, ParseTimestampError> {
+        let trimmed = s.trim();
+        let (date, time) = trimmed
+            .split_once(['T', ' '])
+            .ok_or_else(|| ParseTimestampError::InvalidDate(s.to_string()))?;
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| ParseTimestampError::InvalidDate(s.to_string()))?;
+        let time = <TimeOfDay as TimestampBehavior>::parse(time)
+            .map_err(|_| ParseTimestampError::InvalidTime(s.to_string()))?;
+        Ok(Timestamp::new(date, time))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}T{:02}:{:02}:{:02}.{:03}",
+            self.date.format("%Y-%m-%d"),
+            self.time / MILLIS_PER_HOUR,
+            (self.time % MILLIS_PER_HOUR) / MILLIS_PER_MINUTE,
+            (self.time % MILLIS_PER_MINUTE) / MILLIS_PER_SECOND,
+            self.time % MILLIS_PER_SECOND
+        )
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = ParseTimestampError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Timestamp as TimestampBehavior>::parse(s)
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+struct TimestampVisitor;
+
+impl Visitor<'_> for TimestampVisitor {
+    type Value = Timestamp;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a timestamp like 2023-01-03T09:30:00.125")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Timestamp, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(TimestampVisitor)
+    }
+}
+
+// Helper functions for easier conversion
+pub fn time_of_day_to_string(time: TimeOfDay) -> String {
+    time.format()
+}
+
+pub fn string_to_time_of_day(s: &str) -> Result<TimeOfDay, ParseTimestampError> {
+    <TimeOfDay as TimestampBehavior>::parse(s)
+}
+
+/** 日内时间按 "09:30:00" 格式序列化 用于 #[serde(with = "...")] */
+pub mod time_of_day_serde {
+    use super::{TimeOfDay, TimestampBehavior};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &TimeOfDay, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&time.format())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TimeOfDay, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        <TimeOfDay as TimestampBehavior>::parse(&s).map_err(serde::de::Error::custom)
+    }
+}
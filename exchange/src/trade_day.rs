@@ -1,14 +1,33 @@
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+#[derive(Serialize, Deserialize)]
 pub struct TradingCalendar {
     holidays: HashSet<NaiveDate>,
+    /** 周末是否交易 */
+    weekends_open: bool,
+}
+
+impl Default for TradingCalendar {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TradingCalendar {
     pub fn new() -> Self {
         TradingCalendar {
             holidays: HashSet::new(),
+            weekends_open: false,
+        }
+    }
+
+    /** 每天都是交易日 用于全天候交易的场所 */
+    pub fn every_day() -> Self {
+        TradingCalendar {
+            holidays: HashSet::new(),
+            weekends_open: true,
         }
     }
 
@@ -20,7 +39,8 @@ impl TradingCalendar {
         if self.holidays.contains(&date) {
             return false;
         }
-        false
+        // 周末休市
+        self.weekends_open || !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
     }
 
     pub fn next_trade_day(&self, mut date: NaiveDate) -> NaiveDate {
@@ -1,52 +1,111 @@
 use crate::exchange::Exchange;
-use crate::trading_strategy::{get_trading_strategy, TradingAction, TradingStrategy};
+use crate::random::SimRng;
+use crate::trading_strategy::{
+    ActionResult, MarketContext, StrategyInstance, TradingAction, TradingStrategy,
+};
 use crate::types::*;
 use crate::user::User;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
+#[derive(Serialize, Deserialize)]
 pub struct TradingBot {
     #[allow(dead_code)]
     user_id: UserId,
     strategy: TradingStrategy,
+    /** 机器人独立的随机数生成器 由主种子派生 */
+    rng: SimRng,
+    /** 上一次决策各操作的执行结果 下一次决策时提供给策略 */
+    #[serde(default)]
+    last_results: Vec<ActionResult>,
+    /** 策略实例 保存策略在多次决策之间的状态 首次决策时创建 */
+    #[serde(default)]
+    instance: Option<StrategyInstance>,
+    /** 已提供给策略的最后一笔成交 */
+    #[serde(default)]
+    last_trade_id: TradeId,
 }
 
 impl TradingBot {
-    pub fn new(user_id: UserId, strategy: TradingStrategy) -> Self {
-        TradingBot { user_id, strategy }
+    pub fn new(user_id: UserId, strategy: TradingStrategy, rng: SimRng) -> Self {
+        TradingBot {
+            user_id,
+            strategy,
+            rng,
+            last_results: Vec::new(),
+            instance: None,
+            last_trade_id: 0,
+        }
+    }
+
+    pub fn execute_strategy(&mut self, user: &User, exchange: &Exchange) -> Vec<TradingAction> {
+        let strategy = &self.strategy;
+        let instance = self
+            .instance
+            .get_or_insert_with(|| StrategyInstance::new(strategy));
+        let context = MarketContext::new(exchange, user, &self.last_results, self.last_trade_id);
+        if let Some(fill) = context.fills().last() {
+            self.last_trade_id = fill.trade_id;
+        }
+        instance.as_decide().decide(&context, &mut self.rng)
     }
 
-    pub fn execute_strategy(&self, user: &User, exchange: &Exchange) -> TradingAction {
-        let strategy = get_trading_strategy(self.strategy.clone());
-        strategy.decide(user, exchange)
+    /** 上一次决策的执行结果 */
+    pub fn last_results(&self) -> &[ActionResult] {
+        &self.last_results
     }
 }
 
+// 按用户ID顺序执行 保证相同种子的模拟结果相同
+#[derive(Serialize, Deserialize)]
 pub struct TradingBotManager {
-    bots: HashMap<UserId, TradingBot>,
+    bots: BTreeMap<UserId, TradingBot>,
+}
+
+impl Default for TradingBotManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TradingBotManager {
     pub fn new() -> Self {
         TradingBotManager {
-            bots: HashMap::new(),
+            bots: BTreeMap::new(),
         }
     }
 
-    pub fn add_bot(&mut self, user_id: UserId, strategy: TradingStrategy) {
-        let bot = TradingBot::new(user_id, strategy);
+    pub fn add_bot(&mut self, user_id: UserId, strategy: TradingStrategy, rng: SimRng) {
+        let bot = TradingBot::new(user_id, strategy, rng);
         self.bots.insert(user_id, bot);
     }
 
+    pub fn is_bot(&self, user_id: UserId) -> bool {
+        self.bots.contains_key(&user_id)
+    }
+
     pub fn remove_bot(&mut self, user_id: UserId) -> Option<TradingBot> {
         self.bots.remove(&user_id)
     }
 
-    pub fn execute_strategy(&self, exchange: &Exchange) -> Vec<(UserId, TradingAction)> {
+    pub fn get_bot(&self, user_id: UserId) -> Option<&TradingBot> {
+        self.bots.get(&user_id)
+    }
+
+    /** 记录各机器人本次决策的执行结果 没有操作的机器人结果为空 */
+    pub fn report_results(&mut self, mut results: BTreeMap<UserId, Vec<ActionResult>>) {
+        for (user_id, bot) in self.bots.iter_mut() {
+            bot.last_results = results.remove(user_id).unwrap_or_default();
+        }
+    }
+
+    pub fn execute_strategy(&mut self, exchange: &Exchange) -> Vec<(UserId, TradingAction)> {
         let mut actions = Vec::new();
-        for (user_id, bot) in self.bots.iter() {
+        for (user_id, bot) in self.bots.iter_mut() {
             let user = exchange.user_manager.get_user(*user_id).unwrap();
-            let action = bot.execute_strategy(&user, exchange);
-            actions.push((*user_id, action));
+            for action in bot.execute_strategy(user, exchange) {
+                actions.push((*user_id, action));
+            }
         }
         actions
     }
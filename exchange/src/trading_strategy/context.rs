@@ -0,0 +1,181 @@
+use super::ActionResult;
+use crate::config::TradingPeriod;
+use crate::exchange::Exchange;
+use crate::instrument::InstrumentRules;
+use crate::order::{Order, OrderType};
+use crate::stock::{PriceHistoryInfo, PriceLimit};
+use crate::types::*;
+use crate::user::User;
+use chrono::NaiveDate;
+
+/** 一只股票的行情 */
+#[derive(Clone, Debug)]
+pub struct Quote {
+    pub stock_code: StockCode,
+    /** 最新价 */
+    pub last_price: Price,
+    /** 昨收价 */
+    pub start_price: Price,
+    pub price_limit: PriceLimit,
+    /** 买盘 价格从高到低 (价格, 数量) 含自己的委托 */
+    pub bids: Vec<(Price, u64)>,
+    /** 卖盘 价格从低到高 (价格, 数量) 含自己的委托 */
+    pub asks: Vec<(Price, u64)>,
+    pub rules: InstrumentRules,
+}
+
+impl Quote {
+    pub fn best_bid(&self) -> Option<Price> {
+        self.bids.first().map(|(price, _)| *price)
+    }
+
+    pub fn best_ask(&self) -> Option<Price> {
+        self.asks.first().map(|(price, _)| *price)
+    }
+}
+
+/** 自己的一笔成交 */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fill {
+    pub trade_id: TradeId,
+    pub order_id: OrderId,
+    pub stock_code: StockCode,
+    pub order_type: OrderType,
+    pub price: Price,
+    pub quantity: Quantity,
+    pub timestamp: Timestamp,
+}
+
+/**
+ * 决策时机器人可见的信息 只读
+ *
+ * 包括行情、价格历史、自己的账户、未完成的委托、上一次决策以来的成交及时钟
+ */
+pub struct MarketContext<'a> {
+    exchange: &'a Exchange,
+    user: &'a User,
+    open_orders: Vec<&'a Order>,
+    fills: Vec<Fill>,
+    results: &'a [ActionResult],
+}
+
+impl<'a> MarketContext<'a> {
+    /** last_trade_id 为上一次决策时已看到的最后一笔成交 */
+    pub fn new(
+        exchange: &'a Exchange,
+        user: &'a User,
+        results: &'a [ActionResult],
+        last_trade_id: TradeId,
+    ) -> Self {
+        let open_orders = exchange
+            .order_manager
+            .get_user_orders(user.id)
+            .into_iter()
+            .filter(|order| order.remaining_quantity > 0)
+            .collect();
+        let fills = exchange
+            .log_manager
+            .user_logs_after(user.id, last_trade_id)
+            .map(|log| {
+                let (order_type, order_id) = if log.buyer_id == user.id {
+                    (OrderType::Buy, log.buy_order_id)
+                } else {
+                    (OrderType::Sell, log.sell_order_id)
+                };
+                Fill {
+                    trade_id: log.id,
+                    order_id,
+                    stock_code: log.stock_code.clone(),
+                    order_type,
+                    price: log.price,
+                    quantity: log.quantity,
+                    timestamp: log.timestamp,
+                }
+            })
+            .collect();
+        MarketContext {
+            exchange,
+            user,
+            open_orders,
+            fills,
+            results,
+        }
+    }
+
+    /** 自己的账户 现金及持仓 */
+    pub fn user(&self) -> &User {
+        self.user
+    }
+
+    /** 自己尚未完成的委托 按编号排列 */
+    pub fn open_orders(&self) -> &[&'a Order] {
+        &self.open_orders
+    }
+
+    /** 上一次决策以来自己的成交 按编号排列 */
+    pub fn fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    /** 上一次决策各操作的执行结果 */
+    pub fn results(&self) -> &[ActionResult] {
+        self.results
+    }
+
+    pub fn now(&self) -> Timestamp {
+        self.exchange.get_current_timestamp()
+    }
+
+    pub fn trade_day(&self) -> NaiveDate {
+        self.exchange.current_trade_day()
+    }
+
+    pub fn current_period(&self) -> Option<&TradingPeriod> {
+        self.exchange.config.get_current_period()
+    }
+
+    /** 全部股票代码 按代码排列 */
+    pub fn stock_codes(&self) -> Vec<StockCode> {
+        self.exchange.stock_manager.get_stock_codes()
+    }
+
+    pub fn quote(&self, stock_code: &str) -> Option<Quote> {
+        let stock = self
+            .exchange
+            .stock_manager
+            .get_stock(&stock_code.to_string())?;
+        let level =
+            |(price, quantity): (&Price, &u64)| (*quantity > 0).then_some((*price, *quantity));
+        Some(Quote {
+            stock_code: stock.code.clone(),
+            last_price: stock.current_price,
+            start_price: stock.start_price,
+            price_limit: stock.price_limit.clone(),
+            bids: stock
+                .buy_quantities
+                .iter()
+                .rev()
+                .filter_map(level)
+                .collect(),
+            asks: stock.sell_quantities.iter().filter_map(level).collect(),
+            rules: self
+                .exchange
+                .get_instrument_rules(stock_code)
+                .cloned()
+                .unwrap_or_else(InstrumentRules::main_board),
+        })
+    }
+
+    /** 基本面价值 未启用基本面价值过程时返回 None 只应由知情交易者使用 */
+    pub fn fundamental_value(&self, stock_code: &str) -> Option<Price> {
+        self.exchange.fundamental_value(stock_code)
+    }
+
+    /** 价格历史 按时间排列 包括导入的历史行情 */
+    pub fn bars(&self, stock_code: &str) -> &'a [PriceHistoryInfo] {
+        self.exchange
+            .stock_manager
+            .get_stock(&stock_code.to_string())
+            .map_or(&[], |stock| stock.price_history.as_slice())
+    }
+}
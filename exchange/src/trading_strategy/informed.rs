@@ -0,0 +1,57 @@
+use super::signal::trade_toward;
+use super::{MarketContext, TradingAction, TradingStrategyDecide};
+use crate::order::OrderType;
+use crate::random::SimRng;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+
+/** 知情交易者参数 */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InformedParams {
+    /** 价格偏离基本面价值超过该比例时交易 */
+    pub threshold: f64,
+    /** 每次决策最多买卖的数量 */
+    pub order_size: Quantity,
+    /** 最大持仓 */
+    pub max_position: u64,
+}
+
+/**
+ * 知情交易策略 知道股票的基本面价值 朝价值方向交易
+ *
+ * 卖一低于价值时以卖一价买入 买一高于价值时以买一价卖出 没有基本面价值的股票不交易
+ */
+#[derive(Serialize, Deserialize)]
+pub struct InformedStrategy(pub InformedParams);
+
+impl TradingStrategyDecide for InformedStrategy {
+    fn decide(&mut self, context: &MarketContext, _rng: &mut SimRng) -> Vec<TradingAction> {
+        let params = &self.0;
+        let mut actions = Vec::new();
+        for stock_code in context.stock_codes() {
+            let Some(value) = context.fundamental_value(&stock_code) else {
+                continue;
+            };
+            let quote = context.quote(&stock_code).unwrap();
+            let value = value.as_f64();
+            let ask = quote.best_ask().unwrap_or(quote.last_price).as_f64();
+            let bid = quote.best_bid().unwrap_or(quote.last_price).as_f64();
+            let direction = if ask < value * (1.0 - params.threshold) {
+                Some(OrderType::Buy)
+            } else if bid > value * (1.0 + params.threshold) {
+                Some(OrderType::Sell)
+            } else {
+                None
+            };
+            trade_toward(
+                context,
+                &quote,
+                direction,
+                params.order_size,
+                params.max_position,
+                &mut actions,
+            );
+        }
+        actions
+    }
+}
@@ -0,0 +1,194 @@
+use super::{MarketContext, Quote, TradingAction, TradingStrategyDecide};
+use crate::order::{Order, OrderType};
+use crate::random::SimRng;
+use crate::types::*;
+use crate::user::User;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/** 做市商参数 */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketMakerParams {
+    /** 买卖报价之间的价差 以最小价格变动单位计 */
+    pub spread_ticks: u32,
+    /** 每侧报价数量 */
+    pub quote_size: Quantity,
+    /** 目标持仓 持仓高于目标时报价下移 低于目标时报价上移 */
+    pub target_position: u64,
+    /** 最大持仓 达到后只报卖价 */
+    pub max_position: u64,
+    /** 持仓达到最大或为0时报价的偏移 以最小价格变动单位计 */
+    pub skew_ticks: u32,
+    /** 挂单价格与新报价相差达到该值时撤单重报 以最小价格变动单位计 */
+    pub requote_ticks: u32,
+}
+
+/**
+ * 做市策略 以盘口中间价为公允价双边报价
+ *
+ * 报价按持仓偏离目标的比例偏移 市场移动后撤销偏离的挂单并重新报价
+ */
+#[derive(Serialize, Deserialize)]
+pub struct MarketMakerStrategy(pub MarketMakerParams);
+
+/** 一侧的报价 */
+struct SideQuote {
+    price: Price,
+    /** 撤销的同侧挂单释放的现金或持仓 */
+    released: u64,
+}
+
+impl TradingStrategyDecide for MarketMakerStrategy {
+    fn decide(&mut self, context: &MarketContext, _rng: &mut SimRng) -> Vec<TradingAction> {
+        let mut open_orders: BTreeMap<&StockCode, Vec<&Order>> = BTreeMap::new();
+        for order in context.open_orders().iter().copied() {
+            open_orders
+                .entry(&order.stock_code)
+                .or_default()
+                .push(order);
+        }
+
+        let mut actions = Vec::new();
+        for stock_code in context.stock_codes() {
+            let quote = context.quote(&stock_code).unwrap();
+            let orders = open_orders.remove(&stock_code).unwrap_or_default();
+            self.quote_stock(context.user(), &quote, &orders, &mut actions);
+        }
+        // 已删除股票的挂单全部撤销
+        for order in open_orders.into_values().flatten() {
+            actions.push(TradingAction::Cancel { order_id: order.id });
+        }
+        // 先撤单释放资金及持仓 再下新单
+        actions.sort_by_key(|action| !matches!(action, TradingAction::Cancel { .. }));
+        actions
+    }
+}
+
+impl MarketMakerStrategy {
+    fn quote_stock(
+        &self,
+        user: &User,
+        stock: &Quote,
+        orders: &[&Order],
+        actions: &mut Vec<TradingAction>,
+    ) {
+        let params = &self.0;
+        let rules = &stock.rules;
+        let tick = rules.tick_size.raw().max(1) as i64;
+        let (lower, upper) = (
+            stock.price_limit.lower.raw() as i64,
+            stock.price_limit.upper.raw() as i64,
+        );
+
+        // 持仓相对目标的偏离 -1 到 1 之间
+        let holding = user.holdings.get(&stock.stock_code);
+        let position = holding.map_or(0, |holding| holding.quantity);
+        let deviation = if position >= params.target_position {
+            let room = params
+                .max_position
+                .saturating_sub(params.target_position)
+                .max(1);
+            (position - params.target_position) as f64 / room as f64
+        } else {
+            -((params.target_position - position) as f64 / params.target_position as f64)
+        }
+        .clamp(-1.0, 1.0);
+        let skew = (deviation * params.skew_ticks as f64).round() as i64;
+
+        let fair_value = Self::fair_value(stock, orders);
+        let reservation = fair_value.round_to(rules.tick_size).raw() as i64 - skew * tick;
+        let spread = params.spread_ticks.max(1) as i64;
+        let bid = (reservation - spread / 2 * tick).clamp(lower, upper);
+        let ask = (bid + spread * tick).clamp(lower, upper);
+
+        let requote = params.requote_ticks.max(1) as i64 * tick;
+        let mut quote_side = |order_type: OrderType, target: Option<i64>| {
+            let mut kept = false;
+            let mut released = 0;
+            for order in orders.iter().filter(|order| order.order_type == order_type) {
+                let close = target
+                    .is_some_and(|target| (order.price.raw() as i64 - target).abs() < requote);
+                if close && !kept {
+                    kept = true;
+                } else {
+                    released += match order_type {
+                        OrderType::Buy => {
+                            (order.price * order.remaining_quantity + order.frozen_fee).raw() as u64
+                        }
+                        OrderType::Sell => order.remaining_quantity as u64,
+                    };
+                    actions.push(TradingAction::Cancel { order_id: order.id });
+                }
+            }
+            match target {
+                Some(target) if !kept => Some(SideQuote {
+                    price: Price::from_raw(target as u32),
+                    released,
+                }),
+                _ => None,
+            }
+        };
+
+        // 达到最大持仓停止买入 买卖报价重叠时只报卖价
+        let can_buy = position + (params.quote_size as u64) <= params.max_position && bid < ask;
+        let buy = quote_side(OrderType::Buy, Some(bid).filter(|_| can_buy && bid > 0));
+        let sell = quote_side(OrderType::Sell, Some(ask).filter(|ask| *ask > 0));
+
+        if let Some(quote) = buy {
+            // 预留交易费用
+            let budget =
+                (user.available_balance() + Money::from_raw(quote.released as i64)).mul_rate(0.99);
+            let room = params.max_position.saturating_sub(position);
+            let quantity = (params.quote_size as u64)
+                .min(budget.quantity_at(quote.price))
+                .min(room)
+                .min(Quantity::MAX as u64) as Quantity;
+            let quantity = rules.round_buy_quantity(quantity);
+            if quantity > 0 {
+                actions.push(TradingAction::Buy {
+                    stock_code: stock.stock_code.clone(),
+                    price: quote.price,
+                    quantity,
+                });
+            }
+        }
+        if let Some(quote) = sell {
+            let available =
+                holding.map_or(0, |holding| holding.available_quantity) + quote.released;
+            let quantity = rules.round_sell_quantity(params.quote_size, available);
+            if quantity > 0 {
+                actions.push(TradingAction::Sell {
+                    stock_code: stock.stock_code.clone(),
+                    price: quote.price,
+                    quantity,
+                });
+            }
+        }
+    }
+
+    /** 其他参与者的买一卖一中间价 一侧没有挂单时取最新价 */
+    fn fair_value(stock: &Quote, orders: &[&Order]) -> Price {
+        // 扣除自己在各价位的挂单
+        let best_other = |levels: &[(Price, u64)], order_type: OrderType| {
+            levels
+                .iter()
+                .find(|(price, quantity)| {
+                    let own: u64 = orders
+                        .iter()
+                        .filter(|order| order.order_type == order_type && order.price == *price)
+                        .map(|order| order.remaining_quantity as u64)
+                        .sum();
+                    *quantity > own
+                })
+                .map(|(price, _)| *price)
+        };
+        let best_bid = best_other(&stock.bids, OrderType::Buy);
+        let best_ask = best_other(&stock.asks, OrderType::Sell);
+        match (best_bid, best_ask) {
+            (Some(bid), Some(ask)) if bid < ask => {
+                Price::from_raw(((bid.raw() as u64 + ask.raw() as u64) / 2) as u32)
+            }
+            _ => stock.last_price,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use super::signal::{moving_average, standard_deviation, vwap, BarTrader};
+use super::{MarketContext, TradingAction, TradingStrategyDecide};
+use crate::order::OrderType;
+use crate::random::SimRng;
+use crate::stock::PriceHistoryInfo;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+
+/** 均值回归的基准 */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReversionSignal {
+    /** 收盘价偏离成交量加权均价的比例超过 threshold 时反向买卖 */
+    Vwap,
+    /** 收盘价偏离均值超过 threshold 倍标准差时反向买卖 */
+    Bollinger,
+}
+
+/** 均值回归参数 */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MeanReversionParams {
+    pub signal: ReversionSignal,
+    /** 计算均价或布林带的K线数 */
+    pub lookback: usize,
+    /** 偏离阈值 VWAP 为比例 布林带为标准差倍数 */
+    pub threshold: f64,
+    /** 每根K线最多买卖的数量 */
+    pub order_size: Quantity,
+    /** 最大持仓 */
+    pub max_position: u64,
+}
+
+/**
+ * 均值回归策略 价格偏离均价时反向买卖
+ *
+ * 每根新完成的K线决策一次 以对手价委托
+ */
+#[derive(Serialize, Deserialize)]
+pub struct MeanReversionStrategy {
+    params: MeanReversionParams,
+    trader: BarTrader,
+}
+
+impl MeanReversionStrategy {
+    pub fn new(params: MeanReversionParams) -> Self {
+        MeanReversionStrategy {
+            params,
+            trader: BarTrader::default(),
+        }
+    }
+
+    fn signal(params: &MeanReversionParams, bars: &[PriceHistoryInfo]) -> Option<OrderType> {
+        let lookback = params.lookback.max(2);
+        if bars.len() < lookback {
+            return None;
+        }
+        let window = &bars[bars.len() - lookback..];
+        let close = window.last()?.price.as_f64();
+        // 偏离程度 正数表示高于均值
+        let deviation = match params.signal {
+            ReversionSignal::Vwap => {
+                let vwap = vwap(window)?;
+                close / vwap - 1.0
+            }
+            ReversionSignal::Bollinger => {
+                let mean = moving_average(window, lookback);
+                let deviation = standard_deviation(window, mean);
+                if deviation == 0.0 {
+                    return None;
+                }
+                (close - mean) / deviation
+            }
+        };
+        if deviation < -params.threshold {
+            Some(OrderType::Buy)
+        } else if deviation > params.threshold {
+            Some(OrderType::Sell)
+        } else {
+            None
+        }
+    }
+}
+
+impl TradingStrategyDecide for MeanReversionStrategy {
+    fn decide(&mut self, context: &MarketContext, _rng: &mut SimRng) -> Vec<TradingAction> {
+        let params = &self.params;
+        self.trader
+            .decide(context, params.order_size, params.max_position, |bars| {
+                Self::signal(params, bars)
+            })
+    }
+}
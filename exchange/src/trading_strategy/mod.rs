@@ -1,32 +1,113 @@
-use crate::exchange::Exchange;
+use crate::random::SimRng;
 use crate::types::*;
-use crate::user::User;
+use serde::{Deserialize, Serialize};
 
+mod context;
+mod informed;
+mod market_maker;
+mod mean_reversion;
+mod noise;
+mod signal;
 mod simple_random;
 mod trade_random;
+mod trend_following;
 
+pub use self::context::{Fill, MarketContext, Quote};
+pub use self::informed::InformedParams;
+use self::informed::InformedStrategy;
+pub use self::market_maker::MarketMakerParams;
+use self::market_maker::MarketMakerStrategy;
+use self::mean_reversion::MeanReversionStrategy;
+pub use self::mean_reversion::{MeanReversionParams, ReversionSignal};
+pub use self::noise::NoiseParams;
+use self::noise::NoiseStrategy;
 use self::simple_random::RandomStrategy;
 use self::trade_random::TradeRandomStrategy;
+use self::trend_following::TrendFollowingStrategy;
+pub use self::trend_following::{TrendFollowingParams, TrendSignal};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TradingStrategy {
     /** 简单随机策略 */
     SimpleRandom,
     /** 交易随机策略 根据买卖盘口随机交易 */
     TradeRandom(u8),
+    /** 做市策略 围绕公允价双边报价 */
+    MarketMaker(MarketMakerParams),
+    /** 趋势跟踪策略 按均线交叉或突破顺势交易 */
+    TrendFollowing(TrendFollowingParams),
+    /** 均值回归策略 价格偏离均价时反向交易 */
+    MeanReversion(MeanReversionParams),
+    /** 知情交易策略 朝基本面价值方向交易 */
+    Informed(InformedParams),
+    /** 噪声交易策略 在基本面价值附近随机交易 */
+    Noise(NoiseParams),
 }
 
-pub fn get_trading_strategy(strategy: TradingStrategy) -> Box<dyn TradingStrategyDecide> {
-    match strategy {
-        TradingStrategy::SimpleRandom => Box::new(RandomStrategy),
-        TradingStrategy::TradeRandom(n) => Box::new(TradeRandomStrategy(n)),
+/**
+ * 机器人持有的策略实例 决策之间保留状态 随快照保存
+ *
+ * 按配置的策略创建 每个机器人一个实例
+ */
+#[derive(Serialize, Deserialize)]
+pub(crate) enum StrategyInstance {
+    SimpleRandom(RandomStrategy),
+    TradeRandom(TradeRandomStrategy),
+    MarketMaker(MarketMakerStrategy),
+    TrendFollowing(TrendFollowingStrategy),
+    MeanReversion(MeanReversionStrategy),
+    Informed(InformedStrategy),
+    Noise(NoiseStrategy),
+}
+
+impl StrategyInstance {
+    pub(crate) fn new(strategy: &TradingStrategy) -> Self {
+        match strategy {
+            TradingStrategy::SimpleRandom => StrategyInstance::SimpleRandom(RandomStrategy),
+            TradingStrategy::TradeRandom(n) => {
+                StrategyInstance::TradeRandom(TradeRandomStrategy(*n))
+            }
+            TradingStrategy::MarketMaker(params) => {
+                StrategyInstance::MarketMaker(MarketMakerStrategy(params.clone()))
+            }
+            TradingStrategy::TrendFollowing(params) => {
+                StrategyInstance::TrendFollowing(TrendFollowingStrategy::new(params.clone()))
+            }
+            TradingStrategy::MeanReversion(params) => {
+                StrategyInstance::MeanReversion(MeanReversionStrategy::new(params.clone()))
+            }
+            TradingStrategy::Informed(params) => {
+                StrategyInstance::Informed(InformedStrategy(params.clone()))
+            }
+            TradingStrategy::Noise(params) => {
+                StrategyInstance::Noise(NoiseStrategy(params.clone()))
+            }
+        }
+    }
+
+    pub(crate) fn as_decide(&mut self) -> &mut dyn TradingStrategyDecide {
+        match self {
+            StrategyInstance::SimpleRandom(strategy) => strategy,
+            StrategyInstance::TradeRandom(strategy) => strategy,
+            StrategyInstance::MarketMaker(strategy) => strategy,
+            StrategyInstance::TrendFollowing(strategy) => strategy,
+            StrategyInstance::MeanReversion(strategy) => strategy,
+            StrategyInstance::Informed(strategy) => strategy,
+            StrategyInstance::Noise(strategy) => strategy,
+        }
     }
 }
 
 pub trait TradingStrategyDecide {
-    fn decide(&self, user: &User, exchange: &Exchange) -> TradingAction;
+    /**
+     * 返回本次决策的操作 按顺序执行 不操作时返回空列表
+     *
+     * 策略可在决策之间保留状态 随机决策须使用传入的随机数生成器 以保证模拟可复现
+     */
+    fn decide(&mut self, context: &MarketContext, rng: &mut SimRng) -> Vec<TradingAction>;
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TradingAction {
     Buy {
         stock_code: StockCode,
@@ -38,5 +119,32 @@ pub enum TradingAction {
         price: Price,
         quantity: Quantity,
     },
-    Hold,
+    /** 撤销自己的委托 */
+    Cancel { order_id: OrderId },
+    /** 改单 撤销自己委托的未成交部分 以新的价格及数量重新委托 */
+    Amend {
+        order_id: OrderId,
+        price: Price,
+        quantity: Quantity,
+    },
+}
+
+/** 操作的执行结果 */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActionOutcome {
+    /** 已委托 */
+    Submitted { order_id: OrderId },
+    /** 已撤单 */
+    Cancelled,
+    /** 已改单 新委托的编号 */
+    Amended { order_id: OrderId },
+    /** 被交易所拒绝 */
+    Rejected { reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionResult {
+    pub action: TradingAction,
+    pub outcome: ActionOutcome,
 }
@@ -0,0 +1,66 @@
+use super::signal::{cancel_orders, place_order};
+use super::{MarketContext, TradingAction, TradingStrategyDecide};
+use crate::order::OrderType;
+use crate::random::{standard_normal, SimRng};
+use crate::types::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/** 噪声交易者参数 */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoiseParams {
+    /** 委托价相对锚定价的标准差 比例 */
+    pub noise: f64,
+    /** 每次委托的最大数量 */
+    pub order_size: Quantity,
+    /** 每次决策下单的概率 */
+    pub probability: f64,
+}
+
+/**
+ * 噪声交易策略 在锚定价附近随机买卖
+ *
+ * 锚定价为基本面价值 没有基本面价值时为最新价 下单前撤销该股票之前的委托
+ */
+#[derive(Serialize, Deserialize)]
+pub struct NoiseStrategy(pub NoiseParams);
+
+impl TradingStrategyDecide for NoiseStrategy {
+    fn decide(&mut self, context: &MarketContext, rng: &mut SimRng) -> Vec<TradingAction> {
+        let params = &self.0;
+        let stock_codes = context.stock_codes();
+        if stock_codes.is_empty() || !rng.gen_bool(params.probability.clamp(0.0, 1.0)) {
+            return Vec::new();
+        }
+
+        let stock_code = &stock_codes[rng.gen_range(0..stock_codes.len())];
+        let quote = context.quote(stock_code).unwrap();
+        let anchor = context
+            .fundamental_value(stock_code)
+            .unwrap_or(quote.last_price);
+        let price = anchor.scale((1.0 + params.noise * standard_normal(rng)).max(0.0));
+        let price = price
+            .round_to(quote.rules.tick_size)
+            .clamp(quote.price_limit.lower, quote.price_limit.upper);
+        let order_type = if rng.gen_bool(0.5) {
+            OrderType::Buy
+        } else {
+            OrderType::Sell
+        };
+        let quantity = rng.gen_range(1..=params.order_size.max(1));
+
+        let mut actions = Vec::new();
+        let released = cancel_orders(context, stock_code, &mut actions);
+        place_order(
+            context,
+            &quote,
+            order_type,
+            price,
+            quantity,
+            u64::MAX,
+            released,
+            &mut actions,
+        );
+        actions
+    }
+}
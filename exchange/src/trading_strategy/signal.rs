@@ -0,0 +1,209 @@
+use super::{MarketContext, Quote, TradingAction};
+use crate::order::OrderType;
+use crate::stock::PriceHistoryInfo;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/**
+ * 按K线信号调整持仓 趋势跟踪及均值回归策略共用
+ *
+ * 每只股票每根新完成的K线决策一次 决策时撤销上一根K线未成交的委托
+ * 信号为买入时向最大持仓买入 为卖出时卖出持仓 每次最多 order_size
+ */
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct BarTrader {
+    /** 各股票已决策的最后一根K线 */
+    last_bars: BTreeMap<StockCode, Timestamp>,
+}
+
+impl BarTrader {
+    /**
+     * 对每只有新完成K线的股票按 signal 计算的方向调整持仓
+     *
+     * signal 的参数为已完成的K线 按时间排列 数量不足时返回 None
+     */
+    pub(super) fn decide<F>(
+        &mut self,
+        context: &MarketContext,
+        order_size: Quantity,
+        max_position: u64,
+        signal: F,
+    ) -> Vec<TradingAction>
+    where
+        F: Fn(&[PriceHistoryInfo]) -> Option<OrderType>,
+    {
+        let current_minute = context.now().floor_to_minute();
+        let mut actions = Vec::new();
+        for stock_code in context.stock_codes() {
+            // 当前分钟的K线尚未完成
+            let bars = context.bars(&stock_code);
+            let completed = bars.partition_point(|bar| bar.timestamp < current_minute);
+            let bars = &bars[..completed];
+            let Some(last_bar) = bars.last() else {
+                continue;
+            };
+            if self.last_bars.get(&stock_code) == Some(&last_bar.timestamp) {
+                continue;
+            }
+            self.last_bars
+                .insert(stock_code.clone(), last_bar.timestamp);
+
+            let quote = context.quote(&stock_code).unwrap();
+            let direction = signal(bars);
+            trade_toward(
+                context,
+                &quote,
+                direction,
+                order_size,
+                max_position,
+                &mut actions,
+            );
+        }
+        actions
+    }
+}
+
+/** 撤销该股票的挂单 按方向以对手价下单 */
+pub(super) fn trade_toward(
+    context: &MarketContext,
+    quote: &Quote,
+    direction: Option<OrderType>,
+    order_size: Quantity,
+    max_position: u64,
+    actions: &mut Vec<TradingAction>,
+) {
+    let released = cancel_orders(context, &quote.stock_code, actions);
+    let price = match direction {
+        Some(OrderType::Buy) => quote
+            .best_ask()
+            .unwrap_or(quote.last_price)
+            .min(quote.price_limit.upper),
+        Some(OrderType::Sell) => quote
+            .best_bid()
+            .unwrap_or(quote.last_price)
+            .max(quote.price_limit.lower),
+        None => return,
+    };
+    let order_type = direction.unwrap();
+    place_order(
+        context,
+        quote,
+        order_type,
+        price,
+        order_size,
+        max_position,
+        released,
+        actions,
+    );
+}
+
+/** 撤单释放的现金及持仓 */
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct Released {
+    balance: Money,
+    quantity: u64,
+}
+
+/** 撤销自己在该股票上的全部挂单 */
+pub(super) fn cancel_orders(
+    context: &MarketContext,
+    stock_code: &str,
+    actions: &mut Vec<TradingAction>,
+) -> Released {
+    let mut released = Released::default();
+    for order in context
+        .open_orders()
+        .iter()
+        .filter(|order| order.stock_code == stock_code)
+    {
+        match order.order_type {
+            OrderType::Buy => {
+                released.balance += order.price * order.remaining_quantity + order.frozen_fee
+            }
+            OrderType::Sell => released.quantity += order.remaining_quantity as u64,
+        }
+        actions.push(TradingAction::Cancel { order_id: order.id });
+    }
+    released
+}
+
+/**
+ * 按可用资金、可卖持仓及最大持仓调整数量后下单
+ *
+ * released 为同一批操作中先撤单释放的现金及持仓
+ */
+#[allow(clippy::too_many_arguments)]
+pub(super) fn place_order(
+    context: &MarketContext,
+    quote: &Quote,
+    order_type: OrderType,
+    price: Price,
+    quantity: Quantity,
+    max_position: u64,
+    released: Released,
+    actions: &mut Vec<TradingAction>,
+) {
+    let user = context.user();
+    let holding = user.holdings.get(&quote.stock_code);
+    let rules = &quote.rules;
+    if price.is_zero() {
+        return;
+    }
+    match order_type {
+        OrderType::Buy => {
+            // 预留交易费用
+            let budget = (user.available_balance() + released.balance).mul_rate(0.99);
+            let room = max_position.saturating_sub(holding.map_or(0, |holding| holding.quantity));
+            let quantity = (quantity as u64)
+                .min(budget.quantity_at(price))
+                .min(room)
+                .min(Quantity::MAX as u64) as Quantity;
+            let quantity = rules.round_buy_quantity(quantity);
+            if quantity > 0 {
+                actions.push(TradingAction::Buy {
+                    stock_code: quote.stock_code.clone(),
+                    price,
+                    quantity,
+                });
+            }
+        }
+        OrderType::Sell => {
+            let available =
+                holding.map_or(0, |holding| holding.available_quantity) + released.quantity;
+            let quantity = rules.round_sell_quantity(quantity, available);
+            if quantity > 0 {
+                actions.push(TradingAction::Sell {
+                    stock_code: quote.stock_code.clone(),
+                    price,
+                    quantity,
+                });
+            }
+        }
+    }
+}
+
+/** 最近 window 根K线收盘价的均值 */
+pub(super) fn moving_average(bars: &[PriceHistoryInfo], window: usize) -> f64 {
+    let bars = &bars[bars.len() - window..];
+    bars.iter().map(|bar| bar.price.as_f64()).sum::<f64>() / window as f64
+}
+
+/** 收盘价的标准差 */
+pub(super) fn standard_deviation(bars: &[PriceHistoryInfo], mean: f64) -> f64 {
+    let variance = bars
+        .iter()
+        .map(|bar| (bar.price.as_f64() - mean).powi(2))
+        .sum::<f64>()
+        / bars.len() as f64;
+    variance.sqrt()
+}
+
+/** 成交量加权均价 没有成交时返回 None */
+pub(super) fn vwap(bars: &[PriceHistoryInfo]) -> Option<f64> {
+    let volume: u64 = bars.iter().map(|bar| bar.volume as u64).sum();
+    let amount = bars
+        .iter()
+        .fold(Money::ZERO, |amount, bar| amount + bar.amount);
+    (volume > 0).then(|| amount.per_unit(volume).as_f64())
+}
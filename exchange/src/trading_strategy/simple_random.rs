@@ -1,66 +1,78 @@
-use crate::exchange::Exchange;
-use crate::trading_strategy::{TradingAction, TradingStrategyDecide};
-use crate::user::User;
+use crate::random::SimRng;
+use crate::trading_strategy::{MarketContext, TradingAction, TradingStrategyDecide};
+use crate::types::{Price, Quantity};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 // 修改后的随机交易策略
+#[derive(Serialize, Deserialize)]
 pub struct RandomStrategy;
 
 impl TradingStrategyDecide for RandomStrategy {
-    fn decide(&self, user: &User, exchange: &Exchange) -> TradingAction {
-        let mut rng = rand::thread_rng();
-
+    fn decide(&mut self, context: &MarketContext, rng: &mut SimRng) -> Vec<TradingAction> {
+        let user = context.user();
         // 20% 概率进行交易，80% 概率保持不动
         if rng.gen_bool(0.2) {
-            let stock_codes = exchange.stock_manager.get_stock_codes();
+            let stock_codes = context.stock_codes();
             if stock_codes.is_empty() {
-                return TradingAction::Hold;
+                return Vec::new();
             }
 
             let stock_code = stock_codes[rng.gen_range(0..stock_codes.len())].clone();
-            let stock = exchange.stock_manager.get_stock(&stock_code).unwrap();
-            let limit = stock.price_limit.clone();
-            let current_price = stock.current_price;
+            let quote = context.quote(&stock_code).unwrap();
+            let limit = &quote.price_limit;
+            let current_price = quote.last_price;
+            let rules = &quote.rules;
 
-            // 在当前价格的基础上，以较小的幅度随机生成价格（±0.01%）
-            let price_range = ((current_price as f64 * 0.0001) as u32).max(1);
-            let min_price = current_price.saturating_sub(price_range).max(limit.lower);
-            let max_price = (current_price + price_range).min(limit.upper);
-            let price = rng.gen_range(min_price..=max_price);
+            // 在当前价格的基础上，以较小的幅度随机生成价格（±0.01%，至少一个最小价格变动单位）
+            let tick_size = rules.tick_size.raw().max(1) as i64;
+            let tick_range = ((current_price.raw() as f64 * 0.0001) as i64 / tick_size).max(1);
+            let ticks = rng.gen_range(-tick_range..=tick_range);
+            let price = (rules.align_price(current_price).raw() as i64 + ticks * tick_size)
+                .clamp(limit.lower.raw() as i64, limit.upper.raw() as i64);
+            let price = Price::from_raw(price as u32);
 
             // 随机决定买入或卖出
             if rng.gen_bool(0.5) {
                 // 买入
-                let max_quantity = (user.balance / price as u64) as u32;
-                if max_quantity >= 100 {
-                    let quantity = (rng.gen_range(1..=5) * 100).min(max_quantity);
-                    TradingAction::Buy {
-                        stock_code: stock.code.clone(),
+                let max_quantity = user
+                    .available_balance()
+                    .quantity_at(price)
+                    .min(Quantity::MAX as u64) as Quantity;
+                let quantity = rules.round_buy_quantity(
+                    (rng.gen_range(1..=5) * rules.min_quantity).min(max_quantity),
+                );
+                if quantity > 0 {
+                    vec![TradingAction::Buy {
+                        stock_code: stock_code.clone(),
                         price,
                         quantity,
-                    }
+                    }]
                 } else {
-                    TradingAction::Hold
+                    Vec::new()
                 }
             } else {
                 // 卖出
-                if let Some(holding) = user.holdings.get(&stock.code) {
-                    if holding.available_quantity >= 100 {
-                        let quantity = (rng.gen_range(1..=5) * 100).min(holding.available_quantity);
-                        TradingAction::Sell {
-                            stock_code: stock.code.clone(),
+                if let Some(holding) = user.holdings.get(&stock_code) {
+                    let quantity = rules.round_sell_quantity(
+                        rng.gen_range(1..=5) * rules.min_quantity,
+                        holding.available_quantity,
+                    );
+                    if quantity > 0 {
+                        vec![TradingAction::Sell {
+                            stock_code: stock_code.clone(),
                             price,
-                            quantity: quantity as u32,
-                        }
+                            quantity,
+                        }]
                     } else {
-                        TradingAction::Hold
+                        Vec::new()
                     }
                 } else {
-                    TradingAction::Hold
+                    Vec::new()
                 }
             }
         } else {
-            TradingAction::Hold
+            Vec::new()
         }
     }
 }
@@ -1,89 +1,94 @@
-use super::{TradingAction, TradingStrategyDecide};
-use crate::exchange::Exchange;
-use crate::user::User;
+use super::{MarketContext, TradingAction, TradingStrategyDecide};
+use crate::random::SimRng;
+use crate::types::Quantity;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 // 修改后的随机交易策略
 #[allow(dead_code)]
+#[derive(Serialize, Deserialize)]
 pub struct TradeRandomStrategy(pub u8);
 
 impl TradingStrategyDecide for TradeRandomStrategy {
-    fn decide(&self, user: &User, exchange: &Exchange) -> TradingAction {
-        let mut rng = rand::thread_rng();
-
+    fn decide(&mut self, context: &MarketContext, rng: &mut SimRng) -> Vec<TradingAction> {
+        let user = context.user();
         // 50% 概率进行交易，50% 概率保持不动
         if rng.gen_bool(0.5) {
-            let stock_codes = exchange.stock_manager.get_stock_codes();
+            let stock_codes = context.stock_codes();
             if stock_codes.is_empty() {
-                return TradingAction::Hold;
+                return Vec::new();
             }
 
             let stock_code = stock_codes[rng.gen_range(0..stock_codes.len())].clone();
-            let stock = exchange.stock_manager.get_stock(&stock_code).unwrap();
+            let stock = context.quote(&stock_code).unwrap();
+            let rules = &stock.rules;
 
             // 决定是买入还是卖出
             let is_buy = rng.gen_bool(0.5);
 
             // 选择价格
             let price = if is_buy {
-                if stock.sell_orders.is_empty() {
-                    stock.current_price
+                if stock.asks.is_empty() {
+                    stock.last_price
                 } else if rng.gen_bool(0.8) {
                     // 80% 概率选择卖一价格
-                    *stock.sell_orders.keys().next().unwrap()
+                    stock.asks[0].0
                 } else {
                     // 20% 概率随机选择卖盘中的价格
-                    let index = rng.gen_range(0..stock.sell_orders.len());
-                    *stock.sell_orders.keys().nth(index).unwrap()
+                    let index = rng.gen_range(0..stock.asks.len());
+                    stock.asks[index].0
                 }
             } else {
-                if stock.buy_orders.is_empty() {
-                    stock.current_price
+                if stock.bids.is_empty() {
+                    stock.last_price
                 } else if rng.gen_bool(0.8) {
                     // 80% 概率选择买一价格
-                    *stock.buy_orders.keys().next_back().unwrap()
+                    stock.bids[0].0
                 } else {
-                    // 20% 概率随机选择买盘中的价格
-                    let index = rng.gen_range(0..stock.buy_orders.len());
-                    *stock.buy_orders.keys().nth(index).unwrap()
+                    // 20% 概率随机选择买盘中的价格 下标按价格从低到高
+                    let index = rng.gen_range(0..stock.bids.len());
+                    stock.bids[stock.bids.len() - 1 - index].0
                 }
             };
 
             // 随机决定交易数量
-            let quantity = rng.gen_range(1..=10) * 100;
+            let quantity = rng.gen_range(1..=10) * rules.min_quantity;
 
             if is_buy {
                 // 买入
-                let max_quantity = (user.balance / price as u64) as u32;
-                if max_quantity >= 100 {
-                    let quantity = quantity.min(max_quantity);
-                    TradingAction::Buy {
-                        stock_code: stock.code.clone(),
+                let max_quantity = user
+                    .available_balance()
+                    .quantity_at(price)
+                    .min(Quantity::MAX as u64) as Quantity;
+                let quantity = rules.round_buy_quantity(quantity.min(max_quantity));
+                if quantity > 0 {
+                    vec![TradingAction::Buy {
+                        stock_code: stock.stock_code.clone(),
                         price,
                         quantity,
-                    }
+                    }]
                 } else {
-                    TradingAction::Hold
+                    Vec::new()
                 }
             } else {
                 // 卖出
-                if let Some(holding) = user.holdings.get(&stock.code) {
-                    if holding.available_quantity >= 100 {
-                        let quantity = quantity.min(holding.available_quantity as u32);
-                        TradingAction::Sell {
-                            stock_code: stock.code.clone(),
+                if let Some(holding) = user.holdings.get(&stock.stock_code) {
+                    let quantity = rules.round_sell_quantity(quantity, holding.available_quantity);
+                    if quantity > 0 {
+                        vec![TradingAction::Sell {
+                            stock_code: stock.stock_code.clone(),
                             price,
                             quantity,
-                        }
+                        }]
                     } else {
-                        TradingAction::Hold
+                        Vec::new()
                     }
                 } else {
-                    TradingAction::Hold
+                    Vec::new()
                 }
             }
         } else {
-            TradingAction::Hold
+            Vec::new()
         }
     }
 }
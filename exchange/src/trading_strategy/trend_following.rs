@@ -0,0 +1,102 @@
+use super::signal::{moving_average, BarTrader};
+use super::{MarketContext, TradingAction, TradingStrategyDecide};
+use crate::order::OrderType;
+use crate::random::SimRng;
+use crate::stock::PriceHistoryInfo;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+
+/** 趋势信号 */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendSignal {
+    /** 短期均线高于长期均线时买入 低于时卖出 */
+    Crossover,
+    /** 收盘价突破此前 lookback 根K线的最高价时买入 跌破最低价时卖出 */
+    Breakout,
+}
+
+/** 趋势跟踪参数 */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrendFollowingParams {
+    pub signal: TrendSignal,
+    /** 长期均线或突破通道的K线数 */
+    pub lookback: usize,
+    /** 短期均线的K线数 仅用于均线交叉 */
+    pub fast_lookback: usize,
+    /** 信号须超过的幅度 比例 过滤噪声 */
+    pub threshold: f64,
+    /** 每根K线最多买卖的数量 */
+    pub order_size: Quantity,
+    /** 最大持仓 */
+    pub max_position: u64,
+}
+
+/**
+ * 趋势跟踪策略 按分钟K线的均线交叉或突破顺势买卖
+ *
+ * 每根新完成的K线决策一次 以对手价委托
+ */
+#[derive(Serialize, Deserialize)]
+pub struct TrendFollowingStrategy {
+    params: TrendFollowingParams,
+    trader: BarTrader,
+}
+
+impl TrendFollowingStrategy {
+    pub fn new(params: TrendFollowingParams) -> Self {
+        TrendFollowingStrategy {
+            params,
+            trader: BarTrader::default(),
+        }
+    }
+
+    fn signal(params: &TrendFollowingParams, bars: &[PriceHistoryInfo]) -> Option<OrderType> {
+        let lookback = params.lookback.max(1);
+        let threshold = params.threshold;
+        match params.signal {
+            TrendSignal::Crossover => {
+                if bars.len() < lookback {
+                    return None;
+                }
+                let fast = moving_average(bars, params.fast_lookback.clamp(1, lookback));
+                let slow = moving_average(bars, lookback);
+                if fast > slow * (1.0 + threshold) {
+                    Some(OrderType::Buy)
+                } else if fast < slow * (1.0 - threshold) {
+                    Some(OrderType::Sell)
+                } else {
+                    None
+                }
+            }
+            TrendSignal::Breakout => {
+                // 最后一根K线与之前 lookback 根K线比较
+                let (last, previous) = bars.split_last()?;
+                if previous.len() < lookback {
+                    return None;
+                }
+                let channel = &previous[previous.len() - lookback..];
+                let high = channel.iter().map(|bar| bar.max_price).max()?.as_f64();
+                let low = channel.iter().map(|bar| bar.min_price).min()?.as_f64();
+                let close = last.price.as_f64();
+                if close > high * (1.0 + threshold) {
+                    Some(OrderType::Buy)
+                } else if close < low * (1.0 - threshold) {
+                    Some(OrderType::Sell)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl TradingStrategyDecide for TrendFollowingStrategy {
+    fn decide(&mut self, context: &MarketContext, _rng: &mut SimRng) -> Vec<TradingAction> {
+        let params = &self.params;
+        self.trader
+            .decide(context, params.order_size, params.max_position, |bars| {
+                Self::signal(params, bars)
+            })
+    }
+}
@@ -1,42 +1,219 @@
+use crate::config::{CostBasisMethod, SettlementCycle};
 use crate::exchange_error::{ExchangeError, ExchangeResult};
+use crate::fee::{FeeReport, Fees};
 use crate::types::*;
-use std::collections::HashMap;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub id: UserId,
     pub username: String,
-    pub balance: u64,
+    /** 现金总额 含冻结部分 */
+    pub balance: Money,
+    /** 买入委托冻结的现金 */
+    pub frozen_balance: Money,
     pub holdings: HashMap<StockCode, Holding>,
+    /** 各股票累计已实现盈亏 清仓后仍保留 */
+    pub realized_pnl: HashMap<StockCode, Money>,
+    /** 每日盈亏快照 */
+    pub pnl_history: Vec<PnlSnapshot>,
+    /** 佣金档位 为空时使用默认费率 */
+    pub fee_tier: Option<String>,
+    /** 累计交易费用 */
+    pub fee_report: FeeReport,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Holding {
+    /** 持仓总数 */
     pub quantity: u64,
+    /** 可卖数量 T+1 当日买入的不可卖 */
     pub available_quantity: u64,
+    /** 卖出委托冻结的数量 */
+    pub frozen_quantity: u64,
+    /** 持仓成本 */
+    pub cost: Money,
+    /** 买入批次 按买入先后排列 */
+    pub lots: VecDeque<Lot>,
+}
+
+/** 买入批次 */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lot {
+    pub quantity: u64,
+    pub cost: Money,
+}
+
+/** 每日收盘盈亏快照 */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PnlSnapshot {
+    pub trade_day: NaiveDate,
+    /** 累计已实现盈亏 */
+    pub realized_pnl: Money,
+    /** 收盘浮动盈亏 */
+    pub unrealized_pnl: Money,
+    /** 累计总盈亏 */
+    pub total_pnl: Money,
+    /** 当日盈亏 */
+    pub daily_pnl: Money,
+    pub total_equity: Money,
+    /** 收盘现金 含冻结部分 */
+    #[serde(default)]
+    pub cash: Money,
+    /** 收盘持仓市值 */
+    #[serde(default)]
+    pub market_value: Money,
+    /** 累计交易费用 */
+    #[serde(default)]
+    pub fees: Money,
+}
+
+/** 盈亏排名 */
+#[derive(Debug, Clone, Serialize)]
+pub struct PnlRanking {
+    pub rank: usize,
+    pub user_id: UserId,
+    pub username: String,
+    pub is_robot: bool,
+    pub realized_pnl: Money,
+    pub unrealized_pnl: Money,
+    pub total_pnl: Money,
+    pub total_equity: Money,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountInfo {
+    pub user_id: UserId,
+    pub username: String,
+    /** 现金总额 */
+    pub balance: Money,
+    /** 可用现金 */
+    pub available_balance: Money,
+    /** 冻结现金 */
+    pub frozen_balance: Money,
+    /** 持仓市值 */
+    pub market_value: Money,
+    /** 总资产 */
+    pub total_equity: Money,
+    /** 累计已实现盈亏 */
+    pub realized_pnl: Money,
+    /** 浮动盈亏 */
+    pub unrealized_pnl: Money,
+    pub positions: Vec<PositionInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionInfo {
+    pub stock_code: StockCode,
+    pub quantity: u64,
+    pub available_quantity: u64,
+    pub frozen_quantity: u64,
+    /** 持仓成本 */
+    pub cost: Money,
+    /** 持仓均价 */
+    pub average_cost: Price,
+    pub current_price: Price,
+    /** 按当前价计算的市值 */
+    pub market_value: Money,
+    /** 浮动盈亏 */
+    pub unrealized_pnl: Money,
+    /** 该股票累计已实现盈亏 */
+    pub realized_pnl: Money,
+}
+
+impl Holding {
+    /** 增加买入批次 */
+    fn add_lot(&mut self, quantity: u64, cost: Money) {
+        self.quantity += quantity;
+        self.cost += cost;
+        self.lots.push_back(Lot { quantity, cost });
+    }
+
+    /** 减少持仓 按成本计算方式结转成本 返回结转的成本 */
+    fn reduce(&mut self, quantity: u64, method: CostBasisMethod) -> Money {
+        let quantity = quantity.min(self.quantity);
+
+        // 批次总是按先进先出消耗
+        let mut fifo_cost = Money::ZERO;
+        let mut remaining = quantity;
+        while remaining > 0 {
+            let Some(lot) = self.lots.front_mut() else {
+                break;
+            };
+            if lot.quantity <= remaining {
+                remaining -= lot.quantity;
+                fifo_cost += lot.cost;
+                self.lots.pop_front();
+            } else {
+                let lot_cost = lot.cost.mul_div(remaining, lot.quantity);
+                lot.quantity -= remaining;
+                lot.cost -= lot_cost;
+                fifo_cost += lot_cost;
+                remaining = 0;
+            }
+        }
+
+        let cost = match method {
+            CostBasisMethod::AverageCost => proportional_cost(self, quantity),
+            CostBasisMethod::Fifo => fifo_cost,
+        }
+        .min(self.cost);
+        self.cost -= cost;
+        self.quantity -= quantity;
+        cost
+    }
+
+    /** 按价格计算的浮动盈亏 */
+    pub fn unrealized_pnl(&self, price: Price) -> Money {
+        price * self.quantity - self.cost
+    }
+
+    /** 持仓均价 */
+    pub fn average_cost(&self) -> Price {
+        self.cost.per_unit(self.quantity)
+    }
 }
 
 impl User {
-    pub fn new(id: UserId, username: String, initial_balance: u64) -> Self {
+    pub fn new(id: UserId, username: String, initial_balance: Money) -> Self {
         User {
             id,
             username,
             balance: initial_balance,
+            frozen_balance: Money::ZERO,
             holdings: HashMap::new(),
+            realized_pnl: HashMap::new(),
+            pnl_history: Vec::new(),
+            fee_tier: None,
+            fee_report: FeeReport::default(),
         }
     }
 
-    // 判断用户是否足够
-    pub fn has_enough_balance(&self, price: Price, quantity: Quantity) -> bool {
-        self.balance >= price as u64 * quantity as u64
+    /** 可用现金 */
+    pub fn available_balance(&self) -> Money {
+        self.balance - self.frozen_balance
+    }
+
+    // 判断用户可用现金是否足够支付委托金额及费用
+    pub fn has_enough_balance(&self, price: Price, quantity: Quantity, fee: Money) -> bool {
+        self.available_balance() >= price * quantity + fee
+    }
+
+    // 判断用户可卖持仓是否足够
+    pub fn has_enough_holding(&self, stock_code: &StockCode, quantity: Quantity) -> bool {
+        self.holdings
+            .get(stock_code)
+            .is_some_and(|holding| holding.available_quantity >= quantity as u64)
     }
 
-    pub fn deposit(&mut self, amount: u64) {
+    pub fn deposit(&mut self, amount: Money) {
         self.balance += amount;
     }
 
-    pub fn withdraw(&mut self, amount: u64) -> ExchangeResult<()> {
-        if self.balance >= amount {
+    pub fn withdraw(&mut self, amount: Money) -> ExchangeResult<()> {
+        if self.available_balance() >= amount {
             self.balance -= amount;
             Ok(())
         } else {
@@ -44,29 +221,166 @@ impl User {
         }
     }
 
-    pub fn add_holding(&mut self, stock_code: StockCode, quantity: u64) {
-        let holding = self.holdings.entry(stock_code).or_insert(Holding {
-            quantity: 0,
-            available_quantity: 0,
-        });
-        holding.quantity += quantity;
+    pub fn add_holding(&mut self, stock_code: StockCode, quantity: u64, cost: Money) {
+        let holding = self.holdings.entry(stock_code).or_default();
+        holding.add_lot(quantity, cost);
         holding.available_quantity += quantity;
     }
 
     pub fn remove_holding(&mut self, stock_code: StockCode, quantity: u64) {
         let holding = self.holdings.get_mut(&stock_code).unwrap();
+        let quantity = quantity.min(holding.available_quantity);
+        holding.reduce(quantity, CostBasisMethod::AverageCost);
         holding.available_quantity -= quantity;
-        if holding.available_quantity <= 0 {
+        if holding.quantity == 0 {
             self.holdings.remove(&stock_code);
         }
     }
+
+    /** 买入委托 冻结现金 */
+    pub fn freeze_balance(&mut self, amount: Money) -> ExchangeResult<()> {
+        if self.available_balance() < amount {
+            return Err(ExchangeError::InsufficientBalance);
+        }
+        self.frozen_balance += amount;
+        Ok(())
+    }
+
+    /** 买入委托撤单 解冻现金 */
+    pub fn unfreeze_balance(&mut self, amount: Money) {
+        self.frozen_balance = self.frozen_balance.saturating_sub(amount);
+    }
+
+    /** 卖出委托 冻结持仓 */
+    pub fn freeze_holding(
+        &mut self,
+        stock_code: &StockCode,
+        quantity: Quantity,
+    ) -> ExchangeResult<()> {
+        if !self.has_enough_holding(stock_code, quantity) {
+            return Err(ExchangeError::InsufficientStock);
+        }
+        let holding = self.holdings.get_mut(stock_code).unwrap();
+        holding.available_quantity -= quantity as u64;
+        holding.frozen_quantity += quantity as u64;
+        Ok(())
+    }
+
+    /** 卖出委托撤单 解冻持仓 */
+    pub fn unfreeze_holding(&mut self, stock_code: &StockCode, quantity: Quantity) {
+        if let Some(holding) = self.holdings.get_mut(stock_code) {
+            let quantity = (quantity as u64).min(holding.frozen_quantity);
+            holding.frozen_quantity -= quantity;
+            holding.available_quantity += quantity;
+        }
+    }
+
+    /**
+     * 买入成交交割
+     *
+     * 释放委托冻结的现金，按成交价扣款并扣除费用，T+1 交收时买入的股票当日不可卖
+     */
+    pub fn settle_buy(
+        &mut self,
+        stock_code: &StockCode,
+        released_amount: Money,
+        trade_price: Price,
+        quantity: Quantity,
+        fees: &Fees,
+        settlement: SettlementCycle,
+    ) {
+        let amount = trade_price * quantity;
+        self.unfreeze_balance(released_amount);
+        self.balance -= amount + fees.total();
+        self.fee_report.add(fees);
+        // 买入费用计入持仓成本
+        let holding = self.holdings.entry(stock_code.clone()).or_default();
+        holding.add_lot(quantity as u64, amount + fees.total());
+        // T+0 买入当日可卖 T+1 需等到下一交易日清算后
+        if settlement == SettlementCycle::T0 {
+            holding.available_quantity += quantity as u64;
+        }
+    }
+
+    /**
+     * 卖出成交交割
+     *
+     * 扣减冻结持仓并结转成本，卖出所得扣除费用后当日可用，返回本次已实现盈亏
+     */
+    pub fn settle_sell(
+        &mut self,
+        stock_code: &StockCode,
+        trade_price: Price,
+        quantity: Quantity,
+        method: CostBasisMethod,
+        fees: &Fees,
+    ) -> Money {
+        let Some(holding) = self.holdings.get_mut(stock_code) else {
+            return Money::ZERO;
+        };
+        let quantity = (quantity as u64).min(holding.frozen_quantity);
+        let cost = holding.reduce(quantity, method);
+        holding.frozen_quantity -= quantity;
+        if holding.quantity == 0 {
+            self.holdings.remove(stock_code);
+        }
+
+        let amount = trade_price * quantity;
+        self.balance = (self.balance + amount).saturating_sub(fees.total());
+        self.fee_report.add(fees);
+        let pnl = amount - fees.total() - cost;
+        *self
+            .realized_pnl
+            .entry(stock_code.clone())
+            .or_insert(Money::ZERO) += pnl;
+        pnl
+    }
+
+    /** 累计已实现盈亏 */
+    pub fn total_realized_pnl(&self) -> Money {
+        self.realized_pnl.values().sum()
+    }
+
+    /** 记录每日盈亏快照 */
+    pub fn record_pnl_snapshot(&mut self, trade_day: NaiveDate, account: &AccountInfo) {
+        let realized_pnl = self.total_realized_pnl();
+        let unrealized_pnl = account.unrealized_pnl;
+        let total_pnl = realized_pnl + unrealized_pnl;
+        let previous_total_pnl = self
+            .pnl_history
+            .last()
+            .map_or(Money::ZERO, |last| last.total_pnl);
+        self.pnl_history.push(PnlSnapshot {
+            trade_day,
+            realized_pnl,
+            unrealized_pnl,
+            total_pnl,
+            daily_pnl: total_pnl - previous_total_pnl,
+            total_equity: account.total_equity,
+            cash: account.balance,
+            market_value: account.market_value,
+            fees: self.fee_report.total,
+        });
+    }
+}
+
+/** 按数量比例计算持仓成本 */
+fn proportional_cost(holding: &Holding, quantity: u64) -> Money {
+    holding.cost.mul_div(quantity, holding.quantity)
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct UserManager {
     users: HashMap<UserId, User>,
     next_user_id: UserId,
 }
 
+impl Default for UserManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UserManager {
     pub fn new() -> Self {
         UserManager {
@@ -75,7 +389,7 @@ impl UserManager {
         }
     }
 
-    pub fn create_user(&mut self, username: String, initial_balance: u64) -> UserId {
+    pub fn create_user(&mut self, username: String, initial_balance: Money) -> UserId {
         let user_id = self.next_user_id;
         self.next_user_id += 1;
         let user = User::new(user_id, username, initial_balance);
@@ -83,6 +397,19 @@ impl UserManager {
         user_id
     }
 
+    /** 用户名是否已被使用 含机器人 */
+    pub fn is_username_taken(&self, username: &str) -> bool {
+        self.find_user_id(username).is_some()
+    }
+
+    /** 按用户名查找用户ID 含机器人 */
+    pub fn find_user_id(&self, username: &str) -> Option<UserId> {
+        self.users
+            .values()
+            .find(|user| user.username == username)
+            .map(|user| user.id)
+    }
+
     pub fn get_user(&self, user_id: UserId) -> Option<&User> {
         self.users.get(&user_id)
     }
@@ -91,7 +418,13 @@ impl UserManager {
         self.users.get_mut(&user_id)
     }
 
-    pub fn deposit(&mut self, user_id: UserId, amount: u64) -> ExchangeResult<()> {
+    pub fn get_user_ids(&self) -> Vec<UserId> {
+        let mut user_ids: Vec<UserId> = self.users.keys().cloned().collect();
+        user_ids.sort();
+        user_ids
+    }
+
+    pub fn deposit(&mut self, user_id: UserId, amount: Money) -> ExchangeResult<()> {
         if let Some(user) = self.users.get_mut(&user_id) {
             user.deposit(amount);
             Ok(())
@@ -100,7 +433,7 @@ impl UserManager {
         }
     }
 
-    pub fn withdraw(&mut self, user_id: UserId, amount: u64) -> ExchangeResult<()> {
+    pub fn withdraw(&mut self, user_id: UserId, amount: Money) -> ExchangeResult<()> {
         if let Some(user) = self.users.get_mut(&user_id) {
             user.withdraw(amount)
         } else {
@@ -108,10 +441,12 @@ impl UserManager {
         }
     }
 
-    // 清算
+    // 清算 当日委托全部失效，冻结的现金和持仓解冻，持仓全部变为可卖
     pub fn reset_positions(&mut self) {
         for user in self.users.values_mut() {
+            user.frozen_balance = Money::ZERO;
             for holding in user.holdings.values_mut() {
+                holding.frozen_quantity = 0;
                 holding.available_quantity = holding.quantity;
             }
         }
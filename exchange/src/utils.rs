@@ -3,6 +3,12 @@ use crate::types::{OrderId, Price, Quantity, UserId};
 use std::collections::BTreeMap;
 use std::collections::VecDeque;
 
+/** 委托队列中的单笔委托 (委托单ID, 用户ID, 剩余数量) */
+pub type QueuedOrder = (OrderId, UserId, Quantity);
+
+/** 单边委托队列 按价格档位排列 */
+pub type OrderBookSide = VecDeque<(Price, VecDeque<QueuedOrder>)>;
+
 /** 价格选择策略 */
 pub enum PriceSelectionStrategy {
     /** 中间价 偶数个价格取中间两个中靠右的那个 */
@@ -17,7 +23,7 @@ pub fn calculate_max_volume_price(
     strategy: PriceSelectionStrategy,
 ) -> (Price, Quantity) {
     if price_volume.is_empty() {
-        return (0, 0);
+        return (Price::ZERO, 0);
     }
 
     let mut accumulated_buy = 0;
@@ -45,15 +51,14 @@ pub fn calculate_max_volume_price(
     let selected_price = match strategy {
         PriceSelectionStrategy::Middle => {
             let mid_index = best_prices.len() / 2;
-            println!("mid_index: {}", mid_index);
-            println!("best_prices: {:?}", best_prices);
+            log::debug!("mid_index: {} best_prices: {:?}", mid_index, best_prices);
 
             best_prices[mid_index]
         }
         PriceSelectionStrategy::Nearest(reference_price) => *best_prices
             .iter()
-            .min_by_key(|&&p| (p as i64 - reference_price as i64).abs())
-            .unwrap_or(&0),
+            .min_by_key(|&&p| p.diff(reference_price).raw().abs())
+            .unwrap_or(&Price::ZERO),
     };
 
     (selected_price, best_volume)
@@ -67,11 +72,12 @@ pub fn calculate_max_volume_price(
  * 2. 买入委托价格 >= 卖出委托价格
  *
  */
+#[allow(clippy::needless_range_loop)]
 pub fn match_orders(
     // 买入委托单：价格从高到低排序
-    buy_orders: &mut VecDeque<(Price, VecDeque<(OrderId, UserId, Quantity)>)>,
+    buy_orders: &mut OrderBookSide,
     // 卖出委托单：价格从低到高排序
-    sell_orders: &mut VecDeque<(Price, VecDeque<(OrderId, UserId, Quantity)>)>,
+    sell_orders: &mut OrderBookSide,
 ) -> Vec<TradeLog> {
     let mut trade_logs = Vec::new();
 
@@ -117,12 +123,13 @@ pub fn match_orders(
     trade_logs
 }
 
+#[allow(clippy::needless_range_loop)]
 pub fn match_order(
     buy_order_id: OrderId,
     buyer_id: UserId,
     buy_price: Price,
     buy_quantity: Quantity,
-    sell_orders: &mut VecDeque<(Price, VecDeque<(OrderId, UserId, Quantity)>)>,
+    sell_orders: &mut OrderBookSide,
 ) -> (Quantity, Vec<TradeLog>) {
     let mut buy_quantity = buy_quantity;
     let mut trade_logs = Vec::new();
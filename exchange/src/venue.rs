@@ -0,0 +1,244 @@
+use crate::config::{SettlementCycle, TradingPeriod, TradingPeriodType};
+use crate::fee::FeeSchedule;
+use crate::instrument::{InstrumentRules, InstrumentRulesTable};
+use crate::types::string_to_time_of_day;
+use serde::{Deserialize, Serialize};
+
+/** 交易场所 决定默认的交易时段及交易规则 */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Venue {
+    /** 沪深交易所 */
+    #[default]
+    AShare,
+    /** 香港交易所 */
+    Hkex,
+    /** 纽约证券交易所 */
+    Nyse,
+    /** 加密货币 全天候交易 */
+    Crypto,
+}
+
+impl Venue {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Venue::AShare => "模拟交易所",
+            Venue::Hkex => "模拟港股交易所",
+            Venue::Nyse => "模拟美股交易所",
+            Venue::Crypto => "模拟加密货币交易所",
+        }
+    }
+
+    /** 交易时段模板 时间均为当地时间 */
+    pub fn trading_periods(&self) -> Vec<TradingPeriod> {
+        match self {
+            Venue::AShare => a_share_periods(),
+            Venue::Hkex => hkex_periods(),
+            Venue::Nyse => nyse_periods(),
+            Venue::Crypto => crypto_periods(),
+        }
+    }
+
+    /** 涨跌幅限制 仅A股设置 */
+    pub fn price_limit_percentage(&self) -> Option<f64> {
+        match self {
+            Venue::AShare => Some(0.10),
+            Venue::Hkex | Venue::Nyse | Venue::Crypto => None,
+        }
+    }
+
+    pub fn settlement_cycle(&self) -> SettlementCycle {
+        match self {
+            Venue::AShare => SettlementCycle::T1,
+            Venue::Hkex | Venue::Nyse | Venue::Crypto => SettlementCycle::T0,
+        }
+    }
+
+    pub fn trades_on_weekends(&self) -> bool {
+        *self == Venue::Crypto
+    }
+
+    /** 交易费用 A股以外的场所默认不收费 可在配置文件中设置 */
+    pub fn fee_schedule(&self) -> FeeSchedule {
+        match self {
+            Venue::AShare => FeeSchedule::a_share(),
+            Venue::Hkex | Venue::Nyse | Venue::Crypto => FeeSchedule::zero(),
+        }
+    }
+
+    pub fn instrument_rules(&self) -> InstrumentRulesTable {
+        match self {
+            Venue::AShare => InstrumentRulesTable::a_share(),
+            Venue::Hkex => InstrumentRulesTable::uniform(InstrumentRules::hkex()),
+            Venue::Nyse => InstrumentRulesTable::uniform(InstrumentRules::us_equity()),
+            Venue::Crypto => InstrumentRulesTable::uniform(InstrumentRules::crypto()),
+        }
+    }
+}
+
+/** 沪深交易所 */
+fn a_share_periods() -> Vec<TradingPeriod> {
+    vec![
+        TradingPeriod::new(
+            "集合竞价 可以撤单",
+            "9:15:00",
+            "9:19:59",
+            TradingPeriodType::CallAuctionWithCancel,
+        ),
+        TradingPeriod::new(
+            "集合竞价 不可以撤单",
+            "9:20:00",
+            "9:24:59",
+            TradingPeriodType::CallAuctionNoCancel,
+        ),
+        TradingPeriod::new(
+            "开盘集合竞价",
+            "9:25:00",
+            "9:29:59",
+            TradingPeriodType::OpeningAuction,
+        ),
+        TradingPeriod::new(
+            "连续竞价交易",
+            "9:30:00",
+            "11:29:59",
+            TradingPeriodType::ContinuousTrading,
+        ),
+        TradingPeriod::new(
+            "午间休市",
+            "11:30:00",
+            "12:59:59",
+            TradingPeriodType::MiddayBreak,
+        ),
+        TradingPeriod::new(
+            "下午连续竞价交易",
+            "13:00:00",
+            "14:56:59",
+            TradingPeriodType::ContinuousTrading,
+        ),
+        TradingPeriod::new(
+            "收盘集合竞价",
+            "14:57:00",
+            "15:00:00",
+            TradingPeriodType::ClosingAuction,
+        ),
+    ]
+}
+
+/** 香港交易所 开市前时段 午间休市 收市竞价交易时段(随机收市) */
+fn hkex_periods() -> Vec<TradingPeriod> {
+    vec![
+        TradingPeriod::new(
+            "开市前时段 输入买卖盘",
+            "9:00:00",
+            "9:14:59",
+            TradingPeriodType::CallAuctionWithCancel,
+        ),
+        TradingPeriod::new(
+            "开市前时段 不可取消",
+            "9:15:00",
+            "9:19:59",
+            TradingPeriodType::CallAuctionNoCancel,
+        ),
+        TradingPeriod {
+            allow_order: false,
+            ..TradingPeriod::new(
+                "开市前时段 对盘",
+                "9:20:00",
+                "9:21:59",
+                TradingPeriodType::OpeningAuction,
+            )
+        },
+        TradingPeriod::new(
+            "开市前时段 暂停",
+            "9:22:00",
+            "9:29:59",
+            TradingPeriodType::Blocking,
+        ),
+        TradingPeriod::new(
+            "早市持续交易",
+            "9:30:00",
+            "11:59:59",
+            TradingPeriodType::ContinuousTrading,
+        ),
+        TradingPeriod::new(
+            "午间休市",
+            "12:00:00",
+            "12:59:59",
+            TradingPeriodType::MiddayBreak,
+        ),
+        TradingPeriod::new(
+            "午市持续交易",
+            "13:00:00",
+            "15:59:59",
+            TradingPeriodType::ContinuousTrading,
+        ),
+        TradingPeriod::new(
+            "收市竞价 参考价定价",
+            "16:00:00",
+            "16:00:59",
+            TradingPeriodType::Blocking,
+        ),
+        TradingPeriod {
+            allow_cancel: true,
+            allow_matching: false,
+            ..TradingPeriod::new(
+                "收市竞价 输入买卖盘",
+                "16:01:00",
+                "16:05:59",
+                TradingPeriodType::ClosingAuction,
+            )
+        },
+        TradingPeriod {
+            allow_matching: false,
+            ..TradingPeriod::new(
+                "收市竞价 不可取消",
+                "16:06:00",
+                "16:07:59",
+                TradingPeriodType::ClosingAuction,
+            )
+        },
+        // 16:08 至 16:10 之间随机收市
+        TradingPeriod {
+            random_close_window: string_to_time_of_day("0:02:00").unwrap(),
+            ..TradingPeriod::new(
+                "收市竞价 随机收市",
+                "16:08:00",
+                "16:10:00",
+                TradingPeriodType::ClosingAuction,
+            )
+        },
+    ]
+}
+
+/** 纽约证券交易所 盘前 常规交易 盘后 */
+fn nyse_periods() -> Vec<TradingPeriod> {
+    vec![
+        TradingPeriod::new(
+            "盘前交易",
+            "4:00:00",
+            "9:29:59",
+            TradingPeriodType::PreMarket,
+        ),
+        TradingPeriod::new(
+            "常规交易",
+            "9:30:00",
+            "15:59:59",
+            TradingPeriodType::ContinuousTrading,
+        ),
+        TradingPeriod::new(
+            "盘后交易",
+            "16:00:00",
+            "19:59:59",
+            TradingPeriodType::AfterHours,
+        ),
+    ]
+}
+
+/** 加密货币 全天连续交易 */
+fn crypto_periods() -> Vec<TradingPeriod> {
+    vec![TradingPeriod::new(
+        "全天连续交易",
+        "0:00:00",
+        "23:59:59",
+        TradingPeriodType::ContinuousTrading,
+    )]
+}
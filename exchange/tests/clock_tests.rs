@@ -0,0 +1,220 @@
+mod common;
+
+use common::money;
+use exchange::clock::{ClockError, SimulationClock};
+use exchange::config::{ExchangeConfig, TradingPeriod, TradingPeriodType};
+use exchange::event::ExchangeEvent;
+use exchange::exchange::Exchange;
+use exchange::fee::FeeSchedule;
+use exchange::order::OrderType;
+use exchange::types::{string_to_time_of_day, Money, Price, Timestamp};
+use std::time::Duration;
+
+fn timestamp(value: &str) -> Timestamp {
+    value.parse().unwrap()
+}
+
+fn period_changes(exchange: &mut Exchange) -> Vec<Option<TradingPeriodType>> {
+    exchange
+        .drain_events()
+        .into_iter()
+        .filter_map(|event| match event {
+            ExchangeEvent::PeriodChanged { period_type, .. } => Some(period_type),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_clock_speed_and_pause() {
+    let mut clock = SimulationClock::new();
+    assert_eq!(clock.elapse(Duration::from_millis(100)), 1);
+
+    clock.set_speed(1).unwrap();
+    assert_eq!(clock.elapse(Duration::from_millis(500)), 0);
+    assert_eq!(clock.elapse(Duration::from_millis(500)), 1);
+
+    clock.set_speed(1000).unwrap();
+    assert_eq!(clock.elapse(Duration::from_millis(100)), 100);
+    assert_eq!(clock.set_speed(0), Err(ClockError::InvalidSpeed(0)));
+    assert_eq!(clock.set_speed(1001), Err(ClockError::InvalidSpeed(1001)));
+    assert_eq!(clock.speed(), 1000);
+
+    // 手动推进最多一天的步数
+    assert_eq!(clock.max_steps(), 86_400);
+    assert!(clock.check_steps(86_400).is_ok());
+    assert_eq!(
+        clock.check_steps(86_401),
+        Err(ClockError::InvalidSteps {
+            steps: 86_401,
+            max: 86_400
+        })
+    );
+    assert!(clock.check_steps(0).is_err());
+
+    clock.pause();
+    assert_eq!(clock.elapse(Duration::from_secs(10)), 0);
+    clock.resume();
+    assert_eq!(clock.elapse(Duration::from_millis(1)), 1);
+}
+
+#[test]
+fn test_clock_steps_through_sessions_and_days() {
+    let mut exchange = Exchange::new(ExchangeConfig::new());
+    let clock = SimulationClock::new();
+
+    // 2023-01-01 是周日 先切换到周一 再直接进入集合竞价
+    assert_eq!(clock.step(&mut exchange), timestamp("2023-01-02T00:00:00"));
+    assert_eq!(clock.step(&mut exchange), timestamp("2023-01-02T09:15:00"));
+    assert_eq!(
+        period_changes(&mut exchange),
+        vec![Some(TradingPeriodType::CallAuctionWithCancel)]
+    );
+    assert_eq!(clock.step(&mut exchange), timestamp("2023-01-02T09:15:01"));
+
+    // 收盘后切换到下一交易日
+    clock
+        .jump_to(&mut exchange, timestamp("2023-01-02T15:00:00"))
+        .unwrap();
+    assert_eq!(clock.step(&mut exchange), timestamp("2023-01-02T15:00:01"));
+    assert!(exchange.config.get_current_period().is_none());
+    assert_eq!(clock.step(&mut exchange), timestamp("2023-01-03T00:00:00"));
+    assert_eq!(clock.step(&mut exchange), timestamp("2023-01-03T09:15:00"));
+}
+
+#[test]
+fn test_clock_jump_triggers_every_transition() {
+    let mut config = ExchangeConfig::new();
+    config.fee_schedule = FeeSchedule::zero();
+    let mut exchange = Exchange::new(config);
+    let _ = exchange.add_stock("000002", "平安银行", Price::from_yuan(10));
+    let buyer_id = exchange.add_user("buyer", money("1000000")).unwrap();
+    let seller_id = exchange.add_user("seller", Money::ZERO).unwrap();
+    let seller = exchange.user_manager.get_user_mut(seller_id).unwrap();
+    seller.add_holding("000002".to_string(), 1000, money("10000"));
+
+    let clock = SimulationClock::new();
+    clock
+        .jump_to(&mut exchange, timestamp("2023-01-02T09:30:00"))
+        .unwrap();
+    exchange
+        .submit_order(
+            seller_id,
+            "000002".to_string(),
+            OrderType::Sell,
+            Price::from_raw(10_500),
+            100,
+        )
+        .unwrap();
+    exchange
+        .submit_order(
+            buyer_id,
+            "000002".to_string(),
+            OrderType::Buy,
+            Price::from_raw(10_500),
+            100,
+        )
+        .unwrap();
+    exchange
+        .submit_order(
+            buyer_id,
+            "000002".to_string(),
+            OrderType::Buy,
+            Price::from_raw(10_100),
+            100,
+        )
+        .unwrap();
+    exchange.drain_events();
+
+    clock
+        .jump_to(&mut exchange, timestamp("2023-01-03T09:25:30"))
+        .unwrap();
+    assert_eq!(
+        period_changes(&mut exchange),
+        vec![
+            Some(TradingPeriodType::MiddayBreak),
+            Some(TradingPeriodType::ContinuousTrading),
+            Some(TradingPeriodType::ClosingAuction),
+            None,
+            Some(TradingPeriodType::CallAuctionWithCancel),
+            Some(TradingPeriodType::CallAuctionNoCancel),
+            Some(TradingPeriodType::OpeningAuction),
+        ]
+    );
+
+    // 新交易日以收盘价为昨收价 隔夜委托已清除
+    let stock = exchange
+        .stock_manager
+        .get_stock(&"000002".to_string())
+        .unwrap();
+    assert_eq!(stock.start_price, Price::from_raw(10_500));
+    assert_eq!(stock.price_limit.upper, Price::from_raw(11_550));
+    assert!(stock.buy_orders.is_empty());
+    assert!(stock.sell_orders.is_empty());
+
+    assert_eq!(
+        clock.jump_to(&mut exchange, timestamp("2023-01-03T09:00:00")),
+        Err(ClockError::TargetInPast {
+            target: timestamp("2023-01-03T09:00:00"),
+            current: timestamp("2023-01-03T09:25:30"),
+        })
+    );
+    assert!(matches!(
+        clock.jump_to(&mut exchange, timestamp("2023-01-07T10:00:00")),
+        Err(ClockError::NotTradingDay(_))
+    ));
+}
+
+#[test]
+fn test_sub_second_timestamps_stay_in_period() {
+    let mut exchange = Exchange::new(ExchangeConfig::new());
+    let clock = SimulationClock::new();
+    clock
+        .jump_to(&mut exchange, timestamp("2023-01-02T11:29:59"))
+        .unwrap();
+    exchange.drain_events();
+
+    // 时段结束时间包含最后一秒内的每一毫秒
+    exchange.next_timestamp("11:29:59.500");
+    assert_eq!(
+        exchange.config.get_current_period().unwrap().period_type,
+        TradingPeriodType::ContinuousTrading
+    );
+    assert!(exchange.config.is_allow_order());
+    exchange.next_timestamp("11:29:59.999");
+    assert!(exchange.config.is_allow_order());
+    assert!(!exchange.drain_events().iter().any(|event| matches!(
+        event,
+        ExchangeEvent::PeriodChanged { .. } | ExchangeEvent::Halt { .. }
+    )));
+
+    exchange.next_timestamp("11:30:00");
+    assert_eq!(
+        period_changes(&mut exchange),
+        vec![Some(TradingPeriodType::MiddayBreak)]
+    );
+
+    // 午间休市结束后下午交易从 13:00:00 开始
+    exchange.next_timestamp("12:59:59.999");
+    assert!(period_changes(&mut exchange).is_empty());
+    exchange.next_timestamp("13:00:00");
+    assert_eq!(
+        period_changes(&mut exchange),
+        vec![Some(TradingPeriodType::ContinuousTrading)]
+    );
+    exchange.next_timestamp("14:56:59.999");
+    assert!(exchange.config.is_allow_order());
+    assert!(period_changes(&mut exchange).is_empty());
+
+    // 带毫秒的结束时间不延长到下一秒
+    let period = TradingPeriod::new(
+        "连续竞价交易",
+        "09:30:00",
+        "11:29:59.500",
+        TradingPeriodType::ContinuousTrading,
+    );
+    let time = |time: &str| string_to_time_of_day(time).unwrap();
+    assert!(period.contains(time("11:29:59.500")));
+    assert!(!period.contains(time("11:29:59.501")));
+    assert_eq!(period.end_exclusive(), time("11:29:59.501"));
+}
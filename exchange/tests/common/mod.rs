@@ -0,0 +1,117 @@
+//! 集成测试共用的交易所配置及辅助函数
+#![allow(dead_code)]
+
+use exchange::config_file::ConfigFile;
+use exchange::exchange::Exchange;
+use exchange::types::{Money, Price};
+
+pub fn price(value: &str) -> Price {
+    value.parse().unwrap()
+}
+
+pub fn money(value: &str) -> Money {
+    value.parse().unwrap()
+}
+
+/** 成交记录、日K线及账户 用于比较两个交易所的状态 */
+pub fn state_of(exchange: &Exchange) -> (String, String, String) {
+    (
+        serde_json::to_string(&exchange.log_manager.get_all_logs()).unwrap(),
+        serde_json::to_string(&exchange.daily_bars()).unwrap(),
+        serde_json::to_string(&exchange.get_pnl_ranking()).unwrap(),
+    )
+}
+
+/** 两只股票 代码及昨收价 */
+pub const STOCKS: &[(&str, &str)] = &[("000001", "10.00"), ("000002", "20.00")];
+
+/** 随机交易的机器人 名称及策略 */
+pub const RANDOM_BOTS: &[(&str, &str)] = &[
+    ("robot1", "{ type: trade_random, param: 5 }"),
+    ("robot2", "{ type: simple_random }"),
+];
+
+/**
+ * 测试用的配置文件 只有 09:30 开始的连续竞价时段
+ *
+ * 每个机器人有 100 万资金 每只股票各持有 holding 股
+ */
+pub struct TestConfig<'a> {
+    /** 随机数主种子 未指定时随机生成 */
+    pub seed: Option<u64>,
+    /** 连续竞价结束时间 */
+    pub end_tick: &'a str,
+    /** 股票代码及昨收价 名称依次为 股票A 股票B ... */
+    pub stocks: &'a [(&'a str, &'a str)],
+    /** 机器人名称及策略 */
+    pub bots: &'a [(&'a str, &'a str)],
+    pub holding: u64,
+    /** 追加在末尾的顶层配置项 如 fundamental */
+    pub extra: &'a str,
+}
+
+impl Default for TestConfig<'_> {
+    fn default() -> Self {
+        TestConfig {
+            seed: None,
+            end_tick: "09:59:59",
+            stocks: STOCKS,
+            bots: RANDOM_BOTS,
+            holding: 10000,
+            extra: "",
+        }
+    }
+}
+
+impl TestConfig<'_> {
+    pub fn yaml(&self) -> String {
+        let mut yaml = String::from("exchange:\n  start_date: 2024-03-01\n");
+        if let Some(seed) = self.seed {
+            yaml += &format!("  seed: {}\n", seed);
+        }
+        yaml += &format!(
+            r#"  trading_periods:
+    - name: 连续竞价交易
+      start_tick: "09:30:00"
+      end_tick: "{}"
+      period_type: ContinuousTrading
+      allow_order: true
+      allow_cancel: true
+      allow_matching: true
+      allow_record_price_history: true
+stocks:
+"#,
+            self.end_tick
+        );
+        for (index, (code, price)) in self.stocks.iter().enumerate() {
+            let name = char::from(b'A' + index as u8);
+            yaml += &format!(
+                "  - {{ code: \"{}\", name: 股票{}, price: \"{}\" }}\n",
+                code, name, price
+            );
+        }
+        let holdings = self
+            .stocks
+            .iter()
+            .map(|(code, _)| format!("\"{}\": {}", code, self.holding))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !self.bots.is_empty() {
+            yaml += "bots:\n";
+        }
+        for (name, strategy) in self.bots {
+            yaml += &format!(
+                "  - name: {}\n    balance: \"1000000\"\n    strategy: {}\n    holdings: {{ {} }}\n",
+                name, strategy, holdings
+            );
+        }
+        yaml + self.extra
+    }
+
+    pub fn build(&self) -> Exchange {
+        ConfigFile::from_yaml(&self.yaml())
+            .unwrap()
+            .build_exchange()
+            .unwrap()
+    }
+}
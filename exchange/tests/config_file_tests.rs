@@ -0,0 +1,162 @@
+use exchange::config::{CostBasisMethod, TradingPeriodType};
+use exchange::config_file::{ConfigError, ConfigFile};
+use exchange::instrument::Board;
+use exchange::types::{Money, Price};
+
+const CONFIG: &str = r#"
+exchange:
+  name: 测试交易所
+  start_date: 2024-03-01
+  cost_basis_method: Fifo
+  price_limit:
+    percentage: 0.10
+    boards: { ChiNext: 0.20 }
+  trading_periods:
+    - name: 连续竞价交易
+      start_tick: "09:30:00"
+      end_tick: "15:00:00"
+      period_type: ContinuousTrading
+      allow_order: true
+      allow_cancel: true
+      allow_matching: true
+      allow_record_price_history: true
+stocks:
+  - { code: "000001", name: 股票A, price: "10.00" }
+  - { code: "300001", name: 股票B, price: "20" }
+users:
+  - { name: user1, balance: "1000000" }
+bots:
+  - name: robot1
+    balance: "500000.50"
+    strategy: { type: trade_random, param: 5 }
+    holdings: { "000001": 1000, "300001": 200 }
+"#;
+
+#[test]
+fn test_load_config_file() {
+    let config = ConfigFile::from_yaml(CONFIG).unwrap();
+    let mut exchange = config.build_exchange().unwrap();
+
+    assert_eq!(exchange.config.name, "测试交易所");
+    assert_eq!(exchange.config.cost_basis_method, CostBasisMethod::Fifo);
+    assert_eq!(exchange.current_trade_day().to_string(), "2024-03-01");
+    assert_eq!(exchange.config.trading_periods.len(), 1);
+    assert_eq!(
+        exchange.config.trading_periods[0].period_type,
+        TradingPeriodType::ContinuousTrading
+    );
+    assert_eq!(
+        exchange.config.price_limit_percentage_for(Board::Main),
+        Some(0.10)
+    );
+    assert_eq!(
+        exchange.config.price_limit_percentage_for(Board::ChiNext),
+        Some(0.20)
+    );
+
+    let main_board = exchange.get_stock_info("000001").unwrap();
+    assert_eq!(main_board.price_limit.upper, Price::from_yuan(11));
+    let chinext = exchange.get_stock_info("300001").unwrap();
+    assert_eq!(chinext.price_limit.upper, Price::from_yuan(24));
+
+    let robot_id = exchange.user_manager.get_user_ids()[1];
+    let robot = exchange.get_account(robot_id).unwrap();
+    assert_eq!(robot.balance, "500000.50".parse::<Money>().unwrap());
+    assert_eq!(robot.positions.len(), 2);
+    assert!(exchange.bot_manager.is_bot(robot_id));
+
+    exchange.next_timestamp("09:30:00");
+    assert!(exchange.config.is_allow_order());
+}
+
+#[test]
+fn test_invalid_config_reports_all_errors() {
+    let yaml = r#"
+exchange:
+  trading_periods:
+    - name: 上午
+      start_tick: "11:30:00"
+      end_tick: "09:30:00"
+      period_type: ContinuousTrading
+      allow_order: true
+      allow_cancel: true
+      allow_matching: true
+      allow_record_price_history: true
+stocks:
+  - { code: "000001", name: 股票A, price: "10.00" }
+  - { code: "000001", name: 股票B, price: "0" }
+users:
+  - { name: user1, balance: "100", fee_tier: gold, password_hash: secret }
+bots:
+  - name: user1
+    balance: "100"
+    strategy: { type: simple_random }
+    holdings: { "600000": 100 }
+"#;
+    let Err(ConfigError::Invalid(errors)) = ConfigFile::from_yaml(yaml) else {
+        panic!("config should be rejected");
+    };
+    assert_eq!(
+        errors,
+        vec![
+            "exchange.trading_periods[0] (上午): start 11:30:00 is after end 09:30:00",
+            "stocks[1] (000001): duplicate stock code",
+            "stocks[1] (000001): price must be positive",
+            "users[0] (user1): unknown fee tier gold",
+            "users[0] (user1): password_hash must be a PHC string such as $argon2id$...",
+            "bots[0] (user1): duplicate user name",
+            "bots[0] (user1): holding of unknown stock 600000",
+        ]
+    );
+
+    let unknown_field = ConfigFile::from_yaml(
+        "stocks:\n  - { code: \"000001\", name: A, price: 1, board: Main }\n",
+    );
+    assert!(matches!(unknown_field, Err(ConfigError::Parse(_))));
+}
+
+#[test]
+fn test_invalid_rules_and_fees_rejected() {
+    let yaml = r#"
+exchange:
+  instrument_rules:
+    boards:
+      Main:
+        tick_size: "0.01"
+        min_quantity: 2000
+        max_quantity: 1000
+        quantity_step: 0
+        odd_lot: SellAllAtOnce
+      Star:
+        tick_size: "0"
+        min_quantity: 200
+        max_quantity: 0
+        quantity_step: 1
+        odd_lot: SellAllAtOnce
+  fee_schedule:
+    commission_rate: -0.001
+    min_commission: "5"
+    stamp_duty_rate: 1.0
+    transfer_fee_rate: 0.00001
+    tiers:
+      vip: { commission_rate: 1.5, min_commission: "0" }
+stocks:
+  - { code: "000001", name: 股票A, price: "10.00" }
+"#;
+    let Err(ConfigError::Invalid(errors)) = ConfigFile::from_yaml(yaml) else {
+        panic!("config should be rejected");
+    };
+    assert_eq!(
+        errors,
+        vec![
+            "exchange.instrument_rules.boards.Main: quantity_step must be positive",
+            "exchange.instrument_rules.boards.Main: min_quantity 2000 exceeds max_quantity 1000",
+            "exchange.instrument_rules.boards.Star: max_quantity must be positive",
+            "exchange.instrument_rules.boards.Star: tick_size must be positive",
+            "exchange.instrument_rules.boards.Star: min_quantity 200 exceeds max_quantity 0",
+            "exchange.fee_schedule.commission_rate: -0.001 must be at least 0 and below 1",
+            "exchange.fee_schedule.stamp_duty_rate: 1 must be at least 0 and below 1",
+            "exchange.fee_schedule.tiers.vip.commission_rate: 1.5 must be at least 0 and below 1",
+        ]
+    );
+}
@@ -1,17 +1,25 @@
-use exchange::config::ExchangeConfig;
+mod common;
+
+use common::{money, price};
+use exchange::config::{CostBasisMethod, ExchangeConfig};
 use exchange::exchange::Exchange;
-use exchange::order::OrderType;
+use exchange::exchange_error::ExchangeError;
+use exchange::fee::{FeeSchedule, FeeTier};
+use exchange::instrument::RuleViolation;
+use exchange::order::{OrderStatus, OrderType};
+use exchange::record::ExchangeRecord;
+use exchange::types::Money;
 
 #[test]
 fn test_add_stock() {
     let config = ExchangeConfig::new();
     let mut exchange = Exchange::new(config);
-    let _ = exchange.add_stock("000001", "平安银行", 15000);
+    let _ = exchange.add_stock("000001", "平安银行", price("150"));
 
     let stock = exchange.get_stock_info("000001").unwrap();
     assert_eq!(stock.code, "000001");
     assert_eq!(stock.name, "平安银行");
-    assert_eq!(stock.current_price, 15000);
+    assert_eq!(stock.current_price, price("150"));
 }
 
 #[test]
@@ -20,10 +28,16 @@ fn test_submit_order() {
     let mut exchange = Exchange::new(config);
     exchange.next_timestamp("09:30:00");
 
-    let _ = exchange.add_stock("000002", "平安银行", 15000);
-    let user_id = exchange.add_user("user1", 100000000).unwrap();
+    let _ = exchange.add_stock("000002", "平安银行", price("150"));
+    let user_id = exchange.add_user("user1", money("1000000")).unwrap();
 
-    let result = exchange.submit_order(user_id, "000002".to_string(), OrderType::Buy, 14900, 100);
+    let result = exchange.submit_order(
+        user_id,
+        "000002".to_string(),
+        OrderType::Buy,
+        price("149"),
+        100,
+    );
 
     println!("{:?}", result);
     assert!(result.is_ok());
@@ -35,29 +49,188 @@ fn test_cancel_order() {
     let mut exchange = Exchange::new(config);
     exchange.next_timestamp("09:30:00");
 
-    let _ = exchange.add_stock("000002", "平安银行", 15000);
-    let user_id = exchange.add_user("user1", 100000000).unwrap();
+    let _ = exchange.add_stock("000002", "平安银行", price("150"));
+    let user_id = exchange.add_user("user1", money("1000000")).unwrap();
     let order_id = exchange
-        .submit_order(user_id, "000002".to_string(), OrderType::Buy, 14900, 100)
+        .submit_order(
+            user_id,
+            "000002".to_string(),
+            OrderType::Buy,
+            price("149"),
+            100,
+        )
         .unwrap();
     let result = exchange.cancel_order(order_id);
     println!("{:?}", result);
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_amend_order() {
+    let config = ExchangeConfig::new();
+    let mut exchange = Exchange::new(config);
+    exchange.next_timestamp("09:30:00");
+
+    let _ = exchange.add_stock("000002", "平安银行", price("150"));
+    let user_id = exchange.add_user("user1", money("1000000")).unwrap();
+    let order_id = exchange
+        .submit_order(
+            user_id,
+            "000002".to_string(),
+            OrderType::Buy,
+            price("149"),
+            100,
+        )
+        .unwrap();
+    let frozen = exchange
+        .user_manager
+        .get_user(user_id)
+        .unwrap()
+        .frozen_balance;
+
+    // 改单撤销原委托 以新价格及数量重新委托 冻结金额随之调整
+    let new_order_id = exchange.amend_order(order_id, price("148"), 200).unwrap();
+    assert_ne!(new_order_id, order_id);
+    let order = exchange.order_manager.get_order(order_id).unwrap();
+    assert_eq!(order.status(), OrderStatus::Cancelled);
+    let new_order = exchange.order_manager.get_order(new_order_id).unwrap();
+    assert_eq!(
+        (new_order.price, new_order.quantity, new_order.order_type),
+        (price("148"), 200, OrderType::Buy)
+    );
+    let user = exchange.user_manager.get_user(user_id).unwrap();
+    assert_eq!(
+        user.frozen_balance,
+        price("148") * 200u32 + new_order.frozen_fee
+    );
+    assert!(user.frozen_balance > frozen);
+    let stock = exchange
+        .stock_manager
+        .get_stock(&"000002".to_string())
+        .unwrap();
+    assert_eq!(
+        stock.buy_quantities.iter().collect::<Vec<_>>(),
+        vec![(&price("148"), &200)]
+    );
+
+    // 已撤销的委托不能再修改
+    assert!(matches!(
+        exchange.amend_order(order_id, price("147"), 100),
+        Err(ExchangeError::OrderNotCancellable(_))
+    ));
+    assert!(matches!(
+        exchange.amend_order(999, price("147"), 100),
+        Err(ExchangeError::OrderNotFound(999))
+    ));
+}
+
+#[test]
+fn test_rejected_amend_keeps_original_order() {
+    let config = ExchangeConfig::new();
+    let mut exchange = Exchange::new(config);
+    exchange.next_timestamp("09:30:00");
+
+    let _ = exchange.add_stock("000002", "平安银行", price("150"));
+    let user_id = exchange.add_user("user1", money("20000")).unwrap();
+    exchange
+        .user_manager
+        .get_user_mut(user_id)
+        .unwrap()
+        .add_holding("000002".to_string(), 100, money("15000"));
+    let buy_id = exchange
+        .submit_order(
+            user_id,
+            "000002".to_string(),
+            OrderType::Buy,
+            price("149"),
+            100,
+        )
+        .unwrap();
+    let sell_id = exchange
+        .submit_order(
+            user_id,
+            "000002".to_string(),
+            OrderType::Sell,
+            price("151"),
+            100,
+        )
+        .unwrap();
+    let frozen = exchange
+        .user_manager
+        .get_user(user_id)
+        .unwrap()
+        .frozen_balance;
+
+    // 新委托校验失败时原委托保持不变
+    assert!(matches!(
+        exchange.amend_order(buy_id, price("170"), 100),
+        Err(ExchangeError::PriceOutOfLimit(_))
+    ));
+    assert!(matches!(
+        exchange.amend_order(buy_id, price("148.005"), 100),
+        Err(ExchangeError::OrderRuleViolation { .. })
+    ));
+    assert!(matches!(
+        exchange.amend_order(buy_id, price("149"), 200),
+        Err(ExchangeError::InsufficientBalance)
+    ));
+    assert!(matches!(
+        exchange.amend_order(sell_id, price("151"), 200),
+        Err(ExchangeError::InsufficientStock)
+    ));
+    let user = exchange.user_manager.get_user(user_id).unwrap();
+    assert_eq!(user.frozen_balance, frozen);
+    assert_eq!(user.holdings["000002"].available_quantity, 0);
+    for order_id in [buy_id, sell_id] {
+        let order = exchange.order_manager.get_order(order_id).unwrap();
+        assert_eq!(order.status(), OrderStatus::New);
+        assert_eq!(order.remaining_quantity, 100);
+    }
+    let stock = exchange
+        .stock_manager
+        .get_stock(&"000002".to_string())
+        .unwrap();
+    assert_eq!(
+        stock.buy_quantities.iter().collect::<Vec<_>>(),
+        vec![(&price("149"), &100)]
+    );
+
+    // 原委托冻结的持仓可用于新委托
+    let new_sell_id = exchange.amend_order(sell_id, price("152"), 100).unwrap();
+    let new_order = exchange.order_manager.get_order(new_sell_id).unwrap();
+    assert_eq!((new_order.price, new_order.quantity), (price("152"), 100));
+}
+
 #[test]
 fn test_order_matching() {
     let config = ExchangeConfig::new();
     let mut exchange = Exchange::new(config);
     exchange.next_timestamp("09:30:00");
 
-    let _ = exchange.add_stock("000002", "平安银行", 15000);
-    let user_id = exchange.add_user("user1", 100000000).unwrap();
+    let _ = exchange.add_stock("000002", "平安银行", price("150"));
+    let user_id = exchange.add_user("user1", money("1000000")).unwrap();
     exchange
-        .submit_order(user_id, "000002".to_string(), OrderType::Buy, 14900, 100)
+        .user_manager
+        .get_user_mut(user_id)
+        .unwrap()
+        .add_holding("000002".to_string(), 100, money("15000"));
+    exchange
+        .submit_order(
+            user_id,
+            "000002".to_string(),
+            OrderType::Buy,
+            price("149"),
+            100,
+        )
         .unwrap();
     exchange
-        .submit_order(user_id, "000002".to_string(), OrderType::Sell, 15100, 100)
+        .submit_order(
+            user_id,
+            "000002".to_string(),
+            OrderType::Sell,
+            price("151"),
+            100,
+        )
         .unwrap();
     exchange.next_timestamp("09:30:01");
 
@@ -65,3 +238,496 @@ fn test_order_matching() {
 
     assert_eq!(trade_log.len(), 0);
 }
+
+#[test]
+fn test_period_transition_events() {
+    let config = ExchangeConfig::new();
+    let mut exchange = Exchange::new(config);
+    exchange.next_timestamp("11:29:59");
+    exchange.drain_events();
+
+    exchange.next_timestamp("11:30:00");
+    let events = exchange.drain_events();
+    let names: Vec<&str> = events.iter().map(|event| event.name()).collect();
+    assert_eq!(names, vec!["clock", "period", "halt"]);
+
+    exchange.next_timestamp("11:30:01");
+    let events = exchange.drain_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].name(), "clock");
+}
+
+#[test]
+fn test_account_settlement() {
+    let mut config = ExchangeConfig::new();
+    config.fee_schedule = FeeSchedule::zero();
+    let mut exchange = Exchange::new(config);
+    exchange.next_timestamp("09:30:00");
+
+    let _ = exchange.add_stock("000002", "平安银行", price("150"));
+    let buyer_id = exchange.add_user("buyer", money("100000")).unwrap();
+    let seller_id = exchange.add_user("seller", Money::ZERO).unwrap();
+    exchange
+        .user_manager
+        .get_user_mut(seller_id)
+        .unwrap()
+        .add_holding("000002".to_string(), 1000, money("150000"));
+
+    exchange
+        .submit_order(
+            buyer_id,
+            "000002".to_string(),
+            OrderType::Buy,
+            price("151"),
+            500,
+        )
+        .unwrap();
+    exchange
+        .submit_order(
+            seller_id,
+            "000002".to_string(),
+            OrderType::Sell,
+            price("150"),
+            300,
+        )
+        .unwrap();
+
+    let buyer = exchange.get_account(buyer_id).unwrap();
+    assert_eq!(buyer.frozen_balance, price("151") * 500u32);
+    let seller = exchange.get_account(seller_id).unwrap();
+    assert_eq!(seller.positions[0].frozen_quantity, 300);
+    assert_eq!(seller.positions[0].available_quantity, 700);
+
+    exchange.next_timestamp("09:30:01");
+
+    let buyer = exchange.get_account(buyer_id).unwrap();
+    assert_eq!(buyer.balance, money("55000"));
+    assert_eq!(buyer.frozen_balance, money("30200"));
+    assert_eq!(buyer.positions[0].quantity, 300);
+    assert_eq!(buyer.positions[0].available_quantity, 0);
+    assert_eq!(buyer.positions[0].average_cost, price("150"));
+
+    let seller = exchange.get_account(seller_id).unwrap();
+    assert_eq!(seller.balance, money("45000"));
+    assert_eq!(seller.positions[0].quantity, 700);
+    assert_eq!(seller.positions[0].frozen_quantity, 0);
+    assert_eq!(seller.total_equity, money("150000"));
+}
+
+#[test]
+fn test_realized_pnl_by_cost_basis_method() {
+    for (method, expected_pnl) in [
+        (CostBasisMethod::AverageCost, Money::ZERO),
+        (CostBasisMethod::Fifo, money("50000")),
+    ] {
+        let mut config = ExchangeConfig::new();
+        config.cost_basis_method = method;
+        config.fee_schedule = FeeSchedule::zero();
+        let mut exchange = Exchange::new(config);
+        exchange.next_timestamp("09:30:00");
+
+        let _ = exchange.add_stock("000002", "平安银行", price("1500"));
+        let buyer_id = exchange.add_user("buyer", money("1000000")).unwrap();
+        let seller_id = exchange.add_user("seller", Money::ZERO).unwrap();
+        let seller = exchange.user_manager.get_user_mut(seller_id).unwrap();
+        seller.add_holding("000002".to_string(), 100, money("100000"));
+        seller.add_holding("000002".to_string(), 100, money("200000"));
+
+        exchange
+            .submit_order(
+                seller_id,
+                "000002".to_string(),
+                OrderType::Sell,
+                price("1500"),
+                100,
+            )
+            .unwrap();
+        exchange
+            .submit_order(
+                buyer_id,
+                "000002".to_string(),
+                OrderType::Buy,
+                price("1500"),
+                100,
+            )
+            .unwrap();
+        exchange.next_timestamp("09:30:01");
+
+        let seller = exchange.get_account(seller_id).unwrap();
+        assert_eq!(seller.realized_pnl, expected_pnl);
+        assert_eq!(seller.unrealized_pnl, -expected_pnl);
+
+        exchange.next_trade_day();
+        let history = exchange.get_pnl_history(seller_id).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].total_pnl, Money::ZERO);
+        assert_eq!(history[0].realized_pnl, expected_pnl);
+    }
+}
+
+#[test]
+fn test_fees_on_settlement() {
+    let mut config = ExchangeConfig::new();
+    config.fee_schedule.tiers.insert(
+        "vip".to_string(),
+        FeeTier {
+            commission_rate: 0.0001,
+            min_commission: money("1"),
+        },
+    );
+    let mut exchange = Exchange::new(config);
+    exchange.next_timestamp("09:30:00");
+
+    let _ = exchange.add_stock("000002", "平安银行", price("150"));
+    let buyer_id = exchange.add_user("buyer", money("100000")).unwrap();
+    let seller_id = exchange.add_user("seller", Money::ZERO).unwrap();
+    exchange
+        .set_user_fee_tier(buyer_id, Some("vip".to_string()))
+        .unwrap();
+    exchange
+        .user_manager
+        .get_user_mut(seller_id)
+        .unwrap()
+        .add_holding("000002".to_string(), 1000, money("150000"));
+
+    // 买入冻结委托金额及预估费用
+    exchange
+        .submit_order(
+            buyer_id,
+            "000002".to_string(),
+            OrderType::Buy,
+            price("150"),
+            200,
+        )
+        .unwrap();
+    let buyer = exchange.get_account(buyer_id).unwrap();
+    assert_eq!(buyer.frozen_balance, money("30003.30"));
+
+    // 分两笔卖单成交 每笔卖单单独收取最低佣金
+    for timestamp in ["09:30:01", "09:30:02"] {
+        exchange
+            .submit_order(
+                seller_id,
+                "000002".to_string(),
+                OrderType::Sell,
+                price("150"),
+                100,
+            )
+            .unwrap();
+        exchange.next_timestamp(timestamp);
+    }
+
+    let buyer = exchange.get_account(buyer_id).unwrap();
+    assert_eq!(buyer.frozen_balance, Money::ZERO);
+    assert_eq!(buyer.balance, money("69996.70"));
+    let report = exchange.get_fee_report(buyer_id).unwrap();
+    assert_eq!(report.commission, money("3"));
+    assert_eq!(report.stamp_duty, Money::ZERO);
+    assert_eq!(report.transfer_fee, money("0.30"));
+    assert_eq!(report.execution_count, 2);
+
+    let seller = exchange.get_account(seller_id).unwrap();
+    assert_eq!(seller.balance, money("29974.70"));
+    let report = exchange.get_fee_report(seller_id).unwrap();
+    assert_eq!(report.commission, money("10"));
+    assert_eq!(report.stamp_duty, money("15"));
+    assert_eq!(report.total, money("25.30"));
+}
+
+#[test]
+fn test_partial_fill_keeps_available_balance() {
+    let config = ExchangeConfig::new();
+    let mut exchange = Exchange::new(config);
+    exchange.next_timestamp("09:30:00");
+
+    let _ = exchange.add_stock("000002", "平安银行", price("10"));
+    // 资金恰好为委托金额加预估费用 首笔部分成交即收取全额最低佣金
+    let amount = price("10") * 200u32;
+    let balance = amount
+        + exchange
+            .config
+            .fee_schedule
+            .estimate(OrderType::Buy, amount, None);
+    let buyer_id = exchange.add_user("buyer", balance).unwrap();
+    let seller_id = exchange.add_user("seller", Money::ZERO).unwrap();
+    exchange
+        .user_manager
+        .get_user_mut(seller_id)
+        .unwrap()
+        .add_holding("000002".to_string(), 200, money("2000"));
+
+    exchange
+        .submit_order(
+            buyer_id,
+            "000002".to_string(),
+            OrderType::Buy,
+            price("10"),
+            200,
+        )
+        .unwrap();
+    for timestamp in ["09:30:01", "09:30:02"] {
+        exchange
+            .submit_order(
+                seller_id,
+                "000002".to_string(),
+                OrderType::Sell,
+                price("10"),
+                100,
+            )
+            .unwrap();
+        exchange.next_timestamp(timestamp);
+        let buyer = exchange.user_manager.get_user(buyer_id).unwrap();
+        assert!(buyer.available_balance() >= Money::ZERO);
+    }
+
+    let buyer = exchange.get_account(buyer_id).unwrap();
+    assert_eq!(buyer.frozen_balance, Money::ZERO);
+    assert_eq!(buyer.balance, Money::ZERO);
+}
+
+#[test]
+fn test_instrument_rules() {
+    let config = ExchangeConfig::new();
+    let mut exchange = Exchange::new(config);
+    exchange.next_timestamp("09:30:00");
+
+    let _ = exchange.add_stock("000002", "平安银行", price("150"));
+    let _ = exchange.add_stock("688001", "华兴源创", price("150"));
+    let user_id = exchange.add_user("user1", money("1000000")).unwrap();
+    exchange
+        .user_manager
+        .get_user_mut(user_id)
+        .unwrap()
+        .add_holding("000002".to_string(), 250, money("37500"));
+
+    // 主板买入最少100股 须为100股整数倍
+    let result = exchange.submit_order(
+        user_id,
+        "000002".to_string(),
+        OrderType::Buy,
+        price("150"),
+        50,
+    );
+    assert!(matches!(
+        result,
+        Err(ExchangeError::OrderRuleViolation {
+            violation: RuleViolation::BelowMinimum { .. },
+            ..
+        })
+    ));
+    let result = exchange.submit_order(
+        user_id,
+        "000002".to_string(),
+        OrderType::Buy,
+        price("150"),
+        250,
+    );
+    assert!(matches!(
+        result,
+        Err(ExchangeError::OrderRuleViolation {
+            violation: RuleViolation::InvalidStep { .. },
+            ..
+        })
+    ));
+
+    // 零股须一次性卖出
+    let result = exchange.submit_order(
+        user_id,
+        "000002".to_string(),
+        OrderType::Sell,
+        price("150"),
+        30,
+    );
+    assert!(matches!(
+        result,
+        Err(ExchangeError::OrderRuleViolation {
+            violation: RuleViolation::OddLotNotWhole { odd_lot: 50 },
+            ..
+        })
+    ));
+    assert!(exchange
+        .submit_order(
+            user_id,
+            "000002".to_string(),
+            OrderType::Sell,
+            price("150"),
+            150
+        )
+        .is_ok());
+    assert!(exchange
+        .submit_order(
+            user_id,
+            "000002".to_string(),
+            OrderType::Sell,
+            price("150"),
+            100
+        )
+        .is_ok());
+
+    // 科创板最少200股 以1股递增
+    let result = exchange.submit_order(
+        user_id,
+        "688001".to_string(),
+        OrderType::Buy,
+        price("150"),
+        100,
+    );
+    assert!(matches!(
+        result,
+        Err(ExchangeError::OrderRuleViolation {
+            violation: RuleViolation::BelowMinimum {
+                min_quantity: 200,
+                ..
+            },
+            ..
+        })
+    ));
+    assert!(exchange
+        .submit_order(
+            user_id,
+            "688001".to_string(),
+            OrderType::Buy,
+            price("150"),
+            201
+        )
+        .is_ok());
+}
+
+#[test]
+fn test_trade_timestamps_across_days() {
+    let mut config = ExchangeConfig::new();
+    config.fee_schedule = FeeSchedule::zero();
+    let mut exchange = Exchange::new(config);
+    let _ = exchange.add_stock("000002", "平安银行", price("15"));
+    let buyer_id = exchange.add_user("buyer", money("1000000")).unwrap();
+    let seller_id = exchange.add_user("seller", Money::ZERO).unwrap();
+    let seller = exchange.user_manager.get_user_mut(seller_id).unwrap();
+    seller.add_holding("000002".to_string(), 200, money("3000"));
+
+    let first_day = exchange.current_trade_day();
+    for _ in 0..2 {
+        exchange.next_timestamp("09:30:00");
+        let sell_order_id = exchange
+            .submit_order(
+                seller_id,
+                "000002".to_string(),
+                OrderType::Sell,
+                price("15"),
+                100,
+            )
+            .unwrap();
+        exchange
+            .submit_order(
+                buyer_id,
+                "000002".to_string(),
+                OrderType::Buy,
+                price("15"),
+                100,
+            )
+            .unwrap();
+        let order = exchange.order_manager.get_order(sell_order_id).unwrap();
+        assert_eq!(order.timestamp, exchange.get_current_timestamp());
+
+        // 同一秒内的时间点可以区分先后
+        exchange.next_timestamp("09:30:00.250");
+        exchange.next_timestamp("09:30:00.100");
+        assert_eq!(exchange.get_current_timestamp().time(), 34_200_250);
+        exchange.next_trade_day();
+    }
+
+    let (logs, _) = exchange.get_trade_logs("000002", 1, 10);
+    assert_eq!(logs.len(), 2);
+    let second_day = exchange.trade_day_manager.next_trade_day(first_day);
+    assert_eq!(
+        logs[0].timestamp.to_string(),
+        format!("{}T09:30:00.250", second_day)
+    );
+    assert_eq!(
+        logs[1].timestamp.to_string(),
+        format!("{}T09:30:00.250", first_day)
+    );
+    assert!(logs[0].timestamp > logs[1].timestamp);
+}
+
+#[test]
+fn test_records_order_lifecycle() {
+    let mut config = ExchangeConfig::new();
+    config.fee_schedule = FeeSchedule::zero();
+    let mut exchange = Exchange::new(config);
+    let _ = exchange.add_stock("000002", "平安银行", price("15"));
+    let seller_id = exchange.add_user("seller", Money::ZERO).unwrap();
+    let seller = exchange.user_manager.get_user_mut(seller_id).unwrap();
+    seller.add_holding("000002".to_string(), 200, money("3000"));
+    // 启用前已存在的用户同样记录
+    exchange.enable_records();
+    let buyer_id = exchange.add_user("buyer", money("1000000")).unwrap();
+
+    exchange.next_timestamp("09:30:00");
+    let sell_order_id = exchange
+        .submit_order(
+            seller_id,
+            "000002".to_string(),
+            OrderType::Sell,
+            price("15"),
+            200,
+        )
+        .unwrap();
+    exchange
+        .submit_order(
+            buyer_id,
+            "000002".to_string(),
+            OrderType::Buy,
+            price("15"),
+            100,
+        )
+        .unwrap();
+    exchange.next_timestamp("09:30:01");
+    exchange.next_trade_day();
+
+    let records = exchange.drain_records();
+    let users = records
+        .iter()
+        .filter(|record| matches!(record, ExchangeRecord::User { .. }))
+        .count();
+    assert_eq!(users, 2);
+    let sell_statuses: Vec<OrderStatus> = records
+        .iter()
+        .filter_map(|record| match record {
+            ExchangeRecord::Order {
+                order_id, status, ..
+            } if *order_id == sell_order_id => Some(*status),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        sell_statuses,
+        vec![
+            OrderStatus::New,
+            OrderStatus::PartiallyFilled,
+            OrderStatus::Expired
+        ]
+    );
+    let executions = records
+        .iter()
+        .filter(|record| matches!(record, ExchangeRecord::Execution { quantity: 100, .. }))
+        .count();
+    assert_eq!(executions, 2);
+    let trade = records
+        .iter()
+        .find_map(|record| match record {
+            ExchangeRecord::Trade(trade) => Some(trade),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(trade.id, exchange.log_manager.get_all_logs()[0].id);
+    let bars: Vec<_> = records
+        .iter()
+        .filter_map(|record| match record {
+            ExchangeRecord::DailyBar(bar) => Some(bar),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(bars.len(), 1);
+    assert_eq!(bars[0].volume, 100);
+    assert!(exchange.drain_records().is_empty());
+}
@@ -0,0 +1,263 @@
+mod common;
+
+use common::TestConfig;
+use exchange::exchange::Exchange;
+use exchange::export::{
+    export_archived_orders, export_order_archive, CandleInterval, ExportFormat, ExportWriter,
+};
+use exchange::log::SpillWriter;
+use exchange::types::Money;
+use serde_json::Value;
+use std::io::Write;
+
+fn build_exchange() -> Exchange {
+    TestConfig {
+        seed: Some(11),
+        bots: &[
+            ("robot1", "{ type: trade_random, param: 5 }"),
+            ("robot2", "{ type: trade_random, param: 5 }"),
+        ],
+        ..Default::default()
+    }
+    .build()
+}
+
+fn to_string(writer: ExportWriter<Vec<u8>>) -> String {
+    String::from_utf8(writer.finish().unwrap()).unwrap()
+}
+
+fn json_lines(text: &str) -> Vec<Value> {
+    text.lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_export_format() {
+    assert_eq!("csv".parse::<ExportFormat>(), Ok(ExportFormat::Csv));
+    assert_eq!("jsonl".parse::<ExportFormat>(), Ok(ExportFormat::JsonLines));
+    assert!("xlsx".parse::<ExportFormat>().is_err());
+    assert_eq!(ExportFormat::JsonLines.extension(), "jsonl");
+}
+
+#[test]
+fn test_export_trades_in_batches() {
+    let mut exchange = build_exchange();
+    exchange.run_trading_days(1).unwrap();
+    let total = exchange.log_manager.len();
+    assert!(total > 10);
+
+    let mut whole = ExportWriter::new(Vec::new(), ExportFormat::Csv);
+    let next = exchange
+        .export_trades(None, None, 0, usize::MAX, &mut whole)
+        .unwrap();
+    assert_eq!(next, None);
+    assert_eq!(whole.rows(), total);
+    let whole = to_string(whole);
+    // 表头只写一次
+    assert!(whole.starts_with("id,"));
+    assert_eq!(whole.lines().count(), total + 1);
+
+    // 分批写入同一个 writer 结果与一次导出相同
+    let mut batched = ExportWriter::new(Vec::new(), ExportFormat::Csv);
+    let mut cursor = Some(0);
+    let mut batches = 0;
+    while let Some(from) = cursor {
+        cursor = exchange
+            .export_trades(None, None, from, 7, &mut batched)
+            .unwrap();
+        batches += 1;
+    }
+    assert_eq!(batches, total.div_ceil(7));
+    assert_eq!(to_string(batched), whole);
+
+    // 按股票及用户筛选
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::JsonLines);
+    exchange
+        .export_trades(Some("000002"), Some(1), 0, usize::MAX, &mut writer)
+        .unwrap();
+    let rows = json_lines(&to_string(writer));
+    assert!(!rows.is_empty());
+    assert!(rows.iter().all(
+        |row| row["stock_code"] == "000002" && (row["buyer_id"] == 1 || row["seller_id"] == 1)
+    ));
+}
+
+#[test]
+fn test_export_candles() {
+    let mut exchange = build_exchange();
+    exchange.run_trading_days(2).unwrap();
+
+    // 日K线与 daily_bars 一致
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::JsonLines);
+    exchange
+        .export_candles("000001", CandleInterval::Day, 0, usize::MAX, &mut writer)
+        .unwrap();
+    let candles = json_lines(&to_string(writer));
+    let bars: Vec<_> = exchange
+        .daily_bars()
+        .into_iter()
+        .filter(|bar| bar.stock_code == "000001")
+        .collect();
+    assert_eq!(candles.len(), 2);
+    for (candle, bar) in candles.iter().zip(&bars) {
+        assert_eq!(candle["close"], bar.close.to_string());
+        assert_eq!(candle["high"], bar.high.to_string());
+        assert_eq!(candle["low"], bar.low.to_string());
+        assert_eq!(candle["volume"], bar.volume);
+    }
+
+    // 分钟线每条价格历史一行 一次一条时游标为价格历史下标
+    let history_len = exchange
+        .stock_manager
+        .get_stock(&"000001".to_string())
+        .unwrap()
+        .price_history
+        .len();
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::Csv);
+    let next = exchange
+        .export_candles("000001", CandleInterval::Minute, 0, 1, &mut writer)
+        .unwrap();
+    assert_eq!(next, Some(1));
+    let next = exchange
+        .export_candles("000001", CandleInterval::Minute, 1, usize::MAX, &mut writer)
+        .unwrap();
+    assert_eq!(next, None);
+    assert_eq!(writer.rows(), history_len);
+
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::Csv);
+    assert!(exchange
+        .export_candles("999999", CandleInterval::Day, 0, 10, &mut writer)
+        .is_err());
+}
+
+#[test]
+fn test_export_archived_orders() {
+    let mut exchange = build_exchange();
+    exchange.enable_order_archive();
+    exchange.run_trading_days(1).unwrap();
+    let orders = exchange.drain_archived_orders();
+    assert!(!orders.is_empty());
+    assert!(exchange.drain_archived_orders().is_empty());
+
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::JsonLines);
+    export_archived_orders(&orders, &mut writer).unwrap();
+    let rows = json_lines(&to_string(writer));
+    // 每笔成交一行 没有成交的委托一行
+    let expected: usize = orders
+        .iter()
+        .map(|order| order.executions.len().max(1))
+        .sum();
+    assert_eq!(rows.len(), expected);
+    for row in &rows {
+        let remaining = row["remaining_quantity"].as_u64().unwrap();
+        match row["status"].as_str().unwrap() {
+            "expired" => assert!(remaining > 0),
+            "filled" | "cancelled" => {}
+            status => panic!("unexpected status {} after close", status),
+        }
+        assert_eq!(
+            row["execution_price"].is_null(),
+            row["execution_quantity"].is_null()
+        );
+    }
+    assert!(rows.iter().any(|row| !row["execution_price"].is_null()));
+}
+
+#[test]
+fn test_export_order_archive_file() {
+    let mut exchange = build_exchange();
+    exchange.enable_order_archive();
+    let path =
+        std::env::temp_dir().join(format!("export_order_archive_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut archive = SpillWriter::open(&path).unwrap();
+    let mut orders = Vec::new();
+    for _ in 0..2 {
+        exchange.run_trading_days(1).unwrap();
+        let archived = exchange.drain_archived_orders();
+        archive.write(&archived).unwrap();
+        orders.extend(archived);
+    }
+    let user_id = orders[0].user_id;
+
+    let mut expected = ExportWriter::new(Vec::new(), ExportFormat::Csv);
+    let user_orders: Vec<_> = orders
+        .iter()
+        .filter(|order| order.user_id == user_id)
+        .cloned()
+        .collect();
+    export_archived_orders(&user_orders, &mut expected).unwrap();
+
+    // 按字节偏移分批读取 结果与一次导出相同
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::Csv);
+    let mut cursor = Some(0);
+    let mut batches = 0;
+    while let Some(from) = cursor {
+        cursor = export_order_archive(&path, Some(user_id), from, 7, &mut writer).unwrap();
+        batches += 1;
+    }
+    assert!(batches > 2);
+    assert_eq!(to_string(writer), to_string(expected));
+
+    // 未写完的最后一行不读取
+    drop(archive);
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap()
+        .write_all(b"{\"id\":")
+        .unwrap();
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::JsonLines);
+    assert_eq!(
+        export_order_archive(&path, None, 0, usize::MAX, &mut writer).unwrap(),
+        None
+    );
+    let expected: usize = orders
+        .iter()
+        .map(|order| order.executions.len().max(1))
+        .sum();
+    assert_eq!(json_lines(&to_string(writer)).len(), expected);
+    std::fs::remove_file(&path).unwrap();
+
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::JsonLines);
+    assert_eq!(
+        export_order_archive(&path, None, 0, 10, &mut writer).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_export_statements() {
+    let mut exchange = build_exchange();
+    exchange.run_trading_days(2).unwrap();
+
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::Csv);
+    let next = exchange.export_statements(None, 0, 1, &mut writer).unwrap();
+    assert_eq!(next, Some(2));
+    exchange
+        .export_statements(None, 2, usize::MAX, &mut writer)
+        .unwrap();
+    assert_eq!(writer.rows(), 4);
+
+    let text = to_string(writer);
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let headers = reader.headers().unwrap().clone();
+    for record in reader.records() {
+        let record = record.unwrap();
+        let field = |name: &str| -> Money {
+            let index = headers.iter().position(|header| header == name).unwrap();
+            record[index].parse().unwrap()
+        };
+        assert_eq!(field("cash") + field("market_value"), field("total_equity"));
+    }
+
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::JsonLines);
+    exchange
+        .export_statements(Some(2), 0, usize::MAX, &mut writer)
+        .unwrap();
+    let rows = json_lines(&to_string(writer));
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["username"], "robot2");
+    assert_eq!(rows[1]["trade_day"], "2024-03-04");
+}
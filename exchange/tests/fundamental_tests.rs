@@ -0,0 +1,146 @@
+mod common;
+
+use common::TestConfig;
+use exchange::clock::SimulationClock;
+use exchange::config_file::{ConfigError, ConfigFile};
+use exchange::exchange::Exchange;
+use exchange::snapshot;
+use exchange::types::Price;
+use std::collections::BTreeMap;
+
+const NOISE: &str = "{ type: noise, noise: 0.01, order_size: 500, probability: 0.5 }";
+const INFORMED: &str =
+    "{ type: informed, threshold: 0.001, order_size: 2000, max_position: 50000 }";
+const FUNDAMENTAL: &str =
+    "fundamental:\n  volatility: 0.05\n  jump_intensity: 1.0\n  jump_volatility: 0.05\n";
+
+const NOISE_BOTS: &[(&str, &str)] = &[("noise1", NOISE), ("noise2", NOISE), ("noise3", NOISE)];
+const INFORMED_BOTS: &[(&str, &str)] = &[
+    ("noise1", NOISE),
+    ("noise2", NOISE),
+    ("noise3", NOISE),
+    ("informed1", INFORMED),
+    ("informed2", INFORMED),
+];
+
+/** 两只股票及基本面价值过程 */
+fn config(seed: u64, bots: &[(&str, &str)]) -> String {
+    TestConfig {
+        seed: Some(seed),
+        bots,
+        holding: 20000,
+        extra: FUNDAMENTAL,
+        ..Default::default()
+    }
+    .yaml()
+}
+
+fn build_exchange(yaml: &str) -> Exchange {
+    ConfigFile::from_yaml(yaml)
+        .unwrap()
+        .build_exchange()
+        .unwrap()
+}
+
+fn fundamental_values(exchange: &Exchange) -> Vec<Option<Price>> {
+    ["000001", "000002"]
+        .iter()
+        .map(|stock_code| exchange.fundamental_value(stock_code))
+        .collect()
+}
+
+#[test]
+fn test_fundamental_process_is_seeded() {
+    let mut exchange = build_exchange(&config(13, NOISE_BOTS));
+    assert_eq!(
+        fundamental_values(&exchange),
+        vec![Some("10".parse().unwrap()), Some("20".parse().unwrap())]
+    );
+    exchange.run_trading_days(1).unwrap();
+    let values = fundamental_values(&exchange);
+    assert_ne!(values[0], Some("10".parse().unwrap()));
+
+    // 相同种子的价值路径相同 与机器人无关
+    let mut same_seed = build_exchange(&config(13, INFORMED_BOTS));
+    same_seed.run_trading_days(1).unwrap();
+    assert_eq!(fundamental_values(&same_seed), values);
+
+    let mut other_seed = build_exchange(&config(14, NOISE_BOTS));
+    other_seed.run_trading_days(1).unwrap();
+    assert_ne!(fundamental_values(&other_seed), values);
+
+    // 每分钟记录一次价值及市场价格
+    let history = exchange
+        .fundamental
+        .as_ref()
+        .unwrap()
+        .history("000001")
+        .unwrap();
+    assert_eq!(history.len(), 30);
+    assert_eq!(history.last().unwrap().value, values[0].unwrap());
+}
+
+#[test]
+fn test_informed_traders_improve_efficiency() {
+    let mut noise_only = build_exchange(&config(13, NOISE_BOTS));
+    noise_only.run_trading_days(3).unwrap();
+    let mut with_informed = build_exchange(&config(13, INFORMED_BOTS));
+    with_informed.run_trading_days(3).unwrap();
+
+    // 知情交易者使价格更接近基本面价值
+    let noise_only = noise_only.market_efficiency();
+    let with_informed = with_informed.market_efficiency();
+    assert_eq!(with_informed.len(), 2);
+    for (noise_only, with_informed) in noise_only.iter().zip(&with_informed) {
+        assert_eq!(with_informed.samples, 3 * 600);
+        assert!(
+            with_informed.mean_absolute_error < noise_only.mean_absolute_error,
+            "{:?} {:?}",
+            with_informed,
+            noise_only
+        );
+    }
+}
+
+#[test]
+fn test_fundamental_restored_from_snapshot() {
+    let mut exchange = build_exchange(&config(13, INFORMED_BOTS));
+    let clock = SimulationClock::new();
+    for _ in 0..600 {
+        exchange.tick(&clock).unwrap();
+    }
+    let bytes = snapshot::to_bytes(&exchange, &clock, &BTreeMap::new()).unwrap();
+    let mut restored = snapshot::from_slice(&bytes).unwrap().exchange;
+
+    for _ in 0..1500 {
+        exchange.tick(&clock).unwrap();
+        restored.tick(&clock).unwrap();
+    }
+    assert_eq!(fundamental_values(&restored), fundamental_values(&exchange));
+    assert_eq!(
+        serde_json::to_string(&restored.market_efficiency()).unwrap(),
+        serde_json::to_string(&exchange.market_efficiency()).unwrap()
+    );
+}
+
+#[test]
+fn test_fundamental_config() {
+    let without_fundamental = config(13, INFORMED_BOTS).replace(FUNDAMENTAL, "");
+    let err = ConfigFile::from_yaml(&without_fundamental).unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(ref errors) if errors.len() == 2));
+    assert!(err
+        .to_string()
+        .contains("informed strategy requires fundamental"));
+
+    let invalid = config(13, NOISE_BOTS)
+        .replace(
+            "  volatility: 0.05",
+            "  volatility: -0.05\n  stocks: [\"000003\"]",
+        )
+        .replace("probability: 0.5", "probability: 1.5");
+    let err = ConfigFile::from_yaml(&invalid).unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(ref errors) if errors.len() == 5));
+    assert!(err
+        .to_string()
+        .contains("fundamental: unknown stock 000003"));
+}
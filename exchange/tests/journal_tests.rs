@@ -0,0 +1,149 @@
+mod common;
+
+use common::{state_of, TestConfig};
+use exchange::clock::SimulationClock;
+use exchange::exchange::Exchange;
+use exchange::journal::{self, Command, JournalError, JournalWriter};
+use exchange::order::OrderType;
+use exchange::timestamp::MILLIS_PER_SECOND;
+use exchange::types::{Money, Price};
+use std::path::PathBuf;
+
+fn journal_path(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("exchange-journal-{}", std::process::id()))
+        .join(name)
+}
+
+/** 记录一段包含用户下单、入金、机器人交易及换日的模拟 */
+fn record(path: &PathBuf, clock: &SimulationClock) -> Exchange {
+    let mut exchange = TestConfig {
+        seed: Some(11),
+        stocks: &[("000001", "10.00")],
+        ..Default::default()
+    }
+    .build();
+    exchange.enable_journal();
+    let mut writer = JournalWriter::create(path, &exchange).unwrap();
+
+    let user_id = exchange
+        .add_user("trader", Money::from_yuan(10_000))
+        .unwrap();
+    exchange.deposit(user_id, Money::from_yuan(5_000)).unwrap();
+    for step in 0..2500 {
+        exchange.tick(clock).unwrap();
+        if step == 100 {
+            let price: Price = "10.00".parse().unwrap();
+            let order_id = exchange
+                .submit_order(user_id, "000001".to_string(), OrderType::Buy, price, 100)
+                .unwrap();
+            let order_id = exchange
+                .amend_order(order_id, "9.90".parse().unwrap(), 200)
+                .unwrap();
+            let _ = exchange.cancel_order(order_id);
+            // 失败的命令同样记录 重放结果相同
+            assert!(exchange
+                .submit_order(user_id, "999999".to_string(), OrderType::Buy, price, 100)
+                .is_err());
+        }
+        writer.write(&exchange.drain_journal()).unwrap();
+    }
+    exchange
+}
+
+#[test]
+fn test_replay_rebuilds_identical_state() {
+    let path = journal_path("replay.jsonl");
+    let exchange = record(&path, &SimulationClock::new());
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains(r#""type":"bot_action""#));
+    assert!(content.contains(r#""type":"deposit""#));
+    assert!(content.contains(r#""type":"amend""#));
+    // 机器人下单由时钟推进产生 不单独记录
+    assert_eq!(content.matches(r#""type":"submit""#).count(), 2);
+
+    let replay = journal::replay(&path).unwrap();
+    assert_eq!(replay.entries as usize, content.lines().count() - 1);
+    let restored = replay.exchange;
+    assert_eq!(
+        restored.get_current_timestamp(),
+        exchange.get_current_timestamp()
+    );
+    assert_eq!(state_of(&restored), state_of(&exchange));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_replay_uses_recorded_clock_step() {
+    let path = journal_path("half_second.jsonl");
+    let clock = SimulationClock::with_step(MILLIS_PER_SECOND / 2);
+    let exchange = record(&path, &clock);
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains(r#""type":"tick","step":500"#));
+
+    // 按记录的步长重放 时间及机器人交易与记录时相同
+    let restored = journal::replay(&path).unwrap().exchange;
+    assert_eq!(
+        restored.get_current_timestamp(),
+        exchange.get_current_timestamp()
+    );
+    assert_eq!(state_of(&restored), state_of(&exchange));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_replay_detects_divergence() {
+    let path = journal_path("diverged.jsonl");
+    record(&path, &SimulationClock::new());
+    let content = std::fs::read_to_string(&path).unwrap();
+
+    // 篡改一条机器人决策 模拟撮合或策略行为不同的版本
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let index = lines
+        .iter()
+        .position(|line| line.contains(r#""type":"bot_action""#))
+        .unwrap();
+    let mut entry: serde_json::Value = serde_json::from_str(&lines[index]).unwrap();
+    let action = entry["command"]["action"].as_object_mut().unwrap();
+    let (_, order) = action.iter_mut().next().unwrap();
+    order["quantity"] = serde_json::json!(123_456);
+    lines[index] = entry.to_string();
+    std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+    match journal::replay(&path) {
+        Err(JournalError::Diverged { seq, expected, .. }) => {
+            assert_eq!(seq as usize, index);
+            assert!(matches!(
+                expected.as_deref(),
+                Some(Command::BotAction { .. })
+            ));
+        }
+        other => panic!("expected divergence, got {:?}", other.err()),
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_resume_discards_truncated_entry() {
+    let path = journal_path("truncated.jsonl");
+    let exchange = record(&path, &SimulationClock::new());
+    let entries = journal::replay(&path).unwrap().entries;
+
+    // 写入中断 最后一行不完整
+    let mut content = std::fs::read(&path).unwrap();
+    content.extend_from_slice(br#"{"seq":"#);
+    std::fs::write(&path, content).unwrap();
+
+    let replay = journal::replay(&path).unwrap();
+    assert_eq!(replay.entries, entries);
+    let mut writer = JournalWriter::resume(&path, &replay).unwrap();
+    let mut restored = replay.exchange;
+    restored.tick(&SimulationClock::new()).unwrap();
+    writer.write(&restored.drain_journal()).unwrap();
+    drop(writer);
+
+    let replay = journal::replay(&path).unwrap();
+    assert_eq!(replay.entries, entries + 1);
+    assert!(replay.exchange.get_current_timestamp() > exchange.get_current_timestamp());
+    std::fs::remove_file(&path).unwrap();
+}
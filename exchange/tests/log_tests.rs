@@ -0,0 +1,176 @@
+use chrono::NaiveDate;
+use exchange::log::{LogManager, PriceDirection, TradeLog, TradeLogQuery};
+use exchange::types::{Price, StockCode, Timestamp, UserId};
+
+fn trade(stock_code: &str, buyer_id: UserId, seller_id: UserId, second: u32) -> TradeLog {
+    let mut log = TradeLog::new(
+        buyer_id,
+        seller_id,
+        Price::from_raw(10_000),
+        100,
+        1,
+        2,
+        PriceDirection::Flat,
+    );
+    log.stock_code = stock_code.to_string();
+    log.timestamp = at(second);
+    log
+}
+
+fn at(second: u32) -> Timestamp {
+    let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    Timestamp::new(day, (9 * 3600 + 30 * 60 + second) * 1000)
+}
+
+fn ids(logs: &[TradeLog]) -> Vec<u64> {
+    logs.iter().map(|log| log.id).collect()
+}
+
+/** 编号 1-10 股票A、B交替 用户1为买方 用户2、3轮流为卖方 每秒一笔 */
+fn build_logs(max_logs: Option<usize>, spill: bool) -> LogManager {
+    let mut manager = LogManager::with_max_logs(max_logs);
+    if spill {
+        manager.enable_spill();
+    }
+    for i in 0..10 {
+        let stock_code = if i % 2 == 0 { "A" } else { "B" };
+        let seller_id = if i % 3 == 0 { 3 } else { 2 };
+        manager.add_log(trade(stock_code, 1, seller_id, i));
+    }
+    manager
+}
+
+#[test]
+fn test_page_logs() {
+    let manager = build_logs(None, false);
+    // 股票A 编号 9 7 5 3 1
+    let (logs, total_pages) = manager.page_logs("A", 1, 2);
+    assert_eq!(ids(&logs), vec![9, 7]);
+    assert_eq!(total_pages, 3);
+    let (logs, _) = manager.page_logs("A", 2, 2);
+    assert_eq!(ids(&logs), vec![5, 3]);
+    let (logs, _) = manager.page_logs("A", 3, 2);
+    assert_eq!(ids(&logs), vec![1]);
+    assert!(manager.page_logs("A", 4, 2).0.is_empty());
+    let (logs, total_pages) = manager.page_logs("C", 1, 2);
+    assert!(logs.is_empty());
+    assert_eq!(total_pages, 0);
+}
+
+#[test]
+fn test_indexed_queries() {
+    let manager = build_logs(None, false);
+    assert_eq!(
+        ids(&manager.get_logs_by_stock(&"B".to_string())),
+        vec![10, 8, 6, 4, 2]
+    );
+    assert_eq!(ids(&manager.get_logs_by_user(3)), vec![10, 7, 4, 1]);
+    assert_eq!(manager.get_logs_by_user(1).len(), 10);
+    assert_eq!(manager.get_log(4).unwrap().stock_code, "B");
+    assert!(manager.get_log(11).is_none());
+
+    // 游标分页 遍历用户2的全部成交
+    let mut query = TradeLogQuery {
+        user_id: Some(2),
+        limit: 4,
+        ..Default::default()
+    };
+    let mut pages = Vec::new();
+    loop {
+        let page = manager.query(&query);
+        pages.push(ids(&page.logs));
+        match page.next_cursor {
+            Some(cursor) => query.before = Some(cursor),
+            None => break,
+        }
+    }
+    assert_eq!(pages, vec![vec![9, 8, 6, 5], vec![3, 2]]);
+
+    // 时间范围 结束时间不包含
+    let page = manager.query(&TradeLogQuery {
+        stock_code: Some(StockCode::from("A")),
+        start_time: Some(at(2)),
+        end_time: Some(at(8)),
+        limit: 10,
+        ..Default::default()
+    });
+    assert_eq!(ids(&page.logs), vec![7, 5, 3]);
+    assert_eq!(page.next_cursor, None);
+
+    // 股票与用户同时指定
+    let page = manager.query(&TradeLogQuery {
+        stock_code: Some(StockCode::from("B")),
+        user_id: Some(3),
+        limit: 10,
+        ..Default::default()
+    });
+    assert_eq!(ids(&page.logs), vec![10, 4]);
+
+    // 用户最近的成交都不是该股票时 先按股票筛选再分页
+    let mut manager = LogManager::with_max_logs(None);
+    for i in 0..20 {
+        let stock_code = if i < 10 { "000001" } else { "000002" };
+        manager.add_log(trade(stock_code, 1, 2, i));
+    }
+    let mut query = TradeLogQuery {
+        stock_code: Some(StockCode::from("000001")),
+        user_id: Some(1),
+        limit: 5,
+        ..Default::default()
+    };
+    let page = manager.query(&query);
+    assert_eq!(ids(&page.logs), vec![10, 9, 8, 7, 6]);
+    assert_eq!(page.next_cursor, Some(6));
+    query.before = page.next_cursor;
+    let page = manager.query(&query);
+    assert_eq!(ids(&page.logs), vec![5, 4, 3, 2, 1]);
+    assert_eq!(page.next_cursor, None);
+
+    let manager = build_logs(None, false);
+    let page = manager.query(&TradeLogQuery {
+        before: Some(3),
+        limit: 10,
+        ..Default::default()
+    });
+    assert_eq!(ids(&page.logs), vec![2, 1]);
+}
+
+#[test]
+fn test_retention_spills_oldest_logs() {
+    let mut manager = build_logs(Some(4), true);
+    assert_eq!(manager.len(), 4);
+    assert_eq!(manager.total_logs(), 10);
+    assert_eq!(ids(&manager.drain_spilled()), vec![1, 2, 3, 4, 5, 6]);
+    assert!(manager.drain_spilled().is_empty());
+    assert!(manager.get_log(6).is_none());
+    assert_eq!(
+        ids(&manager.get_logs_by_stock(&"A".to_string())),
+        vec![9, 7]
+    );
+    assert_eq!(ids(&manager.get_logs_by_user(3)), vec![10, 7]);
+    assert_eq!(manager.page_logs("B", 1, 10).1, 1);
+
+    // 未启用时移除的记录直接丢弃
+    let mut manager = build_logs(Some(3), false);
+    assert_eq!(manager.len(), 3);
+    assert!(manager.drain_spilled().is_empty());
+}
+
+#[test]
+fn test_restore_rebuilds_indexes() {
+    let manager = build_logs(Some(6), false);
+    let json = serde_json::to_string(&manager).unwrap();
+    let mut restored: LogManager = serde_json::from_str(&json).unwrap();
+    assert_eq!(ids(&restored.get_all_logs()), ids(&manager.get_all_logs()));
+    assert_eq!(ids(&restored.get_logs_by_user(2)), vec![9, 8, 6, 5]);
+    assert_eq!(
+        ids(&restored.get_logs_by_stock(&"A".to_string())),
+        vec![9, 7, 5]
+    );
+
+    // 编号及保留策略继续生效
+    let id = restored.add_log(trade("A", 1, 2, 10));
+    assert_eq!(id, 11);
+    assert_eq!(restored.len(), 6);
+    assert!(restored.get_log(5).is_none());
+}
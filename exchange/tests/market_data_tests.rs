@@ -0,0 +1,264 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{price, TestConfig};
+use exchange::clock::SimulationClock;
+use exchange::config_file::{ConfigError, ConfigFile};
+use exchange::journal::{self, JournalWriter};
+use exchange::market_data::{MarketData, MarketDataError, REPLAY_AGENT_NAME};
+use exchange::types::{Price, Timestamp};
+
+const DAILY_CSV: &str = "\
+Date,Open,High,Low,Close,Volume
+2024-02-29,9.80,10.05,9.75,10.00,1200000
+2024-02-28,9.60,9.90,9.50,9.80,1000000
+2024-03-01,10.00,10.60,9.95,10.50,1500000
+2024-03-04,10.50,10.55,10.00,10.10,1300000
+";
+
+fn date(value: &str) -> NaiveDate {
+    value.parse().unwrap()
+}
+
+/** 单只股票 两个随机交易机器人 */
+fn config() -> TestConfig<'static> {
+    TestConfig {
+        seed: Some(7),
+        end_tick: "10:29:59",
+        stocks: &[("000001", "8.00")],
+        bots: &[
+            ("robot1", "{ type: trade_random, param: 5 }"),
+            ("robot2", "{ type: trade_random, param: 5 }"),
+        ],
+        holding: 20000,
+        ..Default::default()
+    }
+}
+
+fn daily_data() -> MarketData {
+    MarketData::from_reader(DAILY_CSV.as_bytes(), Some("000001"), "000001.csv").unwrap()
+}
+
+#[test]
+fn test_parse_csv() {
+    let data = daily_data();
+    let bars = data.bars("000001");
+    assert_eq!(bars.len(), 4);
+    // 按时间排序
+    assert_eq!(
+        bars[0].timestamp,
+        Timestamp::start_of_day(date("2024-02-28"))
+    );
+    assert_eq!(bars[3].close, price("10.10"));
+    assert!(bars.iter().all(|bar| bar.is_daily()));
+
+    // 代码列 分钟线 超过三位小数的价格四舍五入
+    let csv = "\
+symbol,datetime,open,high,low,close,vol
+600000,2024-03-01 09:30:00,7.1000001,7.12,7.09,7.11,3500.0
+600000,2024-03-01 09:31:00,7.11,7.11,7.10,7.10,1200
+";
+    let data = MarketData::from_reader(csv.as_bytes(), None, "minute.csv").unwrap();
+    let bars = data.bars("600000");
+    assert_eq!(bars[0].open, price("7.10"));
+    assert_eq!(bars[0].volume, 3500);
+    assert!(!bars[1].is_daily());
+    assert_eq!(bars[1].timestamp.time(), (9 * 3600 + 31 * 60) * 1000);
+
+    // 阳线按 开-低-高-收 取价
+    let data = daily_data();
+    let bar = &data.bars("000001")[2];
+    assert_eq!(bar.price_at(0.0), price("10.00"));
+    assert_eq!(bar.price_at(1.0 / 3.0), price("9.95"));
+    assert_eq!(bar.price_at(0.5), price("10.275"));
+    assert_eq!(bar.price_at(1.0), price("10.50"));
+}
+
+#[test]
+fn test_parse_errors() {
+    let missing = "date,open,high,low,volume\n2024-03-01,1,1,1,1\n";
+    assert!(matches!(
+        MarketData::from_reader(missing.as_bytes(), Some("A"), "a.csv"),
+        Err(MarketDataError::MissingColumn {
+            column: "close",
+            ..
+        })
+    ));
+    assert!(matches!(
+        MarketData::from_reader(DAILY_CSV.as_bytes(), None, "data.csv"),
+        Err(MarketDataError::MissingCode { .. })
+    ));
+
+    let invalid =
+        "date,open,high,low,close,volume\n2024-03-01,1,1,1,1,1\n2024-03-04,10,9,8,9.5,1\n";
+    let err = MarketData::from_reader(invalid.as_bytes(), Some("A"), "a.csv").unwrap_err();
+    assert!(matches!(err, MarketDataError::InvalidRow { line: 3, .. }));
+    assert_eq!(
+        err.to_string(),
+        "a.csv line 3: open 10.000 and close 9.500 must be between low 8.000 and high 9.000"
+    );
+    let invalid = "date,open,high,low,close,volume\n03/01/2024,1,1,1,1,1\n";
+    assert!(matches!(
+        MarketData::from_reader(invalid.as_bytes(), Some("A"), "a.csv"),
+        Err(MarketDataError::InvalidRow { line: 2, .. })
+    ));
+}
+
+#[test]
+fn test_import_history() {
+    let mut exchange = TestConfig {
+        bots: &[],
+        ..config()
+    }
+    .build();
+    exchange.import_market_data(daily_data(), false).unwrap();
+
+    // 起始交易日之前的两根K线 收盘价作为昨收价并重新计算涨跌停价
+    let info = exchange.get_stock_info("000001").unwrap();
+    assert_eq!(info.start_price, price("10.00"));
+    assert_eq!(info.current_price, price("10.00"));
+    assert_eq!(info.price_limit.upper, price("11.00"));
+    assert_eq!(info.price_limit.lower, price("9.00"));
+    assert!(exchange.market_replay.is_none());
+
+    let bars = exchange.daily_bars();
+    assert_eq!(bars.len(), 2);
+    assert_eq!(bars[0].trade_day, date("2024-02-28"));
+    assert_eq!(
+        (bars[0].open, bars[0].high, bars[0].low, bars[0].close),
+        (price("9.60"), price("9.90"), price("9.50"), price("9.80"))
+    );
+    assert_eq!(bars[1].volume, 1_200_000);
+
+    // 每根K线一条价格历史 策略看到的K线与导入的相同
+    let history = exchange.get_price_history("000001").unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(
+        (history[0].open_price, history[0].price),
+        (price("9.60"), price("9.80"))
+    );
+    assert!(history[0].timestamp < history[1].timestamp);
+
+    // 不存在的股票以代码为名称添加
+    let csv = "code,date,open,high,low,close,volume\n600000,2024-02-29,7.00,7.20,6.90,7.10,100\n";
+    let data = MarketData::from_reader(csv.as_bytes(), None, "b.csv").unwrap();
+    exchange.import_market_data(data, false).unwrap();
+    let info = exchange.get_stock_info("600000").unwrap();
+    assert_eq!(info.name, "600000");
+    assert_eq!(info.start_price, price("7.10"));
+}
+
+#[test]
+fn test_replay_follows_history() {
+    let dir = std::env::temp_dir().join(format!("market_data_tests_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("000001.csv"), DAILY_CSV).unwrap();
+    let yaml = format!(
+        "{}market_data:\n  path: {}\n  replay: true\n",
+        config().yaml(),
+        dir.display()
+    );
+
+    let mut exchange = ConfigFile::from_yaml(&yaml)
+        .unwrap()
+        .build_exchange()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let agent_id = exchange.market_replay.as_ref().unwrap().agent_id;
+    let agent = exchange.user_manager.get_user(agent_id).unwrap();
+    assert_eq!(agent.username, REPLAY_AGENT_NAME);
+    assert_eq!(
+        exchange.get_stock_info("000001").unwrap().start_price,
+        price("10.00")
+    );
+
+    exchange.run_trading_days(2).unwrap();
+    let bars = exchange.daily_bars();
+    let replayed: Vec<_> = bars
+        .iter()
+        .filter(|bar| bar.trade_day >= date("2024-03-01"))
+        .collect();
+    assert_eq!(replayed.len(), 2);
+    // 收盘价及最高最低价与历史数据相差不超过1%
+    let close_to = |actual: Price, expected: &str| {
+        let expected = price(expected);
+        (actual.as_f64() - expected.as_f64()).abs() / expected.as_f64() < 0.01
+    };
+    for (bar, (high, low, close)) in replayed
+        .iter()
+        .zip([("10.60", "9.95", "10.50"), ("10.55", "10.00", "10.10")])
+    {
+        assert!(close_to(bar.close, close), "{:?}", bar);
+        assert!(close_to(bar.high, high), "{:?}", bar);
+        assert!(close_to(bar.low, low), "{:?}", bar);
+    }
+}
+
+#[test]
+fn test_replay_is_journaled() {
+    let path =
+        std::env::temp_dir().join(format!("market_data_journal_{}.jsonl", std::process::id()));
+    let mut exchange = config().build();
+    exchange.enable_journal();
+    let mut writer = JournalWriter::create(&path, &exchange).unwrap();
+    exchange.import_market_data(daily_data(), true).unwrap();
+    let clock = SimulationClock::new();
+    for _ in 0..2500 {
+        exchange.tick(&clock).unwrap();
+    }
+    writer.write(&exchange.drain_journal()).unwrap();
+    drop(writer);
+
+    // 回放代理的下单由时钟推进产生 重放结果相同
+    let restored = journal::replay(&path).unwrap().exchange;
+    std::fs::remove_file(&path).unwrap();
+    assert!(restored.market_replay.is_some());
+    assert!(!exchange.log_manager.is_empty());
+    assert_eq!(
+        serde_json::to_string(&restored.log_manager.get_all_logs()).unwrap(),
+        serde_json::to_string(&exchange.log_manager.get_all_logs()).unwrap()
+    );
+}
+
+#[test]
+fn test_holdings_of_imported_stocks() {
+    let dir = std::env::temp_dir().join(format!("market_data_holdings_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("600000.csv"), DAILY_CSV).unwrap();
+    let yaml = |holdings: &str| {
+        let yaml = TestConfig {
+            bots: &[("robot1", "{ type: simple_random }")],
+            ..config()
+        }
+        .yaml()
+        .replace(r#""000001": 20000"#, holdings);
+        format!("{}market_data:\n  path: {}\n", yaml, dir.display())
+    };
+
+    // 持仓的股票仅在历史行情中出现 导入后再检查
+    let config = ConfigFile::from_yaml(&yaml(r#""600000": 1000, "600001": 1000"#)).unwrap();
+    let Err(err) = config.build_exchange() else {
+        panic!("unknown stock should be rejected");
+    };
+    assert!(matches!(err, ConfigError::Invalid(ref errors)
+        if errors == &["bots[0] (robot1): holding of unknown stock 600001"]));
+
+    let exchange = ConfigFile::from_yaml(&yaml(r#""600000": 1000"#))
+        .unwrap()
+        .build_exchange()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let robot_id = exchange.user_manager.find_user_id("robot1").unwrap();
+    let robot = exchange.user_manager.get_user(robot_id).unwrap();
+    assert_eq!(robot.holdings["600000"].quantity, 1000);
+}
+
+#[test]
+fn test_replay_requires_start_date() {
+    let yaml = "market_data:\n  path: data\n  replay: true\n";
+    let err = ConfigFile::from_yaml(yaml).unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(_)));
+    assert!(err
+        .to_string()
+        .contains("market_data.replay requires exchange.start_date"));
+}
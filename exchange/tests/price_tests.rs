@@ -0,0 +1,63 @@
+use exchange::price::ParseAmountError;
+use exchange::types::{Money, Price, PRICE_SCALE};
+
+#[test]
+fn test_price_parse_and_format() {
+    assert_eq!(PRICE_SCALE, 1000);
+    assert_eq!("15.23".parse::<Price>().unwrap(), Price::from_raw(15230));
+    assert_eq!("15".parse::<Price>().unwrap(), Price::from_yuan(15));
+    assert_eq!(Price::from_fen(1), Price::from_raw(10));
+    assert_eq!(Price::from_raw(15230).to_string(), "15.230");
+    assert_eq!(Price::from_raw(5).to_string(), "0.005");
+
+    assert!(matches!(
+        "1.2345".parse::<Price>(),
+        Err(ParseAmountError::TooPrecise(_))
+    ));
+    assert!(matches!(
+        "-1".parse::<Price>(),
+        Err(ParseAmountError::OutOfRange(_))
+    ));
+    assert!(matches!(
+        "abc".parse::<Price>(),
+        Err(ParseAmountError::Invalid(_))
+    ));
+
+    assert_eq!(
+        "-1200.5".parse::<Money>().unwrap(),
+        Money::from_raw(-1_200_500)
+    );
+    assert_eq!(Money::from_raw(-1_200_500).to_string(), "-1200.500");
+}
+
+#[test]
+fn test_price_arithmetic() {
+    let price: Price = "10.01".parse().unwrap();
+    assert_eq!(price * 300u32, Money::from_raw(3_003_000));
+    assert_eq!(Price::from_raw(u32::MAX).checked_add(price), None);
+    assert_eq!(Price::ZERO.checked_sub(price), None);
+    assert_eq!(
+        Price::from_raw(10_016).round_to(Price::from_fen(1)),
+        price.checked_add(Price::from_fen(1)).unwrap()
+    );
+    assert_eq!(price.diff(Price::from_yuan(11)), Money::from_raw(-990));
+
+    let amount = Money::from_yuan(100);
+    assert_eq!(amount.quantity_at(price), 9);
+    assert_eq!(amount.mul_rate(0.0003), Money::from_raw(30));
+    assert_eq!(amount.mul_div(1, 3), Money::from_raw(33_333));
+    assert_eq!(amount.per_unit(3), Price::from_raw(33_333));
+    assert_eq!(Money::from_yuan(1).saturating_sub(amount), Money::ZERO);
+}
+
+#[test]
+fn test_price_serde() {
+    let price = Price::from_raw(15230);
+    assert_eq!(serde_yaml::to_string(&price).unwrap().trim(), "'15.230'");
+    assert_eq!(serde_yaml::from_str::<Price>("15.23").unwrap(), price);
+    assert_eq!(serde_yaml::from_str::<Price>("'15.230'").unwrap(), price);
+    assert_eq!(
+        serde_yaml::from_str::<Money>("-3").unwrap(),
+        Money::from_yuan(-3)
+    );
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::TestConfig;
+
+fn date(value: &str) -> NaiveDate {
+    value.parse().unwrap()
+}
+
+/** 以指定种子模拟一个交易日 返回成交记录及日K线 */
+fn simulate_with_seed(seed: u64) -> (String, String) {
+    let mut exchange = TestConfig {
+        seed: Some(seed),
+        ..Default::default()
+    }
+    .build();
+    exchange.run_trading_days(1).unwrap();
+    (
+        serde_json::to_string(&exchange.log_manager.get_all_logs()).unwrap(),
+        serde_json::to_string(&exchange.daily_bars()).unwrap(),
+    )
+}
+
+#[test]
+fn test_run_trading_days() {
+    let mut exchange = TestConfig::default().build();
+
+    // 2024-03-01 是周五 下一交易日为周一
+    let summary = exchange.run_trading_days(2).unwrap();
+    assert_eq!(summary.trading_days, 2);
+    assert_eq!(summary.start_day, date("2024-03-01"));
+    assert_eq!(summary.end_day, date("2024-03-04"));
+    assert_eq!(exchange.current_trade_day(), date("2024-03-05"));
+    assert_eq!(summary.trades, exchange.log_manager.get_all_logs().len());
+
+    let bars = exchange.daily_bars();
+    let days: Vec<_> = bars
+        .iter()
+        .map(|bar| (bar.stock_code.as_str(), bar.trade_day))
+        .collect();
+    assert_eq!(
+        days,
+        vec![
+            ("000001", date("2024-03-01")),
+            ("000001", date("2024-03-04")),
+            ("000002", date("2024-03-01")),
+            ("000002", date("2024-03-04")),
+        ]
+    );
+    for bar in &bars {
+        assert!(bar.low <= bar.open && bar.open <= bar.high);
+        assert!(bar.low <= bar.close && bar.close <= bar.high);
+    }
+
+    // 每个交易日收盘记录盈亏快照 收盘价作为下一交易日的昨收价
+    for ranking in exchange.get_pnl_ranking() {
+        let history = exchange.get_pnl_history(ranking.user_id).unwrap();
+        assert_eq!(history.len(), 2);
+    }
+    let stock = exchange
+        .stock_manager
+        .get_stock(&"000001".to_string())
+        .unwrap();
+    assert_eq!(stock.start_price, bars[1].close);
+}
+
+#[test]
+fn test_same_seed_reproduces_trades() {
+    let (trades, bars) = simulate_with_seed(42);
+    assert!(trades.len() > 2);
+    assert_eq!(simulate_with_seed(42), (trades.clone(), bars));
+    assert_ne!(simulate_with_seed(43).0, trades);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::{state_of, TestConfig};
+use exchange::clock::SimulationClock;
+use exchange::exchange::Exchange;
+use exchange::snapshot::{self, SnapshotError, SNAPSHOT_VERSION};
+use std::collections::BTreeMap;
+
+fn run(exchange: &mut Exchange, clock: &SimulationClock, steps: u32) {
+    for _ in 0..steps {
+        exchange.tick(clock).unwrap();
+    }
+}
+
+#[test]
+fn test_restored_exchange_continues_identically() {
+    let mut exchange = TestConfig {
+        seed: Some(7),
+        ..Default::default()
+    }
+    .build();
+    let mut clock = SimulationClock::new();
+    clock.set_speed(50).unwrap();
+    run(&mut exchange, &clock, 600);
+    assert!(!exchange.log_manager.get_all_logs().is_empty());
+
+    let mut extensions = BTreeMap::new();
+    extensions.insert("note".to_string(), serde_json::json!({ "saved": true }));
+    let bytes = snapshot::to_bytes(&exchange, &clock, &extensions).unwrap();
+    let restored = snapshot::from_slice(&bytes).unwrap();
+    assert_eq!(restored.version, SNAPSHOT_VERSION);
+    assert_eq!(restored.clock.speed(), 50);
+    assert_eq!(restored.extensions, extensions);
+
+    let mut restored_exchange = restored.exchange;
+    assert_eq!(
+        restored_exchange.get_current_timestamp(),
+        exchange.get_current_timestamp()
+    );
+    assert_eq!(restored_exchange.config.seed(), 7);
+    assert_eq!(state_of(&restored_exchange), state_of(&exchange));
+
+    // 挂单、机器人随机数及收市后的换日都应与原交易所一致
+    run(&mut exchange, &clock, 1500);
+    run(&mut restored_exchange, &restored.clock, 1500);
+    assert_eq!(
+        restored_exchange.get_current_timestamp(),
+        exchange.get_current_timestamp()
+    );
+    assert_eq!(state_of(&restored_exchange), state_of(&exchange));
+}
+
+#[test]
+fn test_snapshot_file_round_trip() {
+    let exchange = TestConfig {
+        seed: Some(7),
+        ..Default::default()
+    }
+    .build();
+    let path = std::env::temp_dir()
+        .join(format!("exchange-snapshot-{}", std::process::id()))
+        .join("snapshot.json");
+    snapshot::save(&path, &exchange, &SimulationClock::new(), &BTreeMap::new()).unwrap();
+    let restored = snapshot::load(&path).unwrap();
+    assert_eq!(restored.exchange.get_stock_list().len(), 2);
+    assert_eq!(restored.exchange.user_manager.get_user_ids().len(), 2);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+    // 版本不符的快照拒绝加载
+    let mut value: serde_json::Value = serde_json::from_slice(
+        &snapshot::to_bytes(&exchange, &SimulationClock::new(), &BTreeMap::new()).unwrap(),
+    )
+    .unwrap();
+    value["version"] = serde_json::json!(SNAPSHOT_VERSION + 1);
+    let result = snapshot::from_slice(&serde_json::to_vec(&value).unwrap());
+    assert!(matches!(
+        result,
+        Err(SnapshotError::UnsupportedVersion(version)) if version == SNAPSHOT_VERSION + 1
+    ));
+}
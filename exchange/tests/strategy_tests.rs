@@ -0,0 +1,507 @@
+mod common;
+
+use common::{money, price};
+use exchange::config::ExchangeConfig;
+use exchange::config_file::{ConfigError, ConfigFile};
+use exchange::exchange::Exchange;
+use exchange::order::OrderType;
+use exchange::trading_strategy::{
+    ActionOutcome, MarketContext, MarketMakerParams, MeanReversionParams, ReversionSignal,
+    TradingAction, TradingStrategy, TrendFollowingParams, TrendSignal,
+};
+use exchange::types::{string_to_time_of_day, Price, UserId};
+
+fn market_maker(target_position: u64, max_position: u64) -> TradingStrategy {
+    TradingStrategy::MarketMaker(MarketMakerParams {
+        spread_ticks: 4,
+        quote_size: 1000,
+        target_position,
+        max_position,
+        skew_ticks: 4,
+        requote_ticks: 1,
+    })
+}
+
+/** 股票A 10元 做市商持有 holding 股 */
+fn build_exchange(strategy: TradingStrategy, holding: u64) -> (Exchange, UserId) {
+    let mut exchange = Exchange::new(ExchangeConfig::new());
+    exchange.add_stock("000001", "股票A", price("10")).unwrap();
+    let holdings = if holding > 0 {
+        vec![("000001", holding)]
+    } else {
+        Vec::new()
+    };
+    let bot_id = exchange
+        .add_robot("maker", money("1000000"), strategy, holdings)
+        .unwrap();
+    exchange.next_timestamp("09:30:00");
+    (exchange, bot_id)
+}
+
+/** 未完成的委托 (方向, 价格, 数量) */
+fn open_quotes(exchange: &Exchange, user_id: UserId) -> Vec<(OrderType, Price, u32)> {
+    exchange
+        .order_manager
+        .get_user_orders(user_id)
+        .into_iter()
+        .filter(|order| order.remaining_quantity > 0)
+        .map(|order| (order.order_type, order.price, order.remaining_quantity))
+        .collect()
+}
+
+#[test]
+fn test_market_maker_quotes_both_sides() {
+    let (mut exchange, bot_id) = build_exchange(market_maker(20000, 40000), 20000);
+    exchange.execute_robot_strategies().unwrap();
+    // 持仓等于目标 以最新价为中心报价
+    assert_eq!(
+        open_quotes(&exchange, bot_id),
+        vec![
+            (OrderType::Buy, price("9.98"), 1000),
+            (OrderType::Sell, price("10.02"), 1000),
+        ]
+    );
+
+    // 市场不变时保留挂单
+    exchange.execute_robot_strategies().unwrap();
+    assert_eq!(exchange.order_manager.get_user_orders(bot_id).len(), 2);
+}
+
+#[test]
+fn test_market_maker_requotes_when_market_moves() {
+    let (mut exchange, bot_id) = build_exchange(market_maker(20000, 40000), 20000);
+    exchange.execute_robot_strategies().unwrap();
+
+    // 其他参与者的盘口中间价移动到 10.05
+    let user_id = exchange.add_user("user1", money("1000000")).unwrap();
+    exchange
+        .user_manager
+        .get_user_mut(user_id)
+        .unwrap()
+        .add_holding("000001".to_string(), 100, money("1000"));
+    for (order_type, value) in [(OrderType::Buy, "10.00"), (OrderType::Sell, "10.10")] {
+        exchange
+            .submit_order(user_id, "000001".to_string(), order_type, price(value), 100)
+            .unwrap();
+    }
+
+    exchange.execute_robot_strategies().unwrap();
+    assert_eq!(
+        open_quotes(&exchange, bot_id),
+        vec![
+            (OrderType::Buy, price("10.03"), 1000),
+            (OrderType::Sell, price("10.07"), 1000),
+        ]
+    );
+    // 原挂单已撤销
+    assert_eq!(exchange.order_manager.get_user_orders(bot_id).len(), 4);
+}
+
+#[test]
+fn test_market_maker_skews_by_inventory() {
+    // 持仓达到上限 报价下移且只报卖价
+    let (mut exchange, bot_id) = build_exchange(market_maker(20000, 40000), 40000);
+    exchange.execute_robot_strategies().unwrap();
+    assert_eq!(
+        open_quotes(&exchange, bot_id),
+        vec![(OrderType::Sell, price("9.98"), 1000)]
+    );
+
+    // 没有持仓 报价上移且只报买价
+    let (mut exchange, bot_id) = build_exchange(market_maker(20000, 40000), 0);
+    exchange.execute_robot_strategies().unwrap();
+    assert_eq!(
+        open_quotes(&exchange, bot_id),
+        vec![(OrderType::Buy, price("10.02"), 1000)]
+    );
+}
+
+#[test]
+fn test_action_results_reported_to_bot() {
+    let (mut exchange, bot_id) = build_exchange(market_maker(20000, 40000), 20000);
+    exchange.execute_robot_strategies().unwrap();
+    let results = exchange.bot_manager.get_bot(bot_id).unwrap().last_results();
+    let order_ids: Vec<_> = exchange
+        .order_manager
+        .get_user_orders(bot_id)
+        .iter()
+        .map(|order| order.id)
+        .collect();
+    assert_eq!(results.len(), 2);
+    assert!(matches!(results[0].action, TradingAction::Buy { .. }));
+    assert_eq!(
+        results[0].outcome,
+        ActionOutcome::Submitted {
+            order_id: order_ids[0]
+        }
+    );
+    assert_eq!(
+        results[1].outcome,
+        ActionOutcome::Submitted {
+            order_id: order_ids[1]
+        }
+    );
+
+    // 市场不变时没有操作 结果清空
+    exchange.execute_robot_strategies().unwrap();
+    let bot = exchange.bot_manager.get_bot(bot_id).unwrap();
+    assert!(bot.last_results().is_empty());
+
+    // 午间休市不能下单 拒绝原因返回给机器人
+    let (mut exchange, bot_id) = build_exchange(market_maker(20000, 40000), 20000);
+    exchange.next_timestamp("12:00:00");
+    exchange.execute_robot_strategies().unwrap();
+    let results = exchange.bot_manager.get_bot(bot_id).unwrap().last_results();
+    assert_eq!(results.len(), 2);
+    for result in results {
+        match &result.outcome {
+            ActionOutcome::Rejected { reason } => assert!(reason.contains("order"), "{}", reason),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+    }
+}
+
+#[test]
+fn test_market_context() {
+    let mut exchange = Exchange::new(ExchangeConfig::new());
+    exchange.add_stock("000001", "股票A", price("10")).unwrap();
+    exchange.next_timestamp("09:30:00");
+    let buyer_id = exchange.add_user("buyer", money("1000000")).unwrap();
+    let seller_id = exchange.add_user("seller", money("1000000")).unwrap();
+    exchange
+        .user_manager
+        .get_user_mut(seller_id)
+        .unwrap()
+        .add_holding("000001".to_string(), 200, money("2000"));
+    let buy_id = exchange
+        .submit_order(
+            buyer_id,
+            "000001".to_string(),
+            OrderType::Buy,
+            price("10"),
+            200,
+        )
+        .unwrap();
+    for value in ["10", "10.10"] {
+        exchange
+            .submit_order(
+                seller_id,
+                "000001".to_string(),
+                OrderType::Sell,
+                price(value),
+                100,
+            )
+            .unwrap();
+    }
+    exchange.next_timestamp("09:30:01");
+
+    let buyer = exchange.user_manager.get_user(buyer_id).unwrap();
+    let context = MarketContext::new(&exchange, buyer, &[], 0);
+    // 盘口不含已成交的数量
+    let quote = context.quote("000001").unwrap();
+    assert_eq!(quote.bids, vec![(price("10"), 100)]);
+    assert_eq!(quote.asks, vec![(price("10.10"), 100)]);
+    assert_eq!(quote.best_bid(), Some(price("10")));
+    assert_eq!(quote.best_ask(), Some(price("10.10")));
+    assert_eq!(context.open_orders().len(), 1);
+    assert_eq!(context.open_orders()[0].remaining_quantity, 100);
+    assert!(context.quote("999999").is_none());
+
+    // 只提供上一次决策之后的成交
+    let fills = context.fills();
+    assert_eq!(fills.len(), 1);
+    assert_eq!(
+        (
+            fills[0].order_id,
+            fills[0].order_type,
+            fills[0].price,
+            fills[0].quantity
+        ),
+        (buy_id, OrderType::Buy, price("10"), 100)
+    );
+    let context = MarketContext::new(&exchange, buyer, &[], fills[0].trade_id);
+    assert!(context.fills().is_empty());
+}
+
+/** 09:30 起每分钟一根K线 每根成交100股 时钟推进到最后一根K线之后 */
+fn add_bars(exchange: &mut Exchange, closes: &[&str]) {
+    let now = exchange.get_current_timestamp();
+    let stock = exchange
+        .stock_manager
+        .get_stock_mut(&"000001".to_string())
+        .unwrap();
+    for (minute, close) in closes.iter().enumerate() {
+        let time = string_to_time_of_day(&format!("09:{}:00", 30 + minute)).unwrap();
+        stock.add_price_to_history(now.with_time(time), price(close), 100);
+    }
+    exchange.next_timestamp(&format!("09:{}:00", 30 + closes.len()));
+}
+
+/** 其他参与者在 bid 买入 在 ask 卖出 */
+fn add_liquidity(exchange: &mut Exchange, bid: &str, ask: &str) {
+    let user_id = exchange.add_user("user1", money("1000000")).unwrap();
+    exchange
+        .user_manager
+        .get_user_mut(user_id)
+        .unwrap()
+        .add_holding("000001".to_string(), 10000, money("100000"));
+    for (order_type, value) in [(OrderType::Buy, bid), (OrderType::Sell, ask)] {
+        exchange
+            .submit_order(
+                user_id,
+                "000001".to_string(),
+                order_type,
+                price(value),
+                1000,
+            )
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_trend_following_crossover() {
+    let strategy = TradingStrategy::TrendFollowing(TrendFollowingParams {
+        signal: TrendSignal::Crossover,
+        lookback: 4,
+        fast_lookback: 2,
+        threshold: 0.001,
+        order_size: 500,
+        max_position: 800,
+    });
+    let (mut exchange, bot_id) = build_exchange(strategy, 0);
+    add_bars(
+        &mut exchange,
+        &["10.00", "10.00", "10.00", "10.10", "10.20"],
+    );
+    add_liquidity(&mut exchange, "10.15", "10.25");
+
+    // 短期均线上穿 以卖一价买入
+    exchange.execute_robot_strategies().unwrap();
+    assert_eq!(
+        open_quotes(&exchange, bot_id),
+        vec![(OrderType::Buy, price("10.25"), 500)]
+    );
+
+    // 同一根K线只决策一次
+    exchange.execute_robot_strategies().unwrap();
+    let bot = exchange.bot_manager.get_bot(bot_id).unwrap();
+    assert!(bot.last_results().is_empty());
+    assert_eq!(exchange.order_manager.get_user_orders(bot_id).len(), 1);
+
+    // 成交后下一根K线继续买入 不超过最大持仓
+    exchange.next_timestamp("09:36:00");
+    exchange.execute_robot_strategies().unwrap();
+    assert_eq!(
+        open_quotes(&exchange, bot_id),
+        vec![(OrderType::Buy, price("10.25"), 300)]
+    );
+}
+
+#[test]
+fn test_trend_following_breakout() {
+    let strategy = TradingStrategy::TrendFollowing(TrendFollowingParams {
+        signal: TrendSignal::Breakout,
+        lookback: 3,
+        fast_lookback: 1,
+        threshold: 0.0,
+        order_size: 500,
+        max_position: 10000,
+    });
+    let (mut exchange, bot_id) = build_exchange(strategy, 2000);
+    add_bars(&mut exchange, &["10.00", "10.05", "9.95", "9.90"]);
+    add_liquidity(&mut exchange, "9.85", "9.95");
+
+    // 跌破此前3根K线的最低价 以买一价卖出
+    exchange.execute_robot_strategies().unwrap();
+    assert_eq!(
+        open_quotes(&exchange, bot_id),
+        vec![(OrderType::Sell, price("9.85"), 500)]
+    );
+}
+
+#[test]
+fn test_mean_reversion() {
+    let strategy = |signal, threshold| {
+        TradingStrategy::MeanReversion(MeanReversionParams {
+            signal,
+            lookback: 5,
+            threshold,
+            order_size: 500,
+            max_position: 10000,
+        })
+    };
+
+    // 低于布林带下轨 买入
+    let (mut exchange, bot_id) = build_exchange(strategy(ReversionSignal::Bollinger, 1.5), 0);
+    add_bars(&mut exchange, &["10.00", "10.02", "9.98", "10.00", "9.70"]);
+    add_liquidity(&mut exchange, "9.70", "9.75");
+    exchange.execute_robot_strategies().unwrap();
+    assert_eq!(
+        open_quotes(&exchange, bot_id),
+        vec![(OrderType::Buy, price("9.75"), 500)]
+    );
+
+    // 在布林带内 不操作
+    let (mut exchange, bot_id) = build_exchange(strategy(ReversionSignal::Bollinger, 2.5), 0);
+    add_bars(&mut exchange, &["10.00", "10.02", "9.98", "10.00", "9.70"]);
+    exchange.execute_robot_strategies().unwrap();
+    assert!(open_quotes(&exchange, bot_id).is_empty());
+
+    // 高于 VWAP 超过1% 卖出
+    let (mut exchange, bot_id) = build_exchange(strategy(ReversionSignal::Vwap, 0.01), 2000);
+    add_bars(
+        &mut exchange,
+        &["10.00", "10.00", "10.00", "10.00", "10.30"],
+    );
+    add_liquidity(&mut exchange, "10.25", "10.30");
+    exchange.execute_robot_strategies().unwrap();
+    assert_eq!(
+        open_quotes(&exchange, bot_id),
+        vec![(OrderType::Sell, price("10.25"), 500)]
+    );
+}
+
+#[test]
+fn test_signal_strategy_config() {
+    let yaml = r#"
+stocks:
+  - { code: "000001", name: 股票A, price: "10.00" }
+bots:
+  - name: trend
+    balance: "1000000"
+    strategy: { type: trend_following, signal: crossover, lookback: 20, order_size: 500, max_position: 5000 }
+  - name: reversion
+    balance: "1000000"
+    strategy: { type: mean_reversion, signal: bollinger, lookback: 20, order_size: 500, max_position: 5000 }
+"#;
+    let config = ConfigFile::from_yaml(yaml).unwrap();
+    assert_eq!(
+        TradingStrategy::from(&config.bots[0].strategy),
+        TradingStrategy::TrendFollowing(TrendFollowingParams {
+            signal: TrendSignal::Crossover,
+            lookback: 20,
+            fast_lookback: 5,
+            threshold: 0.0,
+            order_size: 500,
+            max_position: 5000,
+        })
+    );
+    assert_eq!(
+        TradingStrategy::from(&config.bots[1].strategy),
+        TradingStrategy::MeanReversion(MeanReversionParams {
+            signal: ReversionSignal::Bollinger,
+            lookback: 20,
+            threshold: 2.0,
+            order_size: 500,
+            max_position: 5000,
+        })
+    );
+
+    let invalid = yaml
+        .replace(
+            "signal: crossover, lookback: 20",
+            "signal: crossover, lookback: 20, fast_lookback: 20",
+        )
+        .replace(
+            "signal: bollinger, lookback: 20",
+            "signal: bollinger, lookback: 1",
+        );
+    let err = ConfigFile::from_yaml(&invalid).unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(ref errors) if errors.len() == 2));
+    assert!(err
+        .to_string()
+        .contains("fast_lookback 20 must be between 1 and lookback 20"));
+}
+
+#[test]
+fn test_market_maker_config() {
+    let yaml = r#"
+stocks:
+  - { code: "000001", name: 股票A, price: "10.00" }
+bots:
+  - name: maker
+    balance: "1000000"
+    strategy: { type: market_maker, spread_ticks: 2, quote_size: 500, target_position: 10000, max_position: 20000 }
+    holdings: { "000001": 10000 }
+"#;
+    let config = ConfigFile::from_yaml(yaml).unwrap();
+    assert_eq!(
+        TradingStrategy::from(&config.bots[0].strategy),
+        TradingStrategy::MarketMaker(MarketMakerParams {
+            spread_ticks: 2,
+            quote_size: 500,
+            target_position: 10000,
+            max_position: 20000,
+            skew_ticks: 2,
+            requote_ticks: 1,
+        })
+    );
+
+    let invalid = yaml
+        .replace("spread_ticks: 2", "spread_ticks: 0")
+        .replace("max_position: 20000", "max_position: 5000");
+    let err = ConfigFile::from_yaml(&invalid).unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid(ref errors) if errors.len() == 2));
+    assert!(err
+        .to_string()
+        .contains("target_position 10000 exceeds max_position 5000"));
+}
+
+#[test]
+fn test_market_maker_in_simulation() {
+    let yaml = r#"
+exchange:
+  start_date: 2024-03-01
+  seed: 3
+  trading_periods:
+    - name: 连续竞价交易
+      start_tick: "09:30:00"
+      end_tick: "09:59:59"
+      period_type: ContinuousTrading
+      allow_order: true
+      allow_cancel: true
+      allow_matching: true
+      allow_record_price_history: true
+stocks:
+  - { code: "000001", name: 股票A, price: "10.00" }
+bots:
+  - name: maker
+    balance: "1000000"
+    strategy: { type: market_maker, spread_ticks: 4, quote_size: 500, target_position: 20000, max_position: 30000 }
+    holdings: { "000001": 20000 }
+  - name: robot1
+    balance: "1000000"
+    strategy: { type: trade_random, param: 5 }
+    holdings: { "000001": 10000 }
+  - name: robot2
+    balance: "1000000"
+    strategy: { type: simple_random }
+    holdings: { "000001": 10000 }
+  - name: trend
+    balance: "1000000"
+    strategy: { type: trend_following, signal: breakout, lookback: 5, order_size: 200, max_position: 5000 }
+    holdings: { "000001": 2000 }
+  - name: reversion
+    balance: "1000000"
+    strategy: { type: mean_reversion, signal: vwap, lookback: 10, threshold: 0.001, order_size: 200, max_position: 5000 }
+    holdings: { "000001": 2000 }
+"#;
+    let mut exchange = ConfigFile::from_yaml(yaml)
+        .unwrap()
+        .build_exchange()
+        .unwrap();
+    exchange.run_trading_days(2).unwrap();
+
+    // 做市商参与成交 持仓不超过上限
+    let maker_id = 1;
+    let maker = exchange.user_manager.get_user(maker_id).unwrap();
+    assert_eq!(maker.username, "maker");
+    let position = maker.holdings["000001"].quantity;
+    assert!(position <= 30000);
+    assert!(!exchange.log_manager.get_logs_by_user(maker_id).is_empty());
+
+    // 趋势跟踪及均值回归机器人按K线信号成交
+    for user_id in [4, 5] {
+        assert!(!exchange.log_manager.get_logs_by_user(user_id).is_empty());
+    }
+}
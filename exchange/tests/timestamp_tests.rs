@@ -0,0 +1,56 @@
+use chrono::NaiveDate;
+use exchange::timestamp::{ParseTimestampError, MILLIS_PER_SECOND};
+use exchange::types::{string_to_time_of_day, time_of_day_to_string, Timestamp};
+
+#[test]
+fn test_time_of_day_parse_and_format() {
+    assert_eq!(string_to_time_of_day("9:15:00").unwrap(), 33_300_000);
+    assert_eq!(string_to_time_of_day("09:30").unwrap(), 34_200_000);
+    assert_eq!(string_to_time_of_day("09:30:00.25").unwrap(), 34_200_250);
+    assert_eq!(time_of_day_to_string(34_200_000), "09:30:00");
+    assert_eq!(time_of_day_to_string(34_200_250), "09:30:00.250");
+
+    for invalid in ["24:00:00", "09:60:00", "09:30:00.1234", "0930", "09:30:xx"] {
+        assert!(matches!(
+            string_to_time_of_day(invalid),
+            Err(ParseTimestampError::InvalidTime(_))
+        ));
+    }
+}
+
+#[test]
+fn test_timestamp_ordering_and_format() {
+    let day = NaiveDate::from_ymd_opt(2023, 1, 3).unwrap();
+    let next_day = NaiveDate::from_ymd_opt(2023, 1, 4).unwrap();
+    let late = Timestamp::new(day, string_to_time_of_day("14:59:59.999").unwrap());
+    let early = Timestamp::new(next_day, string_to_time_of_day("09:30:00").unwrap());
+    assert!(late < early);
+    assert_eq!(late.to_string(), "2023-01-03T14:59:59.999");
+    assert_eq!(
+        late.floor_to_minute().to_string(),
+        "2023-01-03T14:59:00.000"
+    );
+
+    assert_eq!(
+        "2023-01-03T14:59:59.999".parse::<Timestamp>().unwrap(),
+        late
+    );
+    assert_eq!("2023-01-04 09:30:00".parse::<Timestamp>().unwrap(), early);
+    assert!(matches!(
+        "09:30:00".parse::<Timestamp>(),
+        Err(ParseTimestampError::InvalidDate(_))
+    ));
+
+    let datetime = late.to_datetime();
+    assert_eq!(Timestamp::from_datetime(datetime), late);
+    assert_eq!(late.time() % MILLIS_PER_SECOND, 999);
+}
+
+#[test]
+fn test_timestamp_serde() {
+    let day = NaiveDate::from_ymd_opt(2023, 1, 3).unwrap();
+    let timestamp = Timestamp::new(day, 34_200_125);
+    let yaml = serde_yaml::to_string(&timestamp).unwrap();
+    assert_eq!(yaml.trim(), "2023-01-03T09:30:00.125");
+    assert_eq!(serde_yaml::from_str::<Timestamp>(&yaml).unwrap(), timestamp);
+}
@@ -0,0 +1,219 @@
+mod common;
+
+use chrono::{Datelike, Weekday};
+use common::money;
+use exchange::config::{ExchangeConfig, SettlementCycle, TradingPeriodType};
+use exchange::exchange::Exchange;
+use exchange::order::OrderType;
+use exchange::types::{string_to_time_of_day, Money, Price};
+use exchange::venue::Venue;
+
+#[test]
+fn test_hkex_random_closing() {
+    let mut exchange = Exchange::new(ExchangeConfig::for_venue(Venue::Hkex));
+    let _ = exchange.add_stock("00700", "腾讯控股", Price::from_yuan(300));
+    let user_id = exchange.add_user("user1", money("1000000")).unwrap();
+
+    exchange.next_timestamp("09:25:00");
+    assert_eq!(
+        exchange.config.get_current_period().unwrap().period_type,
+        TradingPeriodType::Blocking
+    );
+    assert!(!exchange.config.is_allow_order());
+
+    // 收市竞价输入买卖盘时段可以撤单
+    exchange.next_timestamp("16:01:00");
+    let order_id = exchange
+        .submit_order(
+            user_id,
+            "00700".to_string(),
+            OrderType::Buy,
+            Price::from_yuan(350),
+            100,
+        )
+        .unwrap();
+    assert!(exchange.cancel_order(order_id).is_ok());
+
+    exchange.next_timestamp("16:08:00");
+    let close_tick = exchange.config.get_random_close_tick().unwrap();
+    assert!(close_tick >= string_to_time_of_day("16:08:00").unwrap());
+    assert!(close_tick <= string_to_time_of_day("16:10:00").unwrap());
+
+    exchange.next_timestamp("16:10:00");
+    assert_eq!(
+        exchange.config.is_randomly_closed(),
+        close_tick < string_to_time_of_day("16:10:00").unwrap()
+    );
+    assert_eq!(exchange.config.settlement_cycle, SettlementCycle::T0);
+    assert_eq!(exchange.config.price_limit_percentage, None);
+
+    // 相同种子的随机收市时间相同
+    let close_tick_with_seed = |seed| {
+        let mut config = ExchangeConfig::for_venue(Venue::Hkex);
+        config.set_seed(seed);
+        let mut exchange = Exchange::new(config);
+        exchange.next_timestamp("16:08:00");
+        exchange.config.get_random_close_tick().unwrap()
+    };
+    assert_eq!(close_tick_with_seed(7), close_tick_with_seed(7));
+}
+
+#[test]
+fn test_hkex_closing_auction_uncrosses_at_single_price() {
+    let mut exchange = Exchange::new(ExchangeConfig::for_venue(Venue::Hkex));
+    let _ = exchange.add_stock("00700", "腾讯控股", Price::from_yuan(300));
+    let buyer_id = exchange.add_user("buyer", money("1000000")).unwrap();
+    let seller_ids: Vec<_> = ["seller1", "seller2"]
+        .iter()
+        .map(|name| {
+            let user_id = exchange.add_user(name, Money::ZERO).unwrap();
+            let seller = exchange.user_manager.get_user_mut(user_id).unwrap();
+            seller.add_holding("00700".to_string(), 100, money("30000"));
+            user_id
+        })
+        .collect();
+
+    // 收市竞价期间买卖盘交叉 不连续撮合
+    exchange.next_timestamp("16:01:00");
+    exchange
+        .submit_order(
+            buyer_id,
+            "00700".to_string(),
+            OrderType::Buy,
+            Price::from_yuan(305),
+            200,
+        )
+        .unwrap();
+    for (seller_id, price) in seller_ids.iter().zip([298, 299]) {
+        exchange
+            .submit_order(
+                *seller_id,
+                "00700".to_string(),
+                OrderType::Sell,
+                Price::from_yuan(price),
+                100,
+            )
+            .unwrap();
+    }
+    exchange.next_timestamp("16:07:00");
+    assert!(exchange.get_trade_logs("00700", 1, 10).0.is_empty());
+
+    // 随机收市后以同一价格集中成交
+    exchange.next_timestamp("16:08:00");
+    exchange.next_timestamp("16:10:01");
+    let (logs, _) = exchange.get_trade_logs("00700", 1, 10);
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs.iter().map(|log| log.quantity).sum::<u32>(), 200);
+    let closing_price = logs[0].price;
+    assert!(logs.iter().all(|log| log.price == closing_price));
+    assert!(closing_price >= Price::from_yuan(299) && closing_price <= Price::from_yuan(305));
+    let stock = exchange
+        .stock_manager
+        .get_stock(&"00700".to_string())
+        .unwrap();
+    assert_eq!(stock.current_price, closing_price);
+    assert!(stock.buy_orders.is_empty() && stock.sell_orders.is_empty());
+}
+
+#[test]
+fn test_nyse_sessions() {
+    let config = ExchangeConfig::for_venue(Venue::Nyse);
+    let periods: Vec<_> = config
+        .trading_periods
+        .iter()
+        .map(|period| period.period_type.clone())
+        .collect();
+    assert_eq!(
+        periods,
+        vec![
+            TradingPeriodType::PreMarket,
+            TradingPeriodType::ContinuousTrading,
+            TradingPeriodType::AfterHours,
+        ]
+    );
+
+    let mut exchange = Exchange::new(config);
+    let _ = exchange.add_stock("IBM", "IBM", Price::from_yuan(150));
+    let buyer_id = exchange.add_user("buyer", money("100000")).unwrap();
+    let seller_id = exchange.add_user("seller", Money::ZERO).unwrap();
+    let seller = exchange.user_manager.get_user_mut(seller_id).unwrap();
+    seller.add_holding("IBM".to_string(), 10, money("1500"));
+
+    // 盘前交易按连续竞价撮合 美股以1股为单位
+    exchange.next_timestamp("08:00:00");
+    exchange
+        .submit_order(
+            seller_id,
+            "IBM".to_string(),
+            OrderType::Sell,
+            Price::from_yuan(151),
+            3,
+        )
+        .unwrap();
+    exchange
+        .submit_order(
+            buyer_id,
+            "IBM".to_string(),
+            OrderType::Buy,
+            Price::from_yuan(151),
+            3,
+        )
+        .unwrap();
+    exchange.next_timestamp("08:00:01");
+    let (logs, _) = exchange.get_trade_logs("IBM", 1, 10);
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].quantity, 3);
+}
+
+#[test]
+fn test_crypto_trades_around_the_clock_with_t0() {
+    let mut exchange = Exchange::new(ExchangeConfig::for_venue(Venue::Crypto));
+    let _ = exchange.add_stock("BTC-USDT", "比特币", Price::from_yuan(30000));
+    let buyer_id = exchange.add_user("buyer", money("1000000")).unwrap();
+    let seller_id = exchange.add_user("seller", Money::ZERO).unwrap();
+    let seller = exchange.user_manager.get_user_mut(seller_id).unwrap();
+    seller.add_holding("BTC-USDT".to_string(), 10, money("300000"));
+
+    // 2023-01-01 是周日 全天候交易的场所照常交易
+    assert_eq!(exchange.current_trade_day().weekday(), Weekday::Sun);
+    exchange.next_trade_day();
+    assert_eq!(exchange.current_trade_day().weekday(), Weekday::Mon);
+
+    // 不设涨跌幅限制
+    exchange.next_timestamp("02:00:00");
+    exchange
+        .submit_order(
+            seller_id,
+            "BTC-USDT".to_string(),
+            OrderType::Sell,
+            Price::from_yuan(60000),
+            1,
+        )
+        .unwrap();
+    exchange
+        .submit_order(
+            buyer_id,
+            "BTC-USDT".to_string(),
+            OrderType::Buy,
+            Price::from_yuan(60000),
+            1,
+        )
+        .unwrap();
+    exchange.next_timestamp("02:00:01");
+
+    // T+0 买入当日即可卖出
+    let buyer = exchange.get_account(buyer_id).unwrap();
+    assert_eq!(buyer.positions[0].available_quantity, 1);
+    exchange
+        .submit_order(
+            buyer_id,
+            "BTC-USDT".to_string(),
+            OrderType::Sell,
+            Price::from_yuan(60000),
+            1,
+        )
+        .unwrap();
+
+    exchange.next_timestamp("23:59:59.999");
+    assert!(exchange.config.is_allow_order());
+}
@@ -0,0 +1,75 @@
+-- 金额及价格以 1/1000 元为单位
+
+CREATE TABLE users (
+    user_id BIGINT PRIMARY KEY,
+    username TEXT NOT NULL,
+    initial_balance BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+-- 委托的最新状态
+CREATE TABLE orders (
+    order_id BIGINT PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    stock_code TEXT NOT NULL,
+    order_type TEXT NOT NULL,
+    price BIGINT NOT NULL,
+    quantity INTEGER NOT NULL,
+    remaining_quantity INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    submitted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX orders_user_id_idx ON orders (user_id);
+
+-- 委托状态变化 按发生顺序
+CREATE TABLE order_status_changes (
+    id BIGSERIAL PRIMARY KEY,
+    order_id BIGINT NOT NULL REFERENCES orders (order_id),
+    status TEXT NOT NULL,
+    remaining_quantity INTEGER NOT NULL,
+    changed_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX order_status_changes_order_id_idx ON order_status_changes (order_id);
+
+CREATE TABLE executions (
+    id BIGSERIAL PRIMARY KEY,
+    order_id BIGINT NOT NULL REFERENCES orders (order_id),
+    quantity INTEGER NOT NULL,
+    price BIGINT NOT NULL,
+    commission BIGINT NOT NULL,
+    stamp_duty BIGINT NOT NULL,
+    transfer_fee BIGINT NOT NULL,
+    executed_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX executions_order_id_idx ON executions (order_id);
+
+CREATE TABLE trades (
+    trade_id BIGINT PRIMARY KEY,
+    buyer_id BIGINT NOT NULL,
+    seller_id BIGINT NOT NULL,
+    stock_code TEXT NOT NULL,
+    price BIGINT NOT NULL,
+    quantity INTEGER NOT NULL,
+    buy_order_id BIGINT NOT NULL,
+    sell_order_id BIGINT NOT NULL,
+    direction TEXT NOT NULL,
+    traded_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX trades_stock_code_idx ON trades (stock_code, traded_at);
+
+CREATE TABLE daily_bars (
+    stock_code TEXT NOT NULL,
+    trade_day DATE NOT NULL,
+    open BIGINT NOT NULL,
+    high BIGINT NOT NULL,
+    low BIGINT NOT NULL,
+    close BIGINT NOT NULL,
+    volume BIGINT NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (stock_code, trade_day)
+);
@@ -0,0 +1,75 @@
+-- 金额及价格以 1/1000 元为单位 表结构与 PostgreSQL 相同
+
+CREATE TABLE users (
+    user_id INTEGER PRIMARY KEY,
+    username TEXT NOT NULL,
+    initial_balance INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+-- 委托的最新状态
+CREATE TABLE orders (
+    order_id INTEGER PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    stock_code TEXT NOT NULL,
+    order_type TEXT NOT NULL,
+    price INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    remaining_quantity INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    submitted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX orders_user_id_idx ON orders (user_id);
+
+-- 委托状态变化 按发生顺序
+CREATE TABLE order_status_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id INTEGER NOT NULL REFERENCES orders (order_id),
+    status TEXT NOT NULL,
+    remaining_quantity INTEGER NOT NULL,
+    changed_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX order_status_changes_order_id_idx ON order_status_changes (order_id);
+
+CREATE TABLE executions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id INTEGER NOT NULL REFERENCES orders (order_id),
+    quantity INTEGER NOT NULL,
+    price INTEGER NOT NULL,
+    commission INTEGER NOT NULL,
+    stamp_duty INTEGER NOT NULL,
+    transfer_fee INTEGER NOT NULL,
+    executed_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX executions_order_id_idx ON executions (order_id);
+
+CREATE TABLE trades (
+    trade_id INTEGER PRIMARY KEY,
+    buyer_id INTEGER NOT NULL,
+    seller_id INTEGER NOT NULL,
+    stock_code TEXT NOT NULL,
+    price INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    buy_order_id INTEGER NOT NULL,
+    sell_order_id INTEGER NOT NULL,
+    direction TEXT NOT NULL,
+    traded_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX trades_stock_code_idx ON trades (stock_code, traded_at);
+
+CREATE TABLE daily_bars (
+    stock_code TEXT NOT NULL,
+    trade_day DATE NOT NULL,
+    open INTEGER NOT NULL,
+    high INTEGER NOT NULL,
+    low INTEGER NOT NULL,
+    close INTEGER NOT NULL,
+    volume INTEGER NOT NULL,
+    amount INTEGER NOT NULL,
+    PRIMARY KEY (stock_code, trade_day)
+);
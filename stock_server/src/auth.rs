@@ -0,0 +1,361 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, rand_core::RngCore, SaltString},
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+};
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Json},
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use exchange::{config_file::UserSpec, types::UserId, user::UserManager};
+
+/** 会话有效期 */
+const SESSION_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Error, Debug)]
+pub enum AuthError {
+    #[error("Username already exists: {0}")]
+    UsernameTaken(String),
+
+    #[error("Invalid registration: {0}")]
+    InvalidRegistration(String),
+
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+
+    #[error("Missing or invalid session token")]
+    Unauthorized,
+
+    #[error("Password hash error: {0}")]
+    PasswordHash(String),
+
+    #[error("Admin token required")]
+    Forbidden,
+}
+
+impl AuthError {
+    fn code(&self) -> u32 {
+        match self {
+            AuthError::UsernameTaken(_) => 1101,
+            AuthError::InvalidRegistration(_) => 1102,
+            AuthError::InvalidCredentials => 1103,
+            AuthError::Unauthorized => 1104,
+            AuthError::PasswordHash(_) => 1105,
+            AuthError::Forbidden => 1106,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AuthErrorBody {
+    code: u32,
+    message: String,
+    data: Option<()>,
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            AuthError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AuthError::Forbidden => StatusCode::FORBIDDEN,
+            _ => StatusCode::OK,
+        };
+        let body = AuthErrorBody {
+            code: self.code(),
+            message: self.to_string(),
+            data: None,
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Credential {
+    user_id: UserId,
+    password_hash: String,
+}
+
+struct Session {
+    user_id: UserId,
+    expires_at: Instant,
+}
+
+// 账号及会话存储 会话以令牌的 sha1 摘要为键，不保存明文令牌
+#[derive(Default)]
+pub struct AuthStore {
+    credentials: HashMap<String, Credential>,
+    sessions: HashMap<String, Session>,
+    /** 管理员令牌的摘要 */
+    admin_token_digest: Option<String>,
+}
+
+impl AuthStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_username_taken(&self, username: &str) -> bool {
+        self.credentials.contains_key(username)
+    }
+
+    /** 保存账号 密码需先经过 hash_password 处理 */
+    pub fn add_credential(
+        &mut self,
+        username: &str,
+        user_id: UserId,
+        password_hash: String,
+    ) -> Result<(), AuthError> {
+        if self.is_username_taken(username) {
+            return Err(AuthError::UsernameTaken(username.to_string()));
+        }
+        self.credentials.insert(
+            username.to_string(),
+            Credential {
+                user_id,
+                password_hash,
+            },
+        );
+        Ok(())
+    }
+
+    /** 导入配置文件中设置了密码哈希的用户 未设置的用户不能登录 */
+    pub fn import_config_users(
+        &mut self,
+        users: &[UserSpec],
+        user_manager: &UserManager,
+    ) -> Result<(), AuthError> {
+        for user in users {
+            let Some(password_hash) = &user.password_hash else {
+                continue;
+            };
+            check_password_hash(password_hash)?;
+            let user_id = user_manager.find_user_id(&user.name).ok_or_else(|| {
+                AuthError::InvalidRegistration(format!("unknown user {}", user.name))
+            })?;
+            self.add_credential(&user.name, user_id, password_hash.clone())?;
+        }
+        Ok(())
+    }
+
+    /** 查找账号 返回用户ID及密码哈希 */
+    pub fn find_credential(&self, username: &str) -> Result<(UserId, String), AuthError> {
+        self.credentials
+            .get(username)
+            .map(|credential| (credential.user_id, credential.password_hash.clone()))
+            .ok_or(AuthError::InvalidCredentials)
+    }
+
+    /** 创建会话 返回会话令牌 */
+    pub fn create_session(&mut self, user_id: UserId) -> String {
+        let token = generate_token();
+        self.sessions
+            .retain(|_, session| session.expires_at > Instant::now());
+        self.sessions.insert(
+            token_digest(&token),
+            Session {
+                user_id,
+                expires_at: Instant::now() + SESSION_TTL,
+            },
+        );
+        token
+    }
+
+    pub fn logout(&mut self, token: &str) {
+        self.sessions.remove(&token_digest(token));
+    }
+
+    /** 导出账号 用于保存快照 会话不保存 恢复后需重新登录 */
+    pub fn export_credentials(&self) -> serde_json::Value {
+        serde_json::to_value(&self.credentials).unwrap_or_default()
+    }
+
+    /** 从快照恢复账号 */
+    pub fn import_credentials(&mut self, value: serde_json::Value) -> serde_json::Result<()> {
+        self.credentials = serde_json::from_value(value)?;
+        Ok(())
+    }
+
+    /** 设置管理员令牌 用于时钟控制等管理接口 */
+    pub fn set_admin_token(&mut self, token: &str) {
+        self.admin_token_digest = Some(token_digest(token));
+    }
+
+    pub fn is_admin_token(&self, token: &str) -> bool {
+        self.admin_token_digest
+            .as_ref()
+            .is_some_and(|digest| *digest == token_digest(token))
+    }
+
+    /** 根据令牌查找会话用户 */
+    pub fn authenticate(&self, token: &str) -> Option<UserId> {
+        self.sessions
+            .get(&token_digest(token))
+            .filter(|session| session.expires_at > Instant::now())
+            .map(|session| session.user_id)
+    }
+}
+
+pub fn hash_password(password: &str) -> Result<String, AuthError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| AuthError::PasswordHash(err.to_string()))
+}
+
+/** 检查密码哈希能否用于验证密码 用于导入配置文件中的账号 */
+pub fn check_password_hash(password_hash: &str) -> Result<(), AuthError> {
+    let parsed =
+        PasswordHash::new(password_hash).map_err(|err| AuthError::PasswordHash(err.to_string()))?;
+    if parsed.hash.is_none() {
+        return Err(AuthError::PasswordHash("missing hash output".to_string()));
+    }
+    match Argon2::default().verify_password(b"", &parsed) {
+        Ok(()) | Err(argon2::password_hash::Error::Password) => Ok(()),
+        Err(err) => Err(AuthError::PasswordHash(err.to_string())),
+    }
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> Result<(), AuthError> {
+    let parsed =
+        PasswordHash::new(password_hash).map_err(|err| AuthError::PasswordHash(err.to_string()))?;
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .map_err(|_| AuthError::InvalidCredentials)
+}
+
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn token_digest(token: &str) -> String {
+    hex::encode(Sha1::digest(token.as_bytes()))
+}
+
+/** 已登录用户 从 `Authorization: Bearer <token>` 请求头中解析 */
+pub struct AuthUser {
+    pub user_id: UserId,
+    pub token: String,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthUser
+where
+    Arc<Mutex<AuthStore>>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let TypedHeader(Authorization(bearer)) =
+            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
+                .await
+                .map_err(|_| AuthError::Unauthorized)?;
+        let store = Arc::<Mutex<AuthStore>>::from_ref(state);
+        let user_id = store
+            .lock()
+            .unwrap()
+            .authenticate(bearer.token())
+            .ok_or(AuthError::Unauthorized)?;
+        Ok(AuthUser {
+            user_id,
+            token: bearer.token().to_string(),
+        })
+    }
+}
+
+/** 管理员 请求头需携带 `Authorization: Bearer <admin token>` */
+pub struct AdminUser;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminUser
+where
+    Arc<Mutex<AuthStore>>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let TypedHeader(Authorization(bearer)) =
+            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
+                .await
+                .map_err(|_| AuthError::Unauthorized)?;
+        let store = Arc::<Mutex<AuthStore>>::from_ref(state);
+        if store.lock().unwrap().is_admin_token(bearer.token()) {
+            Ok(AdminUser)
+        } else {
+            Err(AuthError::Forbidden)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credentials() {
+        let mut store = AuthStore::new();
+        let password_hash = hash_password("secret").unwrap();
+        store.add_credential("alice", 1, password_hash).unwrap();
+
+        // 用户名重复时拒绝注册 不覆盖原账号
+        let err = store
+            .add_credential("alice", 2, hash_password("other").unwrap())
+            .unwrap_err();
+        assert!(matches!(err, AuthError::UsernameTaken(ref username) if username == "alice"));
+        let (user_id, password_hash) = store.find_credential("alice").unwrap();
+        assert_eq!(user_id, 1);
+
+        assert!(verify_password("secret", &password_hash).is_ok());
+        assert!(matches!(
+            verify_password("wrong", &password_hash),
+            Err(AuthError::InvalidCredentials)
+        ));
+        assert!(matches!(
+            store.find_credential("bob"),
+            Err(AuthError::InvalidCredentials)
+        ));
+    }
+
+    #[test]
+    fn test_sessions() {
+        let mut store = AuthStore::new();
+        let token = store.create_session(1);
+        let other = store.create_session(2);
+        assert_eq!(store.authenticate(&token), Some(1));
+        assert_eq!(store.authenticate(&other), Some(2));
+        assert_eq!(store.authenticate(&generate_token()), None);
+
+        // 过期的会话失效
+        store
+            .sessions
+            .get_mut(&token_digest(&other))
+            .unwrap()
+            .expires_at = Instant::now();
+        assert_eq!(store.authenticate(&other), None);
+
+        // 退出登录后令牌失效 不影响管理员令牌
+        store.set_admin_token("admin");
+        store.logout(&token);
+        assert_eq!(store.authenticate(&token), None);
+        assert!(store.is_admin_token("admin"));
+        assert!(!store.is_admin_token(&token));
+    }
+}
@@ -0,0 +1,199 @@
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use exchange::{
+    exchange::Exchange,
+    export::{ExportFormat, ExportResult, ExportWriter},
+};
+
+/** 每批从交易所读取的记录数 批次之间释放交易所的锁 */
+const EXPORT_BATCH_SIZE: usize = 1000;
+
+/** 发送给客户端的数据块大小 */
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/** 缓冲的数据块数量 客户端读取过慢时导出线程等待 */
+const CHANNEL_CAPACITY: usize = 4;
+
+/**
+ * 将写入的数据按块发送到通道 在阻塞线程中使用
+ *
+ * 客户端断开后写入返回 BrokenPipe 导出随之结束
+ */
+pub struct ChannelWriter {
+    buffer: Vec<u8>,
+    sender: mpsc::Sender<io::Result<Vec<u8>>>,
+}
+
+impl ChannelWriter {
+    fn new(sender: mpsc::Sender<io::Result<Vec<u8>>>) -> Self {
+        ChannelWriter {
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+            sender,
+        }
+    }
+
+    fn send_buffer(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_SIZE));
+        self.sender
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "export receiver dropped"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.send_buffer()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_buffer()
+    }
+}
+
+/**
+ * 以附件形式流式返回导出数据
+ *
+ * export 每次导出从 cursor 起的一批记录 返回下一批的起始位置
+ * 每批只在导出期间持有交易所的锁 导出出错时中断响应
+ */
+pub fn stream_export<C, F>(
+    exchange: Arc<Mutex<Exchange>>,
+    format: ExportFormat,
+    name: &str,
+    start: C,
+    export: F,
+) -> Response
+where
+    C: Send + 'static,
+    F: Fn(&Exchange, C, usize, &mut ExportWriter<ChannelWriter>) -> ExportResult<Option<C>>
+        + Send
+        + 'static,
+{
+    let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+    let error_sender = sender.clone();
+    tokio::task::spawn_blocking(move || {
+        let writer = ExportWriter::new(ChannelWriter::new(sender), format);
+        if let Err(err) = export_batches(&exchange, start, export, writer) {
+            let _ = error_sender.blocking_send(Err(io::Error::other(err.to_string())));
+        }
+    });
+
+    let filename = format!("{}.{}", name, format.extension());
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(ReceiverStream::new(receiver)),
+    )
+        .into_response()
+}
+
+fn export_batches<C, F>(
+    exchange: &Mutex<Exchange>,
+    start: C,
+    export: F,
+    mut writer: ExportWriter<ChannelWriter>,
+) -> ExportResult<()>
+where
+    F: Fn(&Exchange, C, usize, &mut ExportWriter<ChannelWriter>) -> ExportResult<Option<C>>,
+{
+    let mut cursor = Some(start);
+    while let Some(from) = cursor {
+        cursor = export(
+            &exchange.lock().unwrap(),
+            from,
+            EXPORT_BATCH_SIZE,
+            &mut writer,
+        )?;
+        writer.flush()?;
+    }
+    writer.finish()?.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::config_file::ConfigFile;
+
+    const CONFIG: &str = r#"
+exchange:
+  start_date: 2024-03-01
+  seed: 5
+  trading_periods:
+    - name: 连续竞价交易
+      start_tick: "09:30:00"
+      end_tick: "09:59:59"
+      period_type: ContinuousTrading
+      allow_order: true
+      allow_cancel: true
+      allow_matching: true
+      allow_record_price_history: true
+stocks:
+  - { code: "000001", name: 股票A, price: "10.00" }
+bots:
+  - name: robot1
+    balance: "1000000"
+    strategy: { type: trade_random, param: 5 }
+    holdings: { "000001": 10000 }
+  - name: robot2
+    balance: "1000000"
+    strategy: { type: trade_random, param: 5 }
+    holdings: { "000001": 10000 }
+"#;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stream_export_matches_direct_export() {
+        let mut exchange = ConfigFile::from_yaml(CONFIG)
+            .unwrap()
+            .build_exchange()
+            .unwrap();
+        exchange.run_trading_days(1).unwrap();
+        assert!(exchange.log_manager.len() > 7);
+
+        let mut expected = ExportWriter::new(Vec::new(), ExportFormat::Csv);
+        exchange
+            .export_trades(None, None, 0, usize::MAX, &mut expected)
+            .unwrap();
+        let expected = expected.finish().unwrap();
+
+        let exchange = Arc::new(Mutex::new(exchange));
+        let response = stream_export(
+            exchange,
+            ExportFormat::Csv,
+            "trades",
+            0,
+            // 每批7条 验证批次之间的游标衔接
+            |exchange, from, _limit, writer| exchange.export_trades(None, None, from, 7, writer),
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"trades.csv\""
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.as_ref(), expected.as_slice());
+    }
+}
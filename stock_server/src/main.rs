@@ -1,41 +1,258 @@
+mod auth;
+mod export;
+mod storage;
+#[cfg(test)]
+mod testing;
+
 use axum::{
-    extract::{Path, Query, State},
+    extract::{FromRef, Path, Query, State},
     http::StatusCode,
-    response::{Html, IntoResponse, Json},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Json, Response,
+    },
     routing::{get, post},
     serve, Router,
 };
-use chrono::{Duration, NaiveTime, Timelike};
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::Read,
     net::SocketAddr,
+    path::PathBuf,
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::{broadcast, mpsc, mpsc::error::TrySendError};
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use utoipa::{
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+    Modify, OpenApi, ToSchema,
 };
-use tokio;
-use utoipa::{OpenApi, ToSchema};
 use utoipa_swagger_ui::SwaggerUi;
 
+use auth::{AdminUser, AuthError, AuthStore, AuthUser};
 use exchange::{
-    config::{ExchangeConfig, TradingPeriod},
+    clock::{ClockError, SimulationClock},
+    config::TradingPeriod,
+    config_file::ConfigFile,
+    event::ExchangeEvent,
     exchange::Exchange,
     exchange_error::ExchangeError,
+    export::{export_order_archive, CandleInterval, ExportFormat},
+    journal::{self, JournalWriter},
+    log::{SpillWriter, TradeLogQuery},
     order::OrderType,
-    trading_strategy::TradingStrategy,
-    types::{OrderId, Price, Quantity, StockCode, Timestamp, UserId},
+    record::ExchangeRecord,
+    snapshot::{self, SnapshotError},
+    types::{Money, OrderId, Price, Quantity, StockCode, Timestamp, TradeId, UserId},
+    user::PositionInfo,
 };
 
 #[derive(Clone)]
 struct AppState {
     exchange: Arc<Mutex<Exchange>>,
+    events: broadcast::Sender<ExchangeEvent>,
+    auth: Arc<Mutex<AuthStore>>,
+    clock: Arc<Mutex<SimulationClock>>,
+    /** 命令日志 未启用时为 None */
+    journal: Option<Arc<Mutex<JournalWriter>>>,
+    /** 超出保留策略的成交记录写入的文件 未配置时为 None */
+    trade_log_spill: Option<Arc<Mutex<SpillWriter>>>,
+    /** 收盘清除的委托写入的文件 */
+    order_archive: Arc<Mutex<SpillWriter>>,
+    order_archive_path: Arc<PathBuf>,
+    /** 发送待写入数据库的记录 未连接数据库时为 None */
+    records: Option<mpsc::Sender<Vec<ExchangeRecord>>>,
+}
+
+impl FromRef<AppState> for Arc<Mutex<AuthStore>> {
+    fn from_ref(state: &AppState) -> Self {
+        state.auth.clone()
+    }
 }
 
+/** 新注册用户的初始资金 */
+const REGISTER_INITIAL_BALANCE: Money = Money::from_yuan(1_000_000);
+
 #[derive(Deserialize, ToSchema)]
-struct OrderRequest {
+struct RegisterRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct RegisterResponse {
+    user_id: UserId,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct LoginRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct LoginResponse {
+    user_id: UserId,
+    token: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct AccountResponse {
+    user_id: UserId,
+    username: String,
+    #[schema(value_type = String)]
+    balance: Money,
+    #[schema(value_type = String)]
+    available_balance: Money,
+    #[schema(value_type = String)]
+    frozen_balance: Money,
+    #[schema(value_type = String)]
+    market_value: Money,
+    #[schema(value_type = String)]
+    total_equity: Money,
+    #[schema(value_type = String)]
+    realized_pnl: Money,
+    #[schema(value_type = String)]
+    unrealized_pnl: Money,
+    positions: Vec<PositionResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct PositionResponse {
+    stock_code: StockCode,
+    quantity: u64,
+    available_quantity: u64,
+    frozen_quantity: u64,
+    #[schema(value_type = String)]
+    cost: Money,
+    #[schema(value_type = String)]
+    average_cost: Price,
+    #[schema(value_type = String)]
+    current_price: Price,
+    #[schema(value_type = String)]
+    market_value: Money,
+    #[schema(value_type = String)]
+    unrealized_pnl: Money,
+    #[schema(value_type = String)]
+    realized_pnl: Money,
+}
+
+#[derive(Serialize, ToSchema)]
+struct PnlSnapshotResponse {
+    trade_day: NaiveDate,
+    #[schema(value_type = String)]
+    realized_pnl: Money,
+    #[schema(value_type = String)]
+    unrealized_pnl: Money,
+    #[schema(value_type = String)]
+    total_pnl: Money,
+    #[schema(value_type = String)]
+    daily_pnl: Money,
+    #[schema(value_type = String)]
+    total_equity: Money,
+}
+
+#[derive(Serialize, ToSchema)]
+struct MarketEfficiencyResponse {
+    stock_code: StockCode,
+    #[schema(value_type = String)]
+    fundamental_value: Price,
+    #[schema(value_type = String)]
+    market_price: Price,
+    samples: u64,
+    mean_absolute_error: f64,
+    root_mean_squared_log_error: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+struct FeeReportResponse {
+    #[schema(value_type = String)]
+    commission: Money,
+    #[schema(value_type = String)]
+    stamp_duty: Money,
+    #[schema(value_type = String)]
+    transfer_fee: Money,
+    #[schema(value_type = String)]
+    total: Money,
+    execution_count: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct AccountTradesParams {
+    stock_code: Option<StockCode>,
+    /** 上一页返回的 next_cursor */
+    cursor: Option<TradeId>,
+    /** 起始时间 包含 */
+    #[schema(value_type = Option<String>)]
+    start_time: Option<Timestamp>,
+    /** 结束时间 不包含 */
+    #[schema(value_type = Option<String>)]
+    end_time: Option<Timestamp>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct AccountTrade {
+    id: TradeId,
+    stock_code: StockCode,
+    /** buy 或 sell */
+    side: String,
+    order_id: OrderId,
+    #[schema(value_type = String)]
+    price: Price,
+    quantity: Quantity,
+    #[schema(value_type = String)]
+    timestamp: Timestamp,
+}
+
+#[derive(Serialize, ToSchema)]
+struct AccountTradesResponse {
+    list: Vec<AccountTrade>,
+    next_cursor: Option<TradeId>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct PnlRankingResponse {
+    rank: usize,
     user_id: UserId,
+    username: String,
+    is_robot: bool,
+    #[schema(value_type = String)]
+    realized_pnl: Money,
+    #[schema(value_type = String)]
+    unrealized_pnl: Money,
+    #[schema(value_type = String)]
+    total_pnl: Money,
+    #[schema(value_type = String)]
+    total_equity: Money,
+}
+
+impl From<PositionInfo> for PositionResponse {
+    fn from(position: PositionInfo) -> Self {
+        PositionResponse {
+            stock_code: position.stock_code,
+            quantity: position.quantity,
+            available_quantity: position.available_quantity,
+            frozen_quantity: position.frozen_quantity,
+            cost: position.cost,
+            average_cost: position.average_cost,
+            current_price: position.current_price,
+            market_value: position.market_value,
+            unrealized_pnl: position.unrealized_pnl,
+            realized_pnl: position.realized_pnl,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct OrderRequest {
     stock_code: StockCode,
     quantity: Quantity,
+    #[schema(value_type = String)]
     price: Price,
 }
 
@@ -53,24 +270,90 @@ struct OrderQueue {
 #[derive(Serialize, ToSchema)]
 struct ExchangeDetails {
     name: String,
-    current_timestamp: String,
-    current_period: TradingPeriod,
+    #[schema(value_type = String)]
+    current_timestamp: Timestamp,
+    /** 闭市时为空 */
+    current_period: Option<TradingPeriod>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ClockStatus {
+    paused: bool,
+    /** 速度倍数 模拟时间与真实时间之比 */
+    speed: u32,
+    #[schema(value_type = String)]
+    current_timestamp: Timestamp,
 }
 
+#[derive(Deserialize, ToSchema)]
+struct ClockSpeedRequest {
+    speed: u32,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ClockStepRequest {
+    /** 推进步数 每步 1 秒 默认为 1 最多一天的步数 */
+    steps: Option<u32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ClockJumpRequest {
+    /** 目标时间 如 2023-01-03T09:15:00 */
+    #[schema(value_type = String)]
+    timestamp: Timestamp,
+}
+
+/**
+ * 按页码或游标分页
+ *
+ * 指定 cursor 或时间范围时按游标分页 忽略 page
+ */
 #[derive(Deserialize, ToSchema)]
 struct TradeHistoryParams {
+    #[serde(default = "first_page")]
     page: usize,
     page_size: usize,
+    /** 上一页返回的 next_cursor */
+    cursor: Option<TradeId>,
+    /** 起始时间 包含 */
+    #[schema(value_type = Option<String>)]
+    start_time: Option<Timestamp>,
+    /** 结束时间 不包含 */
+    #[schema(value_type = Option<String>)]
+    end_time: Option<Timestamp>,
+}
+
+fn first_page() -> usize {
+    1
 }
 
 #[derive(Serialize, ToSchema)]
 struct TradeHistoryResponse {
     list: Vec<TradeLog>,
+    /** 总页数 */
     total: usize,
+    next_cursor: Option<TradeId>,
+}
+
+/** 导出参数 */
+#[derive(Deserialize, ToSchema)]
+struct ExportParams {
+    /** csv 或 jsonl 默认为 csv */
+    #[serde(default)]
+    #[schema(value_type = String)]
+    format: ExportFormat,
+    /** 只导出指定股票的成交 */
+    stock_code: Option<StockCode>,
+    /** K线周期 minute 或 day 默认为 day */
+    #[serde(default)]
+    #[schema(value_type = String)]
+    interval: CandleInterval,
 }
 
 #[derive(Deserialize, ToSchema)]
 struct PriceHistoryParams {
+    /** 交易日 默认为当前交易日 */
+    trade_day: Option<NaiveDate>,
     start_time: String,
     end_time: String,
 }
@@ -79,21 +362,31 @@ struct PriceHistoryParams {
 struct StockInfo {
     code: StockCode,
     name: String,
+    #[schema(value_type = String)]
     start_price: Price,
+    #[schema(value_type = String)]
     opening_price: Price,
+    #[schema(value_type = String)]
     current_price: Price,
+    #[schema(value_type = String)]
     highest_price: Price,
+    #[schema(value_type = String)]
     lowest_price: Price,
     price_amplitude: f64,
+    #[schema(value_type = String)]
     limit_upper: Price,
+    #[schema(value_type = String)]
     limit_lower: Price,
 }
 
 #[derive(Serialize, ToSchema)]
 struct TradeLog {
+    id: TradeId,
     stock_code: StockCode,
+    #[schema(value_type = String)]
     price: Price,
     quantity: Quantity,
+    #[schema(value_type = String)]
     timestamp: Timestamp,
     trade_type: u8,
 }
@@ -102,6 +395,16 @@ struct TradeLog {
 #[openapi(
     paths(
         home_page,
+        register,
+        login,
+        logout,
+        get_account,
+        get_holdings,
+        get_pnl_history,
+        get_fee_report,
+        get_account_trades,
+        get_pnl_ranking,
+        get_market_efficiency,
         get_stocks,
         buy_order,
         sell_order,
@@ -109,17 +412,42 @@ struct TradeLog {
         get_stock_detail,
         get_price_history,
         get_trade_history,
-        get_exchange_details
+        get_exchange_details,
+        exchange_events,
+        export_trades,
+        export_candles,
+        export_orders,
+        export_statements,
+        get_clock,
+        pause_clock,
+        resume_clock,
+        set_clock_speed,
+        step_clock,
+        jump_clock
     ),
     components(
-        schemas(OrderRequest, OrderResponse, OrderQueue, ExchangeDetails, TradeHistoryParams, TradeHistoryResponse, PriceHistoryParams, StockInfo, TradeLog)
+        schemas(RegisterRequest, RegisterResponse, LoginRequest, LoginResponse, AccountResponse, PositionResponse, PnlSnapshotResponse, FeeReportResponse, AccountTradesParams, AccountTrade, AccountTradesResponse, PnlRankingResponse, MarketEfficiencyResponse, OrderRequest, OrderResponse, OrderQueue, ExchangeDetails, TradeHistoryParams, TradeHistoryResponse, PriceHistoryParams, ExportParams, StockInfo, TradeLog, ClockStatus, ClockSpeedRequest, ClockStepRequest, ClockJumpRequest)
     ),
+    modifiers(&SecurityAddon),
     tags(
         (name = "stock_exchange", description = "Stock Exchange API")
     )
 )]
 struct ApiDoc;
 
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "session_token",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            )
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct ApiResponse<T> {
     code: u32,
@@ -165,6 +493,14 @@ fn handle_exchange_error<T: Serialize>(err: ExchangeError) -> ApiResponse<T> {
         ExchangeError::StockAlreadyExists(stock_code) => {
             (1008, format!("Stock already exists: {}", stock_code))
         }
+        ExchangeError::InsufficientStock => (1009, "Insufficient stock".to_string()),
+        ExchangeError::OrderRuleViolation {
+            stock_code,
+            violation,
+        } => (
+            1010,
+            format!("Order rule violation for stock {}: {}", stock_code, violation),
+        ),
         // 添加其他错误类型的处理
         _ => (9999, "Unknown error".to_string()),
     };
@@ -175,40 +511,329 @@ fn handle_exchange_error<T: Serialize>(err: ExchangeError) -> ApiResponse<T> {
     }
 }
 
+fn handle_clock_error<T: Serialize>(err: ClockError) -> ApiResponse<T> {
+    let code = match err {
+        ClockError::InvalidSpeed(_) => 1201,
+        ClockError::TargetInPast { .. } => 1202,
+        ClockError::NotTradingDay(_) => 1203,
+        ClockError::InvalidSteps { .. } => 1204,
+    };
+    ApiResponse {
+        code,
+        message: err.to_string(),
+        data: None,
+    }
+}
+
+/** 默认配置文件路径 */
+const DEFAULT_CONFIG_PATH: &str = "stock_server/config/exchange.yaml";
+
+/** 管理员令牌的环境变量 */
+const ADMIN_TOKEN_ENV: &str = "STOCK_SERVER_ADMIN_TOKEN";
+
+/** 数据库连接地址的环境变量 */
+const DATABASE_URL_ENV: &str = "DATABASE_URL";
+
+/** 默认快照文件路径 */
+const DEFAULT_SNAPSHOT_PATH: &str = "stock_server/data/snapshot.json";
+
+/** 默认的委托存档文件路径 */
+const DEFAULT_ORDER_ARCHIVE_PATH: &str = "stock_server/data/orders.jsonl";
+
+/** 默认自动快照间隔 秒 */
+const DEFAULT_SNAPSHOT_INTERVAL: u64 = 60;
+
+/** 快照中保存登录账号的键 */
+const CREDENTIALS_EXTENSION: &str = "credentials";
+
+const USAGE: &str = "Usage: stock-server [--config <path>] [--admin-token <token>] [--snapshot <path>] [--snapshot-interval <secs>] [--journal <path>] [--order-archive <path>] [--database-url <url>]";
+
+/** 成交记录默认及最大每页条数 */
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 1000;
+
+/** 时钟任务的间隔 */
+const CLOCK_INTERVAL: Duration = Duration::from_millis(100);
+
+/** 手动推进时每批的步数 每批之间释放交易所锁 */
+const CLOCK_STEP_BATCH: u32 = 100;
+
+struct ServerArgs {
+    config_path: String,
+    admin_token: Option<String>,
+    snapshot_path: String,
+    /** 自动快照间隔 秒 0 表示不自动保存 */
+    snapshot_interval: u64,
+    journal_path: Option<String>,
+    /** 收盘清除的委托追加写入的文件 */
+    order_archive_path: String,
+    database_url: Option<String>,
+}
+
+/** 解析命令行参数 */
+fn parse_args() -> Result<ServerArgs, String> {
+    let mut args = std::env::args().skip(1);
+    let mut config_path = None;
+    let mut admin_token = std::env::var(ADMIN_TOKEN_ENV).ok();
+    let mut snapshot_path = None;
+    let mut snapshot_interval = DEFAULT_SNAPSHOT_INTERVAL;
+    let mut journal_path = None;
+    let mut order_archive_path = None;
+    let mut database_url = std::env::var(DATABASE_URL_ENV).ok();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" | "--config" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                config_path = Some(path);
+            }
+            "--admin-token" => {
+                let token = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                admin_token = Some(token);
+            }
+            "--snapshot" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                snapshot_path = Some(path);
+            }
+            "--snapshot-interval" => {
+                let secs = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                snapshot_interval = secs
+                    .parse()
+                    .map_err(|_| format!("invalid snapshot interval: {}", secs))?;
+            }
+            "--journal" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                journal_path = Some(path);
+            }
+            "--order-archive" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                order_archive_path = Some(path);
+            }
+            "--database-url" => {
+                let url = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                database_url = Some(url);
+            }
+            "--hash-password" => {
+                let password = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                let password_hash =
+                    auth::hash_password(&password).map_err(|err| err.to_string())?;
+                println!("{}", password_hash);
+                std::process::exit(0);
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                println!(
+                    "  -c, --config <path>      exchange config file (default: {})",
+                    DEFAULT_CONFIG_PATH
+                );
+                println!(
+                    "      --admin-token <token>  token for /admin endpoints (env: {}, default: random)",
+                    ADMIN_TOKEN_ENV
+                );
+                println!(
+                    "      --snapshot <path>      snapshot file, restored on startup if present (default: {})",
+                    DEFAULT_SNAPSHOT_PATH
+                );
+                println!(
+                    "      --snapshot-interval <secs>  auto-snapshot interval, 0 disables (default: {})",
+                    DEFAULT_SNAPSHOT_INTERVAL
+                );
+                println!(
+                    "      --journal <path>       record every command to a journal, replayed on startup if present"
+                );
+                println!(
+                    "      --order-archive <path>  orders cleared at each close, exported with /export/orders (default: {})",
+                    DEFAULT_ORDER_ARCHIVE_PATH
+                );
+                println!(
+                    "      --database-url <url>   save users, orders, trades and bars to postgres:// or sqlite: (env: {})",
+                    DATABASE_URL_ENV
+                );
+                println!(
+                    "      --hash-password <password>  print the password_hash for a user in the config file"
+                );
+                std::process::exit(0);
+            }
+            path if config_path.is_none() && !path.starts_with('-') => {
+                config_path = Some(path.to_string());
+            }
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    Ok(ServerArgs {
+        config_path: config_path.unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string()),
+        admin_token,
+        snapshot_path: snapshot_path.unwrap_or_else(|| DEFAULT_SNAPSHOT_PATH.to_string()),
+        snapshot_interval,
+        journal_path,
+        order_archive_path: order_archive_path
+            .unwrap_or_else(|| DEFAULT_ORDER_ARCHIVE_PATH.to_string()),
+        database_url,
+    })
+}
+
 #[tokio::main]
 async fn main() {
-    let config = ExchangeConfig::new();
-    let exchange = Arc::new(Mutex::new(Exchange::new(config)));
+    let args = parse_args().unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        std::process::exit(2);
+    });
+    // 有快照时从快照恢复 忽略配置文件
+    let snapshot_path = args.snapshot_path;
+    let config_path = args.config_path;
+    let restore = std::path::Path::new(&snapshot_path).exists();
+    let (exchange, clock, credentials, config_users) = if restore {
+        let snapshot = snapshot::load(&snapshot_path).unwrap_or_else(|err| {
+            eprintln!("{}: {}", snapshot_path, err);
+            std::process::exit(1);
+        });
+        println!(
+            "Restored snapshot {} at {}",
+            snapshot_path,
+            snapshot.exchange.get_current_timestamp()
+        );
+        let mut extensions = snapshot.extensions;
+        let credentials = extensions.remove(CREDENTIALS_EXTENSION);
+        (snapshot.exchange, snapshot.clock, credentials, Vec::new())
+    } else {
+        let (exchange, users) = ConfigFile::load(&config_path)
+            .and_then(|config| Ok((config.build_exchange()?, config.users)))
+            .unwrap_or_else(|err| {
+                eprintln!("{}: {}", config_path, err);
+                std::process::exit(1);
+            });
+        (exchange, SimulationClock::new(), None, users)
+    };
 
+    // 日志记录了快照之后的全部命令 存在时重放日志恢复交易所状态
+    let mut exchange = exchange;
+    let journal = args.journal_path.map(|journal_path| {
+        let writer = if std::path::Path::new(&journal_path).exists() {
+            journal::replay(&journal_path).and_then(|replay| {
+                println!(
+                    "Replayed {} journal entries from {}",
+                    replay.entries, journal_path
+                );
+                let writer = JournalWriter::resume(&journal_path, &replay)?;
+                exchange = replay.exchange;
+                Ok(writer)
+            })
+        } else {
+            exchange.enable_journal();
+            JournalWriter::create(&journal_path, &exchange)
+        };
+        let writer = writer.unwrap_or_else(|err| {
+            eprintln!("{}: {}", journal_path, err);
+            std::process::exit(1);
+        });
+        Arc::new(Mutex::new(writer))
+    });
+    println!("Random seed: {}", exchange.config.seed());
+
+    // 超出保留策略的成交记录写入文件 未配置时丢弃
+    let spill_path = exchange.config.trade_log_retention.spill_path.clone();
+    let trade_log_spill = spill_path.map(|spill_path| {
+        let writer = SpillWriter::open(&spill_path).unwrap_or_else(|err| {
+            eprintln!("{}: {}", spill_path.display(), err);
+            std::process::exit(1);
+        });
+        exchange.enable_log_spill();
+        Arc::new(Mutex::new(writer))
+    });
+
+    // 收盘清除的委托写入存档文件 导出委托时先读取存档
+    let order_archive_path = PathBuf::from(args.order_archive_path);
+    let order_archive = SpillWriter::open(&order_archive_path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", order_archive_path.display(), err);
+        std::process::exit(1);
+    });
+    exchange.enable_order_archive();
+
+    // 连接数据库后记录异步写入 不阻塞撮合
+    let records = match args.database_url {
+        Some(database_url) => {
+            let storage = storage::connect(&database_url).await.unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            });
+            exchange.enable_records();
+            let sender = storage::spawn_writer(storage, storage::WritePolicy::default());
+            sender.try_send(exchange.drain_records()).ok();
+            Some(sender)
+        }
+        None => None,
+    };
+    let exchange = Arc::new(Mutex::new(exchange));
+
+    // 未指定管理员令牌时随机生成
+    let admin_token = args.admin_token.unwrap_or_else(|| {
+        let token = auth::generate_token();
+        println!("Admin token: {}", token);
+        token
+    });
+    let mut auth_store = AuthStore::new();
+    auth_store.set_admin_token(&admin_token);
+    if let Some(credentials) = credentials {
+        auth_store
+            .import_credentials(credentials)
+            .unwrap_or_else(|err| {
+                eprintln!("{}: invalid credentials: {}", snapshot_path, err);
+                std::process::exit(1);
+            });
+    }
+    // 配置文件中设置了密码哈希的用户可直接登录 快照中已保存账号
     {
-        let mut ex = exchange.lock().unwrap();
-        ex.add_stock("000001", "股票A", 100).unwrap();
-        ex.add_stock("000002", "股票B", 200).unwrap();
-        ex.add_user("user1", 100000000).unwrap();
-        ex.add_user("user2", 150000000).unwrap();
-        ex.add_robot("robot1", 100000000, TradingStrategy::SimpleRandom, vec![("000001", 100000)])
-            .unwrap();
-        ex.add_robot("robot2", 100000000, TradingStrategy::SimpleRandom, vec![("000002", 100000)])
-            .unwrap();
-        ex.add_robot("robot3", 100000000, TradingStrategy::TradeRandom(5), vec![("000001", 100000), ("000002", 100000)])
-            .unwrap();
-        ex.add_robot("robot5", 100000000, TradingStrategy::SimpleRandom, vec![("000001", 100000), ("000002", 100000)])
-            .unwrap();
-        ex.add_robot("robot6", 100000000, TradingStrategy::TradeRandom(5), vec![("000001", 100000), ("000002", 100000)])
-            .unwrap();
-        ex.add_robot("robot7", 100000000, TradingStrategy::SimpleRandom, vec![("000001", 100000), ("000002", 100000)])
-            .unwrap();
-        ex.add_robot("robot8", 100000000, TradingStrategy::TradeRandom(5), vec![("000001", 100000), ("000002", 100000)])
-            .unwrap();
-        ex.add_robot("robot9", 100000000, TradingStrategy::TradeRandom(5), vec![("000001", 100000), ("000002", 100000)])
-            .unwrap();
+        let exchange = exchange.lock().unwrap();
+        auth_store
+            .import_config_users(&config_users, &exchange.user_manager)
+            .unwrap_or_else(|err| {
+                eprintln!("{}: {}", config_path, err);
+                std::process::exit(1);
+            });
     }
+
+    let clock = Arc::new(Mutex::new(clock));
+    let (events, _) = broadcast::channel(256);
     let app_state = AppState {
         exchange: exchange.clone(),
+        events: events.clone(),
+        auth: Arc::new(Mutex::new(auth_store)),
+        clock: clock.clone(),
+        journal,
+        trade_log_spill,
+        order_archive: Arc::new(Mutex::new(order_archive)),
+        order_archive_path: Arc::new(order_archive_path),
+        records,
     };
+    let snapshot_state = app_state.clone();
     let app = Router::new()
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .route("/", get(home_page))
+        .route("/register", post(register))
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+        .route("/account", get(get_account))
+        .route("/account/holdings", get(get_holdings))
+        .route("/account/pnl_history", get(get_pnl_history))
+        .route("/account/fees", get(get_fee_report))
+        .route("/account/trades", get(get_account_trades))
+        .route("/pnl_ranking", get(get_pnl_ranking))
+        .route("/market_efficiency", get(get_market_efficiency))
         .route("/stocks", get(get_stocks))
         .route("/buy", post(buy_order))
         .route("/sell", post(sell_order))
@@ -217,47 +842,178 @@ async fn main() {
         .route("/price_history/:stock_code", get(get_price_history))
         .route("/trade_history/:stock_code", get(get_trade_history))
         .route("/exchange_details", get(get_exchange_details))
+        .route("/events", get(exchange_events))
+        .route("/export/trades", get(export_trades))
+        .route("/export/candles/:stock_code", get(export_candles))
+        .route("/export/orders", get(export_orders))
+        .route("/export/statements", get(export_statements))
+        .route("/admin/clock", get(get_clock))
+        .route("/admin/clock/pause", post(pause_clock))
+        .route("/admin/clock/resume", post(resume_clock))
+        .route("/admin/clock/speed", post(set_clock_speed))
+        .route("/admin/clock/step", post(step_clock))
+        .route("/admin/clock/jump", post(jump_clock))
         .with_state(app_state);
 
     // 启动交易所时间更新任务
-    let exchange_clone = exchange.clone();
+    let clock_state = snapshot_state.clone();
     tokio::spawn(async move {
-        update_exchange_time(exchange_clone).await;
+        update_exchange_time(clock_state).await;
     });
 
+    // 定时自动保存快照
+    if args.snapshot_interval > 0 {
+        let state = snapshot_state.clone();
+        let path = snapshot_path.clone();
+        let interval = Duration::from_secs(args.snapshot_interval);
+        tokio::spawn(async move {
+            auto_snapshot(state, path, interval).await;
+        });
+    }
+
     println!("Server running on http://localhost:3000");
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    serve(listener, app).await.unwrap();
+    // 推送事件的长连接不会自行结束 收到退出信号后保存快照并直接退出
+    tokio::select! {
+        result = serve(listener, app) => result.unwrap(),
+        _ = shutdown_signal() => {
+            match save_snapshot(&snapshot_state, &snapshot_path) {
+                Ok(()) => println!("Snapshot saved to {}", snapshot_path),
+                Err(err) => eprintln!("Failed to save snapshot {}: {}", snapshot_path, err),
+            }
+        }
+    }
+}
+
+/** 等待 Ctrl+C 或 SIGTERM */
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.unwrap();
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .unwrap()
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
 
-async fn update_exchange_time(exchange: Arc<Mutex<Exchange>>) {
-    let mut interval = tokio::time::interval(Duration::milliseconds(100).to_std().unwrap());
-    let mut time = NaiveTime::from_hms_opt(9, 30, 0).unwrap();
+/**
+ * 保存快照
+ *
+ * 与注册接口相同 先锁账号再锁交易所 保证账号与用户一致 序列化完成后在锁外写文件
+ */
+fn save_snapshot(state: &AppState, path: &str) -> Result<(), SnapshotError> {
+    let clock = state.clock.lock().unwrap().clone();
+    let bytes = {
+        let auth = state.auth.lock().unwrap();
+        let mut extensions = BTreeMap::new();
+        extensions.insert(CREDENTIALS_EXTENSION.to_string(), auth.export_credentials());
+        let exchange = state.exchange.lock().unwrap();
+        snapshot::to_bytes(&exchange, &clock, &extensions)?
+    };
+    snapshot::write_file(path, &bytes)
+}
+
+async fn auto_snapshot(state: AppState, path: String, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    // 第一次 tick 立即返回 跳过
+    interval.tick().await;
     loop {
         interval.tick().await;
-        // 更新交易所时间 每 tick 更新 1 秒
-        time = time + Duration::seconds(1);
+        let state = state.clone();
+        let path = path.clone();
+        let result = tokio::task::spawn_blocking(move || save_snapshot(&state, &path)).await;
+        if let Ok(Err(err)) = result {
+            eprintln!("Failed to save snapshot: {}", err);
+        }
+    }
+}
 
-        let mut ex = exchange.lock().unwrap();
-        ex.next_timestamp(&time.format("%H:%M:%S").to_string());
+async fn update_exchange_time(state: AppState) {
+    let mut interval = tokio::time::interval(CLOCK_INTERVAL);
+    let mut last_tick = Instant::now();
+    loop {
+        interval.tick().await;
+        // 按经过的真实时间及速度倍数推进模拟时钟
+        let now = Instant::now();
+        let (clock, steps) = {
+            let mut clock = state.clock.lock().unwrap();
+            let steps = clock.elapse(now - last_tick);
+            (clock.clone(), steps)
+        };
+        last_tick = now;
 
-        // 打印当前交易所时间
-        println!(
-            "Exchange time: {} period: {}",
-            exchange::types::timestamp_to_string(ex.get_config().current_timestamp),
-            ex.get_config()
-                .get_current_period()
-                .unwrap()
-                .name
-                .to_string()
-        );
+        let mut ex = state.exchange.lock().unwrap();
+        for _ in 0..steps {
+            advance_clock(&clock, &mut ex, &state.events);
+        }
+        persist_changes(&state, &mut ex);
+    }
+}
 
-        // 每 3 秒执行一次机器人策略
-        if time.second() % 3 == 0 {
-            ex.execute_robot_strategies().unwrap();
+/**
+ * 将交易所执行的命令写入日志 移除的成交记录写入文件 并把产生的记录发送给数据库写入任务
+ *
+ * 须在释放交易所锁之前调用 以保证日志顺序与执行顺序一致
+ */
+fn persist_changes(state: &AppState, exchange: &mut Exchange) {
+    if let Some(spill) = &state.trade_log_spill {
+        let logs = exchange.drain_spilled_logs();
+        if !logs.is_empty() {
+            if let Err(err) = spill.lock().unwrap().write(&logs) {
+                eprintln!("Failed to write spilled trade logs: {}", err);
+            }
+        }
+    }
+    let orders = exchange.drain_archived_orders();
+    if !orders.is_empty() {
+        if let Err(err) = state.order_archive.lock().unwrap().write(&orders) {
+            eprintln!("Failed to write archived orders: {}", err);
         }
     }
+    if let Some(records) = &state.records {
+        let pending = exchange.drain_records();
+        if !pending.is_empty() {
+            // 积压已满时丢弃 不阻塞撮合 写入任务退出后不再发送
+            if let Err(TrySendError::Full(dropped)) = records.try_send(pending) {
+                eprintln!("Record queue full, dropped {} records", dropped.len());
+            }
+        }
+    }
+    if let Some(journal) = &state.journal {
+        let commands = exchange.drain_journal();
+        if commands.is_empty() {
+            return;
+        }
+        if let Err(err) = journal.lock().unwrap().write(&commands) {
+            eprintln!("Failed to write journal: {}", err);
+        }
+    }
+}
+
+/** 推进一步 执行机器人策略并推送事件 */
+fn advance_clock(
+    clock: &SimulationClock,
+    exchange: &mut Exchange,
+    events: &broadcast::Sender<ExchangeEvent>,
+) {
+    // 每 3 秒执行一次机器人策略
+    exchange.tick(clock).unwrap();
+
+    // 推送时钟及交易时段事件 没有订阅者时直接丢弃
+    for event in exchange.drain_events() {
+        let _ = events.send(event);
+    }
 }
 
 #[utoipa::path(
@@ -275,6 +1031,330 @@ async fn home_page() -> Html<String> {
     Html(contents)
 }
 
+#[utoipa::path(
+    post,
+    path = "/register",
+    request_body = RegisterRequest,
+    responses(
+        (status = 200, description = "User registered successfully", body = ApiResponse<RegisterResponse>)
+    ),
+    tag = "stock_exchange"
+)]
+async fn register(
+    State(state): State<AppState>,
+    Json(register_req): Json<RegisterRequest>,
+) -> Result<ApiResponse<RegisterResponse>, AuthError> {
+    let username = register_req.username.trim().to_string();
+    if username.is_empty() || register_req.password.is_empty() {
+        return Err(AuthError::InvalidRegistration(
+            "username and password must not be empty".to_string(),
+        ));
+    }
+    // 账号及交易所用户(配置用户及机器人)的用户名都不能重复
+    if state.auth.lock().unwrap().is_username_taken(&username)
+        || state
+            .exchange
+            .lock()
+            .unwrap()
+            .user_manager
+            .is_username_taken(&username)
+    {
+        return Err(AuthError::UsernameTaken(username));
+    }
+
+    // 密码哈希计算较慢，放在锁外进行
+    let password = register_req.password;
+    let password_hash = tokio::task::spawn_blocking(move || auth::hash_password(&password))
+        .await
+        .map_err(|err| AuthError::PasswordHash(err.to_string()))??;
+
+    let mut auth = state.auth.lock().unwrap();
+    if auth.is_username_taken(&username) {
+        return Err(AuthError::UsernameTaken(username));
+    }
+    let user_id = {
+        let mut exchange = state.exchange.lock().unwrap();
+        if exchange.user_manager.is_username_taken(&username) {
+            return Err(AuthError::UsernameTaken(username));
+        }
+        let result = exchange.add_user(&username, REGISTER_INITIAL_BALANCE);
+        persist_changes(&state, &mut exchange);
+        result
+    }
+    .map_err(|err| AuthError::InvalidRegistration(err.to_string()))?;
+    auth.add_credential(&username, user_id, password_hash)?;
+    Ok(ApiResponse::success(RegisterResponse { user_id }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/login",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in, returns a session token", body = ApiResponse<LoginResponse>)
+    ),
+    tag = "stock_exchange"
+)]
+async fn login(
+    State(state): State<AppState>,
+    Json(login_req): Json<LoginRequest>,
+) -> Result<ApiResponse<LoginResponse>, AuthError> {
+    let (user_id, password_hash) = state
+        .auth
+        .lock()
+        .unwrap()
+        .find_credential(login_req.username.trim())?;
+
+    let password = login_req.password;
+    tokio::task::spawn_blocking(move || auth::verify_password(&password, &password_hash))
+        .await
+        .map_err(|err| AuthError::PasswordHash(err.to_string()))??;
+
+    let token = state.auth.lock().unwrap().create_session(user_id);
+    Ok(ApiResponse::success(LoginResponse { user_id, token }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/logout",
+    responses(
+        (status = 200, description = "Session ended"),
+        (status = 401, description = "Missing or invalid session token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn logout(State(state): State<AppState>, auth_user: AuthUser) -> ApiResponse<()> {
+    state.auth.lock().unwrap().logout(&auth_user.token);
+    ApiResponse::success(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/account",
+    responses(
+        (status = 200, description = "Cash, frozen amounts, positions and total equity of the logged-in user", body = ApiResponse<AccountResponse>),
+        (status = 401, description = "Missing or invalid session token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn get_account(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> ApiResponse<AccountResponse> {
+    let exchange = state.exchange.lock().unwrap();
+    match exchange.get_account(auth_user.user_id) {
+        Ok(account) => ApiResponse::success(AccountResponse {
+            user_id: account.user_id,
+            username: account.username,
+            balance: account.balance,
+            available_balance: account.available_balance,
+            frozen_balance: account.frozen_balance,
+            market_value: account.market_value,
+            total_equity: account.total_equity,
+            realized_pnl: account.realized_pnl,
+            unrealized_pnl: account.unrealized_pnl,
+            positions: account.positions.into_iter().map(Into::into).collect(),
+        }),
+        Err(err) => handle_exchange_error(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/account/holdings",
+    responses(
+        (status = 200, description = "Positions of the logged-in user valued at the current price", body = ApiResponse<Vec<PositionResponse>>),
+        (status = 401, description = "Missing or invalid session token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn get_holdings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> ApiResponse<Vec<PositionResponse>> {
+    let exchange = state.exchange.lock().unwrap();
+    match exchange.get_account(auth_user.user_id) {
+        Ok(account) => {
+            ApiResponse::success(account.positions.into_iter().map(Into::into).collect())
+        }
+        Err(err) => handle_exchange_error(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/account/pnl_history",
+    responses(
+        (status = 200, description = "Daily P&L snapshots of the logged-in user", body = ApiResponse<Vec<PnlSnapshotResponse>>),
+        (status = 401, description = "Missing or invalid session token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn get_pnl_history(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> ApiResponse<Vec<PnlSnapshotResponse>> {
+    let exchange = state.exchange.lock().unwrap();
+    match exchange.get_pnl_history(auth_user.user_id) {
+        Ok(history) => ApiResponse::success(
+            history
+                .into_iter()
+                .map(|snapshot| PnlSnapshotResponse {
+                    trade_day: snapshot.trade_day,
+                    realized_pnl: snapshot.realized_pnl,
+                    unrealized_pnl: snapshot.unrealized_pnl,
+                    total_pnl: snapshot.total_pnl,
+                    daily_pnl: snapshot.daily_pnl,
+                    total_equity: snapshot.total_equity,
+                })
+                .collect(),
+        ),
+        Err(err) => handle_exchange_error(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/account/fees",
+    responses(
+        (status = 200, description = "Accumulated trading fees of the logged-in user", body = ApiResponse<FeeReportResponse>),
+        (status = 401, description = "Missing or invalid session token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn get_fee_report(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> ApiResponse<FeeReportResponse> {
+    let exchange = state.exchange.lock().unwrap();
+    match exchange.get_fee_report(auth_user.user_id) {
+        Ok(report) => ApiResponse::success(FeeReportResponse {
+            commission: report.commission,
+            stamp_duty: report.stamp_duty,
+            transfer_fee: report.transfer_fee,
+            total: report.total,
+            execution_count: report.execution_count,
+        }),
+        Err(err) => handle_exchange_error(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/account/trades",
+    params(
+        ("stock_code" = Option<String>, Query, description = "Stock code"),
+        ("cursor" = Option<u64>, Query, description = "next_cursor of the previous page"),
+        ("start_time" = Option<String>, Query, description = "Start time (inclusive), e.g. 2023-01-03T09:30:00"),
+        ("end_time" = Option<String>, Query, description = "End time (exclusive)"),
+        ("limit" = Option<usize>, Query, description = "Page size")
+    ),
+    responses(
+        (status = 200, description = "Trades of the logged-in user, newest first", body = ApiResponse<AccountTradesResponse>),
+        (status = 401, description = "Missing or invalid session token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn get_account_trades(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(params): Query<AccountTradesParams>,
+) -> ApiResponse<AccountTradesResponse> {
+    let exchange = state.exchange.lock().unwrap();
+    let page = exchange.query_trade_logs(&TradeLogQuery {
+        stock_code: params.stock_code,
+        user_id: Some(auth_user.user_id),
+        start_time: params.start_time,
+        end_time: params.end_time,
+        before: params.cursor,
+        limit: params.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE),
+    });
+    let list = page
+        .logs
+        .into_iter()
+        .map(|log| {
+            let (side, order_id) = if log.buyer_id == auth_user.user_id {
+                ("buy", log.buy_order_id)
+            } else {
+                ("sell", log.sell_order_id)
+            };
+            AccountTrade {
+                id: log.id,
+                stock_code: log.stock_code,
+                side: side.to_string(),
+                order_id,
+                price: log.price,
+                quantity: log.quantity,
+                timestamp: log.timestamp,
+            }
+        })
+        .collect();
+    ApiResponse::success(AccountTradesResponse {
+        list,
+        next_cursor: page.next_cursor,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/pnl_ranking",
+    responses(
+        (status = 200, description = "Users ranked by realized plus unrealized P&L", body = ApiResponse<Vec<PnlRankingResponse>>)
+    ),
+    tag = "stock_exchange"
+)]
+async fn get_pnl_ranking(State(state): State<AppState>) -> ApiResponse<Vec<PnlRankingResponse>> {
+    let exchange = state.exchange.lock().unwrap();
+    let ranking = exchange
+        .get_pnl_ranking()
+        .into_iter()
+        .map(|entry| PnlRankingResponse {
+            rank: entry.rank,
+            user_id: entry.user_id,
+            username: entry.username,
+            is_robot: entry.is_robot,
+            realized_pnl: entry.realized_pnl,
+            unrealized_pnl: entry.unrealized_pnl,
+            total_pnl: entry.total_pnl,
+            total_equity: entry.total_equity,
+        })
+        .collect();
+    ApiResponse::success(ranking)
+}
+
+#[utoipa::path(
+    get,
+    path = "/market_efficiency",
+    responses(
+        (status = 200, description = "Deviation of market prices from the fundamental value, empty when no fundamental process is configured", body = ApiResponse<Vec<MarketEfficiencyResponse>>)
+    ),
+    tag = "stock_exchange"
+)]
+async fn get_market_efficiency(
+    State(state): State<AppState>,
+) -> ApiResponse<Vec<MarketEfficiencyResponse>> {
+    let exchange = state.exchange.lock().unwrap();
+    let reports = exchange
+        .market_efficiency()
+        .into_iter()
+        .map(|report| MarketEfficiencyResponse {
+            stock_code: report.stock_code,
+            fundamental_value: report.fundamental_value,
+            market_price: report.market_price,
+            samples: report.samples,
+            mean_absolute_error: report.mean_absolute_error,
+            root_mean_squared_log_error: report.root_mean_squared_log_error,
+        })
+        .collect();
+    ApiResponse::success(reports)
+}
+
 #[utoipa::path(
     get,
     path = "/stocks",
@@ -313,22 +1393,27 @@ async fn get_stocks(State(state): State<AppState>) -> ApiResponse<Vec<StockInfo>
     path = "/buy",
     request_body = OrderRequest,
     responses(
-        (status = 200, description = "Buy order placed successfully", body = ApiResponse<OrderResponse>), 
+        (status = 200, description = "Buy order placed successfully", body = ApiResponse<OrderResponse>),
+        (status = 401, description = "Missing or invalid session token")
     ),
+    security(("session_token" = [])),
     tag = "stock_exchange"
 )]
 async fn buy_order(
     State(state): State<AppState>,
+    auth_user: AuthUser,
     Json(order_req): Json<OrderRequest>,
 ) -> ApiResponse<OrderResponse> {
     let mut exchange = state.exchange.lock().unwrap();
-    match exchange.submit_order(
-        order_req.user_id,
+    let result = exchange.submit_order(
+        auth_user.user_id,
         order_req.stock_code,
         OrderType::Buy,
         order_req.price,
         order_req.quantity,
-    ) {
+    );
+    persist_changes(&state, &mut exchange);
+    match result {
         Ok(order_id) => ApiResponse::success(OrderResponse { order_id }),
         Err(err) => handle_exchange_error(err),
     }
@@ -339,22 +1424,27 @@ async fn buy_order(
     path = "/sell",
     request_body = OrderRequest,
     responses(
-        (status = 200, description = "Sell order placed successfully", body = ApiResponse<OrderResponse>), 
+        (status = 200, description = "Sell order placed successfully", body = ApiResponse<OrderResponse>),
+        (status = 401, description = "Missing or invalid session token")
     ),
+    security(("session_token" = [])),
     tag = "stock_exchange"
 )]
 async fn sell_order(
     State(state): State<AppState>,
+    auth_user: AuthUser,
     Json(order_req): Json<OrderRequest>,
 ) -> ApiResponse<OrderResponse> {
     let mut exchange = state.exchange.lock().unwrap();
-    match exchange.submit_order(
-        order_req.user_id,
+    let result = exchange.submit_order(
+        auth_user.user_id,
         order_req.stock_code,
         OrderType::Sell,
         order_req.price,
         order_req.quantity,
-    ) {
+    );
+    persist_changes(&state, &mut exchange);
+    match result {
         Ok(order_id) => ApiResponse::success(OrderResponse { order_id }),
         Err(err) => handle_exchange_error(err),
     }
@@ -421,6 +1511,7 @@ async fn get_stock_detail(
     path = "/price_history/{stock_code}",
     params(
         ("stock_code" = String, Path, description = "Stock code"),
+        ("trade_day" = Option<String>, Query, description = "Trade day, defaults to the current trade day"),
         ("start_time" = String, Query, description = "Start time"),
         ("end_time" = String, Query, description = "End time")
     ),
@@ -435,8 +1526,17 @@ async fn get_price_history(
     Query(params): Query<PriceHistoryParams>,
 ) -> ApiResponse<Vec<(String, Price, Quantity)>> {
     let exchange = state.exchange.lock().unwrap();
-    let start_time = exchange::types::string_to_timestamp(&params.start_time).unwrap();
-    let end_time = exchange::types::string_to_timestamp(&params.end_time).unwrap();
+    let trade_day = params
+        .trade_day
+        .unwrap_or_else(|| exchange.current_trade_day());
+    let start_time = Timestamp::new(
+        trade_day,
+        exchange::types::string_to_time_of_day(&params.start_time).unwrap(),
+    );
+    let end_time = Timestamp::new(
+        trade_day,
+        exchange::types::string_to_time_of_day(&params.end_time).unwrap(),
+    );
     let price_history = exchange
         .get_price_history(&stock_code)
         .unwrap()
@@ -444,7 +1544,7 @@ async fn get_price_history(
         .filter(|item| item.timestamp >= start_time && item.timestamp <= end_time)
         .map(|item| {
             (
-                exchange::types::timestamp_to_string(item.timestamp),
+                exchange::types::time_of_day_to_string(item.timestamp.time()),
                 item.price,
                 item.volume,
             )
@@ -459,8 +1559,11 @@ async fn get_price_history(
     path = "/trade_history/{stock_code}",
     params(
         ("stock_code" = String, Path, description = "Stock code"),
-        ("page" = usize, Query, description = "Page number"),
-        ("page_size" = usize, Query, description = "Page size")
+        ("page" = Option<usize>, Query, description = "Page number, starting from 1"),
+        ("page_size" = usize, Query, description = "Page size"),
+        ("cursor" = Option<u64>, Query, description = "next_cursor of the previous page, replaces page"),
+        ("start_time" = Option<String>, Query, description = "Start time (inclusive), e.g. 2023-01-03T09:30:00"),
+        ("end_time" = Option<String>, Query, description = "End time (exclusive)")
     ),
     responses(
         (status = 200, description = "Trade history for the given stock", body = ApiResponse<TradeHistoryResponse>) 
@@ -473,10 +1576,31 @@ async fn get_trade_history(
     Query(params): Query<TradeHistoryParams>,
 ) -> ApiResponse<TradeHistoryResponse> {
     let exchange = state.exchange.lock().unwrap();
-    let (list, total) = exchange.get_trade_logs(&stock_code, params.page, params.page_size);
+    let page_size = params.page_size.min(MAX_PAGE_SIZE);
+    let (list, total, next_cursor) =
+        if params.cursor.is_some() || params.start_time.is_some() || params.end_time.is_some() {
+            let page = exchange.query_trade_logs(&TradeLogQuery {
+                stock_code: Some(stock_code.clone()),
+                start_time: params.start_time,
+                end_time: params.end_time,
+                before: params.cursor,
+                limit: page_size,
+                ..Default::default()
+            });
+            let total = exchange.get_trade_logs(&stock_code, 1, page_size).1;
+            (page.logs, total, page.next_cursor)
+        } else {
+            let (list, total) = exchange.get_trade_logs(&stock_code, params.page, page_size);
+            let next_cursor = list
+                .last()
+                .map(|log| log.id)
+                .filter(|_| params.page < total);
+            (list, total, next_cursor)
+        };
     let list = list
         .into_iter()
         .map(|log| TradeLog {
+            id: log.id,
             stock_code: log.stock_code,
             price: log.price,
             quantity: log.quantity,
@@ -484,7 +1608,11 @@ async fn get_trade_history(
             trade_type: if log.buy_order_id < log.sell_order_id { 0 } else { 1 },
         })
         .collect();
-    ApiResponse::success(TradeHistoryResponse { list, total })
+    ApiResponse::success(TradeHistoryResponse {
+        list,
+        total,
+        next_cursor,
+    })
 }
 
 #[utoipa::path(
@@ -498,11 +1626,546 @@ async fn get_trade_history(
 async fn get_exchange_details(State(state): State<AppState>) -> ApiResponse<ExchangeDetails> {
     let exchange = state.exchange.lock().unwrap();
     let config = exchange.get_config();
-    let current_period = config.get_current_period().unwrap();
     let details = ExchangeDetails {
         name: config.name.clone(),
-        current_timestamp: exchange::types::timestamp_to_string(config.current_timestamp),
-        current_period: current_period.clone(),
+        current_timestamp: config.current_timestamp,
+        current_period: config.get_current_period().cloned(),
     };
     ApiResponse::success(details)
 }
+
+#[utoipa::path(
+    get,
+    path = "/events",
+    responses(
+        (status = 200, description = "Server-Sent Events stream of clock ticks, trading period transitions, day rollovers and halts", content_type = "text/event-stream")
+    ),
+    tag = "stock_exchange"
+)]
+async fn exchange_events(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    // 订阅者消费过慢时跳过丢失的事件
+    let stream = BroadcastStream::new(state.events.subscribe())
+        .filter_map(|event| event.ok())
+        .map(|event| Event::default().event(event.name()).json_data(event));
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[utoipa::path(
+    get,
+    path = "/export/trades",
+    params(
+        ("stock_code" = Option<String>, Query, description = "Only export trades of the given stock"),
+        ("format" = Option<String>, Query, description = "csv (default) or jsonl")
+    ),
+    responses(
+        (status = 200, description = "Trade history in ascending order, streamed as an attachment", content_type = "text/csv")
+    ),
+    tag = "stock_exchange"
+)]
+async fn export_trades(
+    State(state): State<AppState>,
+    Query(params): Query<ExportParams>,
+) -> Response {
+    let stock_code = params.stock_code;
+    export::stream_export(
+        state.exchange,
+        params.format,
+        "trades",
+        0,
+        move |exchange, from, limit, writer| {
+            exchange.export_trades(stock_code.as_deref(), None, from, limit, writer)
+        },
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/export/candles/{stock_code}",
+    params(
+        ("stock_code" = String, Path, description = "Stock code"),
+        ("interval" = Option<String>, Query, description = "minute or day (default)"),
+        ("format" = Option<String>, Query, description = "csv (default) or jsonl")
+    ),
+    responses(
+        (status = 200, description = "Candles built from the price history, streamed as an attachment", content_type = "text/csv")
+    ),
+    tag = "stock_exchange"
+)]
+async fn export_candles(
+    State(state): State<AppState>,
+    Path(stock_code): Path<String>,
+    Query(params): Query<ExportParams>,
+) -> Response {
+    if state
+        .exchange
+        .lock()
+        .unwrap()
+        .get_stock_info(&stock_code)
+        .is_none()
+    {
+        return handle_exchange_error::<()>(ExchangeError::StockNotFound(stock_code))
+            .into_response();
+    }
+    let interval = params.interval;
+    let name = match interval {
+        CandleInterval::Minute => format!("{}_minute", stock_code),
+        CandleInterval::Day => format!("{}_day", stock_code),
+    };
+    export::stream_export(
+        state.exchange,
+        params.format,
+        &name,
+        0,
+        move |exchange, from, limit, writer| {
+            exchange.export_candles(&stock_code, interval, from, limit, writer)
+        },
+    )
+}
+
+/** 委托导出的位置 先读取存档文件 再导出当日的委托 */
+enum OrderCursor {
+    /** 存档文件中的字节偏移 */
+    Archive(u64),
+    /** 当日委托编号 */
+    Today(OrderId),
+}
+
+#[utoipa::path(
+    get,
+    path = "/export/orders",
+    params(
+        ("format" = Option<String>, Query, description = "csv (default) or jsonl")
+    ),
+    responses(
+        (status = 200, description = "Orders of all trade days, archived days first, with one row per execution, streamed as an attachment", content_type = "text/csv"),
+        (status = 401, description = "Missing or invalid session token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn export_orders(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(params): Query<ExportParams>,
+) -> Response {
+    let user_id = auth_user.user_id;
+    let archive_path = state.order_archive_path;
+    export::stream_export(
+        state.exchange,
+        params.format,
+        "orders",
+        OrderCursor::Archive(0),
+        move |exchange, cursor, limit, writer| {
+            let from = match cursor {
+                OrderCursor::Archive(offset) => {
+                    let next =
+                        export_order_archive(&archive_path, Some(user_id), offset, limit, writer)?;
+                    if let Some(offset) = next {
+                        return Ok(Some(OrderCursor::Archive(offset)));
+                    }
+                    // 存档读完后在同一批中开始导出当日委托 期间收盘的委托不会遗漏
+                    0
+                }
+                OrderCursor::Today(from) => from,
+            };
+            let next = exchange.export_orders(Some(user_id), from, limit, writer)?;
+            Ok(next.map(OrderCursor::Today))
+        },
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/export/statements",
+    params(
+        ("format" = Option<String>, Query, description = "csv (default) or jsonl")
+    ),
+    responses(
+        (status = 200, description = "Daily account statements of the logged-in user, streamed as an attachment", content_type = "text/csv"),
+        (status = 401, description = "Missing or invalid session token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn export_statements(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(params): Query<ExportParams>,
+) -> Response {
+    let user_id = auth_user.user_id;
+    export::stream_export(
+        state.exchange,
+        params.format,
+        "statements",
+        user_id,
+        move |exchange, from, limit, writer| {
+            exchange.export_statements(Some(user_id), from, limit, writer)
+        },
+    )
+}
+
+fn clock_status(state: &AppState) -> ClockStatus {
+    let (paused, speed) = {
+        let clock = state.clock.lock().unwrap();
+        (clock.is_paused(), clock.speed())
+    };
+    ClockStatus {
+        paused,
+        speed,
+        current_timestamp: state.exchange.lock().unwrap().get_current_timestamp(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/clock",
+    responses(
+        (status = 200, description = "Simulation clock state", body = ApiResponse<ClockStatus>),
+        (status = 403, description = "Missing or invalid admin token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn get_clock(State(state): State<AppState>, _admin: AdminUser) -> ApiResponse<ClockStatus> {
+    ApiResponse::success(clock_status(&state))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/clock/pause",
+    responses(
+        (status = 200, description = "Clock paused", body = ApiResponse<ClockStatus>),
+        (status = 403, description = "Missing or invalid admin token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn pause_clock(State(state): State<AppState>, _admin: AdminUser) -> ApiResponse<ClockStatus> {
+    state.clock.lock().unwrap().pause();
+    ApiResponse::success(clock_status(&state))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/clock/resume",
+    responses(
+        (status = 200, description = "Clock resumed", body = ApiResponse<ClockStatus>),
+        (status = 403, description = "Missing or invalid admin token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn resume_clock(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> ApiResponse<ClockStatus> {
+    state.clock.lock().unwrap().resume();
+    ApiResponse::success(clock_status(&state))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/clock/speed",
+    request_body = ClockSpeedRequest,
+    responses(
+        (status = 200, description = "Clock speed multiplier set, between 1 and 1000", body = ApiResponse<ClockStatus>),
+        (status = 403, description = "Missing or invalid admin token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn set_clock_speed(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Json(request): Json<ClockSpeedRequest>,
+) -> ApiResponse<ClockStatus> {
+    if let Err(err) = state.clock.lock().unwrap().set_speed(request.speed) {
+        return handle_clock_error(err);
+    }
+    ApiResponse::success(clock_status(&state))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/clock/step",
+    request_body = ClockStepRequest,
+    responses(
+        (status = 200, description = "Clock advanced by the given number of one-second steps, at most one day of steps", body = ApiResponse<ClockStatus>),
+        (status = 403, description = "Missing or invalid admin token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn step_clock(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Json(request): Json<ClockStepRequest>,
+) -> ApiResponse<ClockStatus> {
+    let clock = state.clock.lock().unwrap().clone();
+    let steps = request.steps.unwrap_or(1);
+    if let Err(err) = clock.check_steps(steps) {
+        return handle_clock_error(err);
+    }
+    // 在阻塞线程中分批推进 每批之间释放交易所锁 不阻塞行情推送及下单
+    let task_state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut remaining = steps;
+        while remaining > 0 {
+            let batch = remaining.min(CLOCK_STEP_BATCH);
+            let mut exchange = task_state.exchange.lock().unwrap();
+            for _ in 0..batch {
+                advance_clock(&clock, &mut exchange, &task_state.events);
+            }
+            persist_changes(&task_state, &mut exchange);
+            remaining -= batch;
+        }
+    })
+    .await
+    .unwrap();
+    ApiResponse::success(clock_status(&state))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/clock/jump",
+    request_body = ClockJumpRequest,
+    responses(
+        (status = 200, description = "Clock jumped to the given time, running every session transition on the way", body = ApiResponse<ClockStatus>),
+        (status = 403, description = "Missing or invalid admin token")
+    ),
+    security(("session_token" = [])),
+    tag = "stock_exchange"
+)]
+async fn jump_clock(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Json(request): Json<ClockJumpRequest>,
+) -> ApiResponse<ClockStatus> {
+    let clock = state.clock.lock().unwrap().clone();
+    let target = request.timestamp;
+    if let Err(err) = clock.check_jump(&state.exchange.lock().unwrap(), target) {
+        return handle_clock_error(err);
+    }
+    // 在阻塞线程中逐日跳转 每个交易日之间释放交易所锁
+    let task_state = state.clone();
+    let result = tokio::task::spawn_blocking(move || loop {
+        let mut exchange = task_state.exchange.lock().unwrap();
+        let result = clock.jump_day(&mut exchange, target);
+        persist_changes(&task_state, &mut exchange);
+        for event in exchange.drain_events() {
+            let _ = task_state.events.send(event);
+        }
+        match result {
+            Ok(false) => {}
+            Ok(true) => break Ok(()),
+            Err(err) => break Err(err),
+        }
+    })
+    .await
+    .unwrap();
+    if let Err(err) = result {
+        return handle_clock_error(err);
+    }
+    ApiResponse::success(clock_status(&state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::FromRequestParts, http::Request};
+
+    fn app_state() -> AppState {
+        let mut exchange = testing::build_exchange(7, false);
+        exchange.next_timestamp("09:30:00");
+        let archive_path = std::env::temp_dir()
+            .join(format!("stock-server-main-{}", std::process::id()))
+            .join("orders.jsonl");
+        let order_archive = SpillWriter::open(&archive_path).unwrap();
+        AppState {
+            exchange: Arc::new(Mutex::new(exchange)),
+            events: broadcast::channel(16).0,
+            auth: Arc::new(Mutex::new(AuthStore::new())),
+            clock: Arc::new(Mutex::new(SimulationClock::new())),
+            journal: None,
+            trade_log_spill: None,
+            order_archive: Arc::new(Mutex::new(order_archive)),
+            order_archive_path: Arc::new(archive_path),
+            records: None,
+        }
+    }
+
+    /** 按请求头中的令牌解析登录用户 */
+    async fn auth_user(state: &AppState, token: &str) -> Result<AuthUser, AuthError> {
+        let (mut parts, _) = Request::builder()
+            .header("Authorization", format!("Bearer {}", token))
+            .body(())
+            .unwrap()
+            .into_parts();
+        AuthUser::from_request_parts(&mut parts, state).await
+    }
+
+    fn login(state: &AppState, username: &str) -> (UserId, String) {
+        let user_id = state
+            .exchange
+            .lock()
+            .unwrap()
+            .add_user(username, REGISTER_INITIAL_BALANCE)
+            .unwrap();
+        let token = state.auth.lock().unwrap().create_session(user_id);
+        (user_id, token)
+    }
+
+    #[tokio::test]
+    async fn test_token_trades_on_own_account() {
+        let state = app_state();
+        let (alice_id, alice_token) = login(&state, "alice");
+        let (bob_id, bob_token) = login(&state, "bob");
+
+        let order = OrderRequest {
+            stock_code: "000001".to_string(),
+            quantity: 100,
+            price: "9.90".parse().unwrap(),
+        };
+        let user = auth_user(&state, &alice_token).await.unwrap();
+        let response = buy_order(State(state.clone()), user, Json(order)).await;
+        let order_id = response.data.unwrap().order_id;
+
+        // 委托记在令牌对应的账户上 其他账户不受影响
+        {
+            let exchange = state.exchange.lock().unwrap();
+            let order = exchange.order_manager.get_order(order_id).unwrap();
+            assert_eq!(order.user_id, alice_id);
+            let alice = exchange.get_account(alice_id).unwrap();
+            assert!(alice.frozen_balance > Money::ZERO);
+            let bob = exchange.get_account(bob_id).unwrap();
+            assert_eq!(bob.frozen_balance, Money::ZERO);
+            assert_eq!(bob.available_balance, REGISTER_INITIAL_BALANCE);
+        }
+
+        // 退出登录或未知的令牌不能下单
+        state.auth.lock().unwrap().logout(&bob_token);
+        assert!(matches!(
+            auth_user(&state, &bob_token).await,
+            Err(AuthError::Unauthorized)
+        ));
+        assert!(matches!(
+            auth_user(&state, "unknown").await,
+            Err(AuthError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_existing_exchange_user() {
+        let state = app_state();
+        // 配置文件中的用户及机器人没有账号 但用户名已被占用
+        login(&state, "user1");
+        let request = |username: &str| RegisterRequest {
+            username: username.to_string(),
+            password: "secret".to_string(),
+        };
+        let result = register(State(state.clone()), Json(request(" user1 "))).await;
+        assert!(
+            matches!(result, Err(AuthError::UsernameTaken(ref username)) if username == "user1")
+        );
+
+        let response = register(State(state.clone()), Json(request("alice")))
+            .await
+            .unwrap();
+        let user_id = response.data.unwrap().user_id;
+        assert!(matches!(
+            register(State(state.clone()), Json(request("alice"))).await,
+            Err(AuthError::UsernameTaken(_))
+        ));
+        let exchange = state.exchange.lock().unwrap();
+        assert_eq!(exchange.user_manager.get_user_ids().len(), 2);
+        assert_eq!(
+            exchange.user_manager.get_user(user_id).unwrap().username,
+            "alice"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_config_users_with_password_hash_can_login() {
+        let state = app_state();
+        let config = ConfigFile::from_yaml(&format!(
+            "{}users:\n  - {{ name: user1, balance: \"1000\", password_hash: \"{}\" }}\n  - {{ name: user2, balance: \"1000\" }}\n",
+            testing::config_yaml(7, false),
+            auth::hash_password("secret").unwrap()
+        ))
+        .unwrap();
+        let exchange = config.build_exchange().unwrap();
+        let user_id = exchange.user_manager.find_user_id("user1").unwrap();
+        state
+            .auth
+            .lock()
+            .unwrap()
+            .import_config_users(&config.users, &exchange.user_manager)
+            .unwrap();
+        *state.exchange.lock().unwrap() = exchange;
+
+        let request = |username: &str, password: &str| LoginRequest {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        let response = super::login(State(state.clone()), Json(request("user1", "secret")))
+            .await
+            .unwrap();
+        let token = response.data.unwrap().token;
+        assert_eq!(auth_user(&state, &token).await.unwrap().user_id, user_id);
+
+        // 密码错误及未设置密码哈希的用户不能登录
+        assert!(matches!(
+            super::login(State(state.clone()), Json(request("user1", "wrong"))).await,
+            Err(AuthError::InvalidCredentials)
+        ));
+        assert!(matches!(
+            super::login(State(state.clone()), Json(request("user2", "secret"))).await,
+            Err(AuthError::InvalidCredentials)
+        ));
+    }
+
+    #[test]
+    fn test_config_users_reject_invalid_password_hash() {
+        let config = ConfigFile::from_yaml(&format!(
+            "{}users:\n  - {{ name: user1, balance: \"1000\", password_hash: \"$pbkdf2$i=1000$c2FsdA$aGFzaA\" }}\n",
+            testing::config_yaml(7, false)
+        ))
+        .unwrap();
+        let exchange = config.build_exchange().unwrap();
+        assert!(matches!(
+            AuthStore::new().import_config_users(&config.users, &exchange.user_manager),
+            Err(AuthError::PasswordHash(_))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_clock_step_and_jump_in_batches() {
+        let state = app_state();
+        let timestamp = |state: &AppState| state.exchange.lock().unwrap().get_current_timestamp();
+
+        // 跨越多批的推进与逐步推进结果相同
+        let request = ClockStepRequest {
+            steps: Some(CLOCK_STEP_BATCH * 2 + 50),
+        };
+        let response = step_clock(State(state.clone()), AdminUser, Json(request)).await;
+        assert_eq!(response.code, 0);
+        assert_eq!(timestamp(&state), "2024-03-01T09:34:10".parse().unwrap());
+
+        // 跨周末逐日跳转
+        let target: Timestamp = "2024-03-05T09:40:00".parse().unwrap();
+        let request = ClockJumpRequest { timestamp: target };
+        let response = jump_clock(State(state.clone()), AdminUser, Json(request)).await;
+        assert_eq!(response.code, 0);
+        assert_eq!(response.data.unwrap().current_timestamp, target);
+        assert_eq!(timestamp(&state), target);
+
+        let request = ClockJumpRequest {
+            timestamp: "2024-03-04T09:40:00".parse().unwrap(),
+        };
+        let response = jump_clock(State(state.clone()), AdminUser, Json(request)).await;
+        assert_eq!(response.code, 1202);
+        assert_eq!(timestamp(&state), target);
+    }
+}
@@ -0,0 +1,438 @@
+mod postgres;
+mod sqlite;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use sqlx::{database::HasArguments, Database, Encode, Executor, IntoArguments, Pool, Type};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use exchange::{
+    exchange_error::{ExchangeError, ExchangeResult},
+    log::PriceDirection,
+    order::OrderType,
+    record::ExchangeRecord,
+};
+
+pub use postgres::PgStorage;
+pub use sqlite::SqliteStorage;
+
+/**
+ * 交易记录的存储
+ *
+ * 金额及价格以 1/1000 元为单位保存 时间为模拟时间
+ */
+#[axum::async_trait]
+pub trait Storage: Send + Sync {
+    /** 创建或升级表结构 */
+    async fn migrate(&self) -> ExchangeResult<()>;
+
+    /** 在一个事务中按顺序写入记录 */
+    async fn save_records(&self, records: &[ExchangeRecord]) -> ExchangeResult<()>;
+}
+
+/**
+ * 按连接地址选择存储
+ *
+ * `postgres://` 使用 PostgreSQL `sqlite:` 使用 SQLite 数据库文件 文件不存在时创建
+ */
+pub async fn connect(database_url: &str) -> ExchangeResult<Arc<dyn Storage>> {
+    let storage: Arc<dyn Storage> =
+        if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+            Arc::new(PgStorage::connect(database_url).await?)
+        } else if database_url.starts_with("sqlite:") {
+            Arc::new(SqliteStorage::connect(database_url).await?)
+        } else {
+            return Err(ExchangeError::DatabaseError(format!(
+                "unsupported database url: {}",
+                database_url
+            )));
+        };
+    storage.migrate().await?;
+    Ok(storage)
+}
+
+/** 后台写入任务的队列容量及重试策略 */
+#[derive(Clone, Debug)]
+pub struct WritePolicy {
+    /** 通道中最多积压的批次 已满时丢弃新的记录 不阻塞撮合 */
+    pub capacity: usize,
+    /** 一批记录最多重试的次数 仍然失败时丢弃 */
+    pub max_retries: u32,
+    /** 写入失败后首次重试的等待时间 */
+    pub retry_min: Duration,
+    /** 重试等待时间的上限 */
+    pub retry_max: Duration,
+}
+
+impl Default for WritePolicy {
+    fn default() -> Self {
+        WritePolicy {
+            capacity: 1024,
+            max_retries: 10,
+            retry_min: Duration::from_millis(100),
+            retry_max: Duration::from_secs(30),
+        }
+    }
+}
+
+/**
+ * 启动后台写入任务 返回发送记录的通道
+ *
+ * 撮合只需把记录发送到通道 不等待数据库 积压的记录合并到一个事务写入
+ * 写入失败时保留该批记录 等待后连同新记录一起重试 等待时间逐次加倍
+ * 重试次数用尽后丢弃这些记录 数据库长时间不可用时内存占用不会无限增长
+ */
+pub fn spawn_writer(
+    storage: Arc<dyn Storage>,
+    policy: WritePolicy,
+) -> mpsc::Sender<Vec<ExchangeRecord>> {
+    let (sender, mut receiver) = mpsc::channel::<Vec<ExchangeRecord>>(policy.capacity);
+    tokio::spawn(async move {
+        let mut pending: Vec<ExchangeRecord> = Vec::new();
+        let mut retries = 0;
+        loop {
+            if pending.is_empty() {
+                match receiver.recv().await {
+                    Some(records) => pending = records,
+                    None => break,
+                }
+            }
+            // 失败的记录在前 保持写入顺序
+            while let Ok(more) = receiver.try_recv() {
+                pending.extend(more);
+            }
+            match storage.save_records(&pending).await {
+                Ok(()) => {
+                    pending.clear();
+                    retries = 0;
+                }
+                Err(err) if retries >= policy.max_retries => {
+                    eprintln!(
+                        "Failed to save {} records after {} retries, dropping them: {}",
+                        pending.len(),
+                        retries,
+                        err
+                    );
+                    pending.clear();
+                    retries = 0;
+                }
+                Err(err) => {
+                    let retry_delay =
+                        (policy.retry_min * 2u32.pow(retries.min(16))).min(policy.retry_max);
+                    eprintln!(
+                        "Failed to save {} records, retrying in {:?}: {}",
+                        pending.len(),
+                        retry_delay,
+                        err
+                    );
+                    tokio::time::sleep(retry_delay).await;
+                    retries += 1;
+                }
+            }
+        }
+    });
+    sender
+}
+
+fn database_error(err: impl std::fmt::Display) -> ExchangeError {
+    ExchangeError::DatabaseError(err.to_string())
+}
+
+/** 数量及编号按有符号整数保存 超出范围时报错 不截断 */
+fn to_i32(value: u32) -> sqlx::Result<i32> {
+    i32::try_from(value).map_err(|_| out_of_range(value))
+}
+
+fn to_i64(value: u64) -> sqlx::Result<i64> {
+    i64::try_from(value).map_err(|_| out_of_range(value))
+}
+
+fn out_of_range(value: impl std::fmt::Display) -> sqlx::Error {
+    sqlx::Error::Protocol(format!("value out of range: {}", value))
+}
+
+fn order_type_name(order_type: OrderType) -> &'static str {
+    match order_type {
+        OrderType::Buy => "buy",
+        OrderType::Sell => "sell",
+    }
+}
+
+fn direction_name(direction: &PriceDirection) -> &'static str {
+    match direction {
+        PriceDirection::Flat => "flat",
+        PriceDirection::Up => "up",
+        PriceDirection::Down => "down",
+    }
+}
+
+/** 两种数据库的表结构相同 写入语句通用 */
+async fn save_records<DB>(pool: &Pool<DB>, records: &[ExchangeRecord]) -> ExchangeResult<()>
+where
+    DB: Database,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> i32: Encode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    for<'q> NaiveDate: Encode<'q, DB> + Type<DB>,
+    for<'q> NaiveDateTime: Encode<'q, DB> + Type<DB>,
+{
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    for record in records {
+        save_record::<DB>(&mut tx, record)
+            .await
+            .map_err(database_error)?;
+    }
+    tx.commit().await.map_err(database_error)
+}
+
+async fn save_record<DB>(conn: &mut DB::Connection, record: &ExchangeRecord) -> sqlx::Result<()>
+where
+    DB: Database,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> i32: Encode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    for<'q> NaiveDate: Encode<'q, DB> + Type<DB>,
+    for<'q> NaiveDateTime: Encode<'q, DB> + Type<DB>,
+{
+    match record {
+        ExchangeRecord::User {
+            user_id,
+            username,
+            balance,
+            timestamp,
+        } => {
+            sqlx::query::<DB>(
+                "INSERT INTO users (user_id, username, initial_balance, created_at)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (user_id) DO NOTHING",
+            )
+            .bind(to_i64(*user_id)?)
+            .bind(username.as_str())
+            .bind(balance.raw())
+            .bind(timestamp.to_datetime())
+            .execute(&mut *conn)
+            .await?;
+        }
+        ExchangeRecord::Order {
+            order_id,
+            user_id,
+            stock_code,
+            order_type,
+            price,
+            quantity,
+            remaining_quantity,
+            status,
+            submitted_at,
+            timestamp,
+        } => {
+            sqlx::query::<DB>(
+                "INSERT INTO orders (order_id, user_id, stock_code, order_type, price, quantity,
+                     remaining_quantity, status, submitted_at, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                 ON CONFLICT (order_id) DO UPDATE SET
+                     remaining_quantity = EXCLUDED.remaining_quantity,
+                     status = EXCLUDED.status,
+                     updated_at = EXCLUDED.updated_at",
+            )
+            .bind(to_i64(*order_id)?)
+            .bind(to_i64(*user_id)?)
+            .bind(stock_code.as_str())
+            .bind(order_type_name(*order_type))
+            .bind(i64::from(price.raw()))
+            .bind(to_i32(*quantity)?)
+            .bind(to_i32(*remaining_quantity)?)
+            .bind(status.name())
+            .bind(submitted_at.to_datetime())
+            .bind(timestamp.to_datetime())
+            .execute(&mut *conn)
+            .await?;
+            sqlx::query::<DB>(
+                "INSERT INTO order_status_changes (order_id, status, remaining_quantity, changed_at)
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(to_i64(*order_id)?)
+            .bind(status.name())
+            .bind(to_i32(*remaining_quantity)?)
+            .bind(timestamp.to_datetime())
+            .execute(&mut *conn)
+            .await?;
+        }
+        ExchangeRecord::Execution {
+            order_id,
+            quantity,
+            price,
+            fees,
+            timestamp,
+        } => {
+            sqlx::query::<DB>(
+                "INSERT INTO executions (order_id, quantity, price, commission, stamp_duty,
+                     transfer_fee, executed_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            )
+            .bind(to_i64(*order_id)?)
+            .bind(to_i32(*quantity)?)
+            .bind(i64::from(price.raw()))
+            .bind(fees.commission.raw())
+            .bind(fees.stamp_duty.raw())
+            .bind(fees.transfer_fee.raw())
+            .bind(timestamp.to_datetime())
+            .execute(&mut *conn)
+            .await?;
+        }
+        ExchangeRecord::Trade(trade) => {
+            sqlx::query::<DB>(
+                "INSERT INTO trades (trade_id, buyer_id, seller_id, stock_code, price, quantity,
+                     buy_order_id, sell_order_id, direction, traded_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                 ON CONFLICT (trade_id) DO NOTHING",
+            )
+            .bind(to_i64(trade.id)?)
+            .bind(to_i64(trade.buyer_id)?)
+            .bind(to_i64(trade.seller_id)?)
+            .bind(trade.stock_code.as_str())
+            .bind(i64::from(trade.price.raw()))
+            .bind(to_i32(trade.quantity)?)
+            .bind(to_i64(trade.buy_order_id)?)
+            .bind(to_i64(trade.sell_order_id)?)
+            .bind(direction_name(&trade.direction))
+            .bind(trade.timestamp.to_datetime())
+            .execute(&mut *conn)
+            .await?;
+        }
+        ExchangeRecord::DailyBar(bar) => {
+            sqlx::query::<DB>(
+                "INSERT INTO daily_bars (stock_code, trade_day, open, high, low, close, volume, amount)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                 ON CONFLICT (stock_code, trade_day) DO UPDATE SET
+                     open = EXCLUDED.open,
+                     high = EXCLUDED.high,
+                     low = EXCLUDED.low,
+                     close = EXCLUDED.close,
+                     volume = EXCLUDED.volume,
+                     amount = EXCLUDED.amount",
+            )
+            .bind(bar.stock_code.as_str())
+            .bind(bar.trade_day)
+            .bind(i64::from(bar.open.raw()))
+            .bind(i64::from(bar.high.raw()))
+            .bind(i64::from(bar.low.raw()))
+            .bind(i64::from(bar.close.raw()))
+            .bind(to_i64(bar.volume)?)
+            .bind(bar.amount.raw())
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::{log::TradeLog, types::Timestamp};
+    use std::sync::Mutex;
+
+    /** 前几次写入失败的存储 记录成功写入的成交编号 */
+    struct FlakyStorage {
+        failures: Mutex<usize>,
+        saved: Mutex<Vec<u64>>,
+    }
+
+    #[axum::async_trait]
+    impl Storage for FlakyStorage {
+        async fn migrate(&self) -> ExchangeResult<()> {
+            Ok(())
+        }
+
+        async fn save_records(&self, records: &[ExchangeRecord]) -> ExchangeResult<()> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(ExchangeError::DatabaseError("unavailable".to_string()));
+            }
+            self.saved
+                .lock()
+                .unwrap()
+                .extend(records.iter().map(|record| match record {
+                    ExchangeRecord::Trade(trade) => trade.id,
+                    _ => unreachable!(),
+                }));
+            Ok(())
+        }
+    }
+
+    fn trade(id: u64) -> ExchangeRecord {
+        ExchangeRecord::Trade(TradeLog {
+            id,
+            buyer_id: 1,
+            seller_id: 2,
+            stock_code: "000001".to_string(),
+            price: "10.00".parse().unwrap(),
+            quantity: 100,
+            buy_order_id: 1,
+            sell_order_id: 2,
+            timestamp: Timestamp::default(),
+            direction: PriceDirection::Flat,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_writer_retries_failed_batch() {
+        let storage = Arc::new(FlakyStorage {
+            failures: Mutex::new(2),
+            saved: Mutex::new(Vec::new()),
+        });
+        let policy = WritePolicy::default();
+        let sender = spawn_writer(storage.clone(), policy.clone());
+        sender.try_send(vec![trade(1), trade(2)]).unwrap();
+        // 等待重试期间到达的记录排在失败的记录之后
+        tokio::time::sleep(policy.retry_min / 2).await;
+        sender.try_send(vec![trade(3)]).unwrap();
+
+        for _ in 0..50 {
+            if storage.saved.lock().unwrap().len() == 3 {
+                break;
+            }
+            tokio::time::sleep(policy.retry_min).await;
+        }
+        assert_eq!(*storage.saved.lock().unwrap(), vec![1, 2, 3]);
+        assert_eq!(*storage.failures.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_writer_drops_batch_after_max_retries() {
+        let storage = Arc::new(FlakyStorage {
+            failures: Mutex::new(3),
+            saved: Mutex::new(Vec::new()),
+        });
+        let policy = WritePolicy {
+            capacity: 1,
+            max_retries: 2,
+            retry_min: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let sender = spawn_writer(storage.clone(), policy);
+        sender.try_send(vec![trade(1), trade(2)]).unwrap();
+        for _ in 0..50 {
+            if *storage.failures.lock().unwrap() == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // 重试用尽的记录被丢弃 之后的记录正常写入
+        sender.send(vec![trade(3)]).await.unwrap();
+        for _ in 0..50 {
+            if !storage.saved.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(*storage.saved.lock().unwrap(), vec![3]);
+    }
+}
@@ -0,0 +1,204 @@
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteJournalMode},
+    SqlitePool,
+};
+use std::str::FromStr;
+
+use exchange::{exchange_error::ExchangeResult, record::ExchangeRecord};
+
+use super::{database_error, save_records, Storage};
+
+/** SQLite 存储 数据保存在单个文件中 无需数据库服务 */
+#[derive(Clone)]
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    /** 连接数据库文件 文件及所在目录不存在时创建 */
+    pub async fn connect(database_url: &str) -> ExchangeResult<Self> {
+        let options = SqliteConnectOptions::from_str(database_url)
+            .map_err(database_error)?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal);
+        if let Some(dir) = options
+            .clone()
+            .get_filename()
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir).map_err(database_error)?;
+        }
+        let pool = SqlitePool::connect_with(options)
+            .await
+            .map_err(database_error)?;
+        Ok(Self::new(pool))
+    }
+
+    pub fn new(pool: SqlitePool) -> Self {
+        SqliteStorage { pool }
+    }
+}
+
+#[axum::async_trait]
+impl Storage for SqliteStorage {
+    async fn migrate(&self) -> ExchangeResult<()> {
+        sqlx::migrate!("./migrations/sqlite")
+            .run(&self.pool)
+            .await
+            .map_err(database_error)
+    }
+
+    async fn save_records(&self, records: &[ExchangeRecord]) -> ExchangeResult<()> {
+        save_records(&self.pool, records).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::connect;
+    use crate::testing::build_recording_exchange;
+    use exchange::{clock::SimulationClock, order::OrderType, types::Money};
+    use std::path::{Path, PathBuf};
+
+    /** 每个测试使用独立的数据库文件 */
+    fn database_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("stock-server-sqlite-{}", std::process::id()));
+        let path = dir.join(name);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    async fn open(path: &Path) -> SqliteStorage {
+        let storage = SqliteStorage::connect(&format!("sqlite://{}", path.display()))
+            .await
+            .unwrap();
+        storage.migrate().await.unwrap();
+        storage
+    }
+
+    async fn count(pool: &SqlitePool, sql: &str) -> i64 {
+        sqlx::query_scalar(sql).fetch_one(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_save_simulated_records() {
+        let path = database_path("simulated.db");
+        let storage = open(&path).await;
+
+        let mut exchange = build_recording_exchange();
+        let clock = SimulationClock::new();
+        for _ in 0..2500 {
+            exchange.tick(&clock).unwrap();
+            storage
+                .save_records(&exchange.drain_records())
+                .await
+                .unwrap();
+        }
+
+        let pool = &storage.pool;
+        let trades = exchange.log_manager.get_all_logs().len() as i64;
+        assert!(trades > 0);
+        assert_eq!(count(pool, "SELECT COUNT(*) FROM trades").await, trades);
+        assert_eq!(count(pool, "SELECT COUNT(*) FROM users").await, 2);
+        assert_eq!(
+            count(pool, "SELECT COUNT(*) FROM executions").await,
+            trades * 2
+        );
+        assert_eq!(
+            count(
+                pool,
+                "SELECT COUNT(*) FROM orders WHERE status IN ('new', 'partially_filled')"
+            )
+            .await,
+            exchange.order_manager.get_open_orders().len() as i64
+        );
+        let bars = exchange.daily_bars();
+        let close: i64 = sqlx::query_scalar(
+            "SELECT close FROM daily_bars WHERE stock_code = $1 AND trade_day = $2",
+        )
+        .bind(&bars[0].stock_code)
+        .bind(bars[0].trade_day)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        assert_eq!(close, bars[0].close.raw() as i64);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reopen_keeps_records() {
+        let path = database_path("reopen.db");
+        let storage = open(&path).await;
+
+        let mut exchange = build_recording_exchange();
+        let clock = SimulationClock::new();
+        while !exchange.config.is_allow_order() {
+            exchange.tick(&clock).unwrap();
+        }
+        let user_id = exchange
+            .add_user("trader", Money::from_yuan(100_000))
+            .unwrap();
+        let order_id = exchange
+            .submit_order(
+                user_id,
+                "000001".to_string(),
+                OrderType::Buy,
+                "9.00".parse().unwrap(),
+                100,
+            )
+            .unwrap();
+        exchange.cancel_order(order_id).unwrap();
+        storage
+            .save_records(&exchange.drain_records())
+            .await
+            .unwrap();
+        storage.pool.close().await;
+
+        // 按连接地址选择存储 重复执行迁移不影响已有数据
+        let url = format!("sqlite://{}", path.display());
+        connect(&url).await.unwrap();
+        let storage = open(&path).await;
+        let statuses: Vec<String> = sqlx::query_scalar(
+            "SELECT status FROM order_status_changes WHERE order_id = $1 ORDER BY id",
+        )
+        .bind(order_id as i64)
+        .fetch_all(&storage.pool)
+        .await
+        .unwrap();
+        assert_eq!(statuses, vec!["new", "cancelled"]);
+        assert_eq!(count(&storage.pool, "SELECT COUNT(*) FROM users").await, 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_out_of_range_quantity_rejected() {
+        let path = database_path("out_of_range.db");
+        let storage = open(&path).await;
+
+        // 超出有符号整数范围的数量不截断 整个事务回滚
+        let records = vec![
+            ExchangeRecord::Execution {
+                order_id: 1,
+                quantity: 100,
+                price: "10.00".parse().unwrap(),
+                fees: Default::default(),
+                timestamp: Default::default(),
+            },
+            ExchangeRecord::Execution {
+                order_id: 2,
+                quantity: u32::MAX,
+                price: "10.00".parse().unwrap(),
+                fees: Default::default(),
+                timestamp: Default::default(),
+            },
+        ];
+        assert!(storage.save_records(&records).await.is_err());
+        assert_eq!(
+            count(&storage.pool, "SELECT COUNT(*) FROM executions").await,
+            0
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
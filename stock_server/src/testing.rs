@@ -0,0 +1,57 @@
+//! 单元测试共用的交易所配置
+
+use exchange::{config_file::ConfigFile, exchange::Exchange};
+
+/**
+ * 测试用的配置文件 单只股票 只有 09:30 开始的连续竞价时段
+ *
+ * robots 为 true 时加入两个随机交易的机器人
+ */
+pub fn config_yaml(seed: u64, robots: bool) -> String {
+    let mut yaml = format!(
+        r#"
+exchange:
+  start_date: 2024-03-01
+  seed: {}
+  trading_periods:
+    - name: 连续竞价交易
+      start_tick: "09:30:00"
+      end_tick: "09:59:59"
+      period_type: ContinuousTrading
+      allow_order: true
+      allow_cancel: true
+      allow_matching: true
+      allow_record_price_history: true
+stocks:
+  - {{ code: "000001", name: 股票A, price: "10.00" }}
+"#,
+        seed
+    );
+    if robots {
+        yaml += r#"bots:
+  - name: robot1
+    balance: "1000000"
+    strategy: { type: trade_random, param: 5 }
+    holdings: { "000001": 10000 }
+  - name: robot2
+    balance: "1000000"
+    strategy: { type: simple_random }
+    holdings: { "000001": 10000 }
+"#;
+    }
+    yaml
+}
+
+pub fn build_exchange(seed: u64, robots: bool) -> Exchange {
+    ConfigFile::from_yaml(&config_yaml(seed, robots))
+        .unwrap()
+        .build_exchange()
+        .unwrap()
+}
+
+/** 数据库测试共用的交易所 保留待写入数据库的记录 */
+pub fn build_recording_exchange() -> Exchange {
+    let mut exchange = build_exchange(3, true);
+    exchange.enable_records();
+    exchange
+}